@@ -8,30 +8,44 @@
 //! transition to the Standby state by receiving the
 //! `StopRecording` event. The camera records the total
 //! number of seconds it has been in the Recording state.
+//!
+//! The camera reads the time from its clock, so this
+//! example uses a `ManualClock` instead of sleeping.
 
 #![allow(clippy::missing_trait_methods)]
 #![allow(missing_docs)]
 #![allow(clippy::print_stdout)]
 
 use crate::state_machines::camera::{
-    Camera, StartRecording, StopRecording,
+    Camera, CameraStateKind, StartRecording, StopRecording,
 };
 use core::time::Duration;
-use std::thread::sleep;
+use machine_factory_runtime::ManualClock;
 
 mod state_machines;
 
 #[tokio::main]
 async fn main() {
-    let mut camera = Camera::default();
+    let clock = ManualClock::new();
+    let mut camera =
+        Camera::default().with_clock(clock.clone().into());
+
+    let state = camera.subscribe();
 
     _ = camera.handle_event(StartRecording {}).await;
-    sleep(Duration::from_secs(2));
+    assert_eq!(
+        *state.borrow(),
+        CameraStateKind::Recording,
+        "Camera should be recording"
+    );
+
+    clock.advance(Duration::from_secs(2));
     _ = camera.handle_event(StopRecording {}).await;
 
-    assert!(
-        camera.context().total_recorded_seconds >= 2,
-        "Expected at least 2 seconds of recording time, got {}",
+    assert_eq!(
+        camera.context().total_recorded_seconds,
+        2,
+        "Expected 2 seconds of recording time, got {}",
         camera.context().total_recorded_seconds
     );
 
@@ -0,0 +1,91 @@
+//! This example demonstrates running the camera state
+//! machine (see the [`camera`] module) as an actor: the
+//! machine is spawned in its own task, and events are sent
+//! to it through cloneable handles.
+
+#![allow(clippy::missing_trait_methods)]
+#![allow(missing_docs)]
+#![allow(clippy::print_stdout)]
+
+use crate::state_machines::camera::{
+    Camera, CameraOutcome, CameraState, CameraStateKind,
+    StartRecording, StopRecording,
+};
+use core::time::Duration;
+use machine_factory_runtime::ManualClock;
+
+mod state_machines;
+
+#[tokio::main]
+async fn main() {
+    let clock = ManualClock::new();
+    let camera = Camera::default()
+        .with_clock(clock.clone().into())
+        .spawn();
+
+    // Handles can be cloned and shared between tasks.
+    let remote = camera.clone();
+    tokio::spawn(async move {
+        remote
+            .send(StartRecording {})
+            .await
+            .expect("camera is running");
+    })
+    .await
+    .expect("task failed");
+
+    assert!(
+        matches!(
+            camera.state_snapshot().await,
+            Ok(CameraState::Recording(_))
+        ),
+        "Camera should be recording"
+    );
+
+    clock.advance(Duration::from_secs(3));
+
+    // `ask` waits for the event to be handled, and returns
+    // the outcome.
+    let outcome = camera
+        .ask(StopRecording {})
+        .await
+        .expect("camera is running");
+    assert_eq!(
+        outcome,
+        CameraOutcome::Transitioned {
+            from: CameraStateKind::Recording,
+            to: CameraStateKind::Standby,
+        },
+        "Camera should stop recording"
+    );
+
+    let outcome = camera
+        .ask(StopRecording {})
+        .await
+        .expect("camera is running");
+    assert_eq!(
+        outcome,
+        CameraOutcome::Rejected {
+            state: CameraStateKind::Standby
+        },
+        "Camera isn't recording"
+    );
+
+    // Shutting down returns the machine, once all of the
+    // events already sent have been handled.
+    let camera = camera
+        .shutdown()
+        .await
+        .expect("camera is running");
+
+    assert_eq!(
+        camera.context().rejected_events,
+        1,
+        "One event should have been rejected"
+    );
+
+    println!(
+        "Total recorded seconds: {}",
+        camera.context().total_recorded_seconds
+    );
+}
@@ -0,0 +1,71 @@
+//! This example demonstrates serving the camera state
+//! machine (see the [`camera`] module) as a
+//! `tower::Service`: the camera is shared between requests,
+//! which go through `tower` middleware (here, a timeout and
+//! a concurrency limit). Events are answered with their
+//! outcome, and requests with their typed reply.
+
+#![allow(clippy::missing_trait_methods)]
+#![allow(missing_docs)]
+#![allow(clippy::print_stdout)]
+
+use crate::state_machines::camera::{
+    Camera, CameraOutcome, CameraStateKind, StartRecording,
+    StorageQuery,
+};
+use core::time::Duration;
+use machine_factory_runtime::ManualClock;
+use tower::{ServiceBuilder, ServiceExt};
+
+mod state_machines;
+
+#[tokio::main]
+async fn main() {
+    let clock = ManualClock::new();
+    let camera = Camera::default()
+        .with_clock(clock.clone().into())
+        .into_service();
+
+    let events = ServiceBuilder::new()
+        .timeout(Duration::from_secs(1))
+        .concurrency_limit(4)
+        .service(camera.clone());
+
+    // Every request gets its own clone of the stack, which
+    // shares the same camera.
+    let outcome = events
+        .clone()
+        .oneshot(StartRecording {})
+        .await
+        .expect("event should be handled in time");
+    assert_eq!(
+        outcome,
+        CameraOutcome::Transitioned {
+            from: CameraStateKind::Standby,
+            to: CameraStateKind::Recording,
+        },
+        "Camera should start recording"
+    );
+
+    clock.advance(Duration::from_secs(3));
+
+    let recorded_seconds = ServiceBuilder::new()
+        .timeout(Duration::from_secs(1))
+        .service(camera.requests())
+        .oneshot(StorageQuery {})
+        .await
+        .expect("request should be handled in time")
+        .expect("request should be replied to");
+    assert_eq!(
+        recorded_seconds, 3,
+        "The current recording should be counted"
+    );
+
+    assert_eq!(
+        camera.lock().await.state().kind(),
+        CameraStateKind::Recording,
+        "Camera should still be recording"
+    );
+
+    println!("Recorded seconds: {recorded_seconds}");
+}
@@ -4,7 +4,9 @@
 #![allow(clippy::print_stdout)]
 #![allow(clippy::use_debug)]
 
-use crate::state_machines::http_request_builder::HttpRequest;
+use crate::state_machines::http_request_builder::{
+    HttpRequest, NeedsMethod,
+};
 use reqwest::{header::HeaderMap, Method, Url};
 use tap::Tap;
 
@@ -12,6 +14,17 @@ mod state_machines;
 
 #[tokio::main]
 async fn main() {
+    // The machine's transitions can be listed at runtime.
+    for transition in HttpRequest::<NeedsMethod>::TRANSITIONS {
+        println!(
+            "{} --{}--> {} (line {})",
+            transition.from,
+            transition.event,
+            transition.target,
+            transition.line
+        );
+    }
+
     let request = HttpRequest::default();
     let response = request
         .set_method(Method::GET)
@@ -0,0 +1,71 @@
+//! This example demonstrates a `MachineRegistry` of orders
+//! (see the [`order`] module), keyed by order id: events
+//! are routed to orders by id, orders are created on their
+//! first event, and shipped or cancelled orders are
+//! evicted. The unfinished orders are saved to `SQLite`,
+//! and restored into a new registry.
+
+#![allow(missing_docs)]
+#![allow(clippy::print_stdout)]
+
+use crate::state_machines::order::{
+    Cancel, Order, OrderContext, OrderOutcome, OrderStateKind,
+    Pay, Pending, Ship,
+};
+use machine_factory_runtime::{MachineRegistry, SqlitePersister};
+use std::{env, fs, process};
+
+mod state_machines;
+
+fn registry() -> MachineRegistry<u64, Order> {
+    MachineRegistry::new(|_| {
+        Order::new(Pending, OrderContext::default())
+    })
+    .evicting(|order: &Order| {
+        matches!(
+            order.state().kind(),
+            OrderStateKind::Shipped | OrderStateKind::Cancelled
+        )
+    })
+}
+
+fn main() {
+    let mut orders = registry();
+
+    _ = orders.handle_event(1, Pay { amount_cents: 1200 });
+    _ = orders.handle_event(2, Pay { amount_cents: 300 });
+    _ = orders.handle_event(3, Cancel {});
+    assert_eq!(
+        orders.handle_event(1, Ship {}),
+        OrderOutcome::Transitioned {
+            from: OrderStateKind::Paid,
+            to: OrderStateKind::Shipped,
+        },
+        "Order 1 should ship"
+    );
+
+    // Orders 1 and 3 are finished, so they were evicted
+    assert_eq!(orders.len(), 1, "Only order 2 should be left");
+
+    let path = env::temp_dir().join(format!(
+        "machine-factory-registry-{}.sqlite",
+        process::id()
+    ));
+    let mut persister = SqlitePersister::open(&path, "order")
+        .expect("Database should open");
+    orders.save(&mut persister).expect("Orders should be saved");
+
+    let mut restored = registry();
+    let count = restored
+        .restore(&persister)
+        .expect("Orders should be restored");
+    assert_eq!(count, 1, "Order 2 should be restored");
+    assert_eq!(
+        restored.get(&2).map(|order| order.context().paid_cents),
+        Some(300),
+        "Order 2 should still be paid"
+    );
+
+    println!("Restored {} order(s)", restored.len());
+    fs::remove_file(path).expect("Database should be removed");
+}
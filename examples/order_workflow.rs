@@ -0,0 +1,234 @@
+//! This example demonstrates an event-sourced order (see
+//! the [`order`] module): every event the order accepts is
+//! appended to a `FileEventStore`, and the order is
+//! rebuilt from the stored events and its latest snapshot.
+//! Orders can also be saved to `SQLite` after every
+//! transition, and loaded back on startup. Handling an
+//! event is all-or-nothing: if a hook panics, the order is
+//! left as it was. Interceptors layered around the order
+//! audit its events, and veto them during maintenance.
+
+#![allow(missing_docs)]
+#![allow(clippy::print_stdout)]
+#![allow(clippy::use_debug)]
+#![allow(clippy::missing_trait_methods)]
+
+extern crate alloc;
+
+use alloc::sync::Arc;
+use crate::state_machines::order::{
+    Cancel, Order, OrderContext, OrderEvent, OrderOutcome,
+    OrderState, OrderStateKind, Pay, Pending, Ship,
+    MAX_ORDER_CENTS,
+};
+use machine_factory_runtime::{
+    EventStore, FileEventStore, Interceptor, SqlitePersister,
+    Verdict,
+};
+use std::{env, fs, panic, path::Path, process, sync::Mutex};
+
+mod state_machines;
+
+fn main() {
+    let directory = env::temp_dir().join(format!(
+        "machine-factory-order-{}",
+        process::id()
+    ));
+
+    // With nothing stored yet, the order starts from the
+    // given state and context.
+    let mut order = Order::new(Pending, OrderContext::default())
+        .rebuild(
+            FileEventStore::open(&directory)
+                .expect("Store should open"),
+        )
+        .expect("Store should load");
+
+    _ = order.handle_event(Pay { amount_cents: 1500 });
+    order
+        .save_snapshot()
+        .expect("Snapshot should be saved");
+    _ = order.handle_event(Ship {});
+
+    // Shipped orders can't be cancelled, so the event isn't
+    // stored.
+    assert_eq!(
+        order.handle_event_outcome(Cancel {}),
+        OrderOutcome::Rejected {
+            state: OrderStateKind::Shipped
+        },
+        "Shipped order should reject cancellation"
+    );
+    assert!(
+        order.take_event_store_error().is_none(),
+        "Every accepted event should have been stored"
+    );
+    drop(order);
+
+    let store = FileEventStore::open(&directory)
+        .expect("Store should open");
+    let stored =
+        EventStore::load(&store).expect("Store should load");
+    assert_eq!(
+        stored.events.len(),
+        1,
+        "Only the shipment should follow the snapshot"
+    );
+
+    let order = Order::new(Pending, OrderContext::default())
+        .rebuild(store)
+        .expect("Store should load");
+    assert_eq!(
+        order.state().kind(),
+        OrderStateKind::Shipped,
+        "Rebuilt order should be shipped"
+    );
+    assert_eq!(
+        order.context().paid_cents,
+        1500,
+        "Rebuilt order should be paid"
+    );
+
+    println!("Rebuilt order: {:?}", order.state());
+
+    persisted_orders(&directory);
+    rolled_back_payment();
+    intercepted_events();
+
+    fs::remove_dir_all(directory)
+        .expect("Store should be removed");
+}
+
+// Orders are saved after every transition, keyed by their
+// id, and can all be loaded back, e.g. after a restart.
+fn persisted_orders(directory: &Path) {
+    let persister = SqlitePersister::open(
+        directory.join("orders.sqlite"),
+        "order",
+    )
+    .expect("Database should open");
+
+    let mut paid = Order::new(Pending, OrderContext::default())
+        .with_persister(persister.clone(), "paid");
+    _ = paid.handle_event(Pay { amount_cents: 700 });
+
+    let mut cancelled =
+        Order::new(Pending, OrderContext::default())
+            .with_persister(persister.clone(), "cancelled");
+    _ = cancelled.handle_event(Cancel {});
+    drop((paid, cancelled));
+
+    let orders =
+        Order::load_all(&persister).expect("Orders should load");
+    let states = orders
+        .iter()
+        .map(|order| (order.id(), order.state().kind()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        states,
+        [
+            (Some("cancelled"), OrderStateKind::Cancelled),
+            (Some("paid"), OrderStateKind::Paid),
+        ],
+        "Both orders should be loaded in their last state"
+    );
+}
+
+// `Paid::on_enter` panics for payments over the limit, after
+// the payment was added to the context: the transactional
+// order restores its context and state before the panic
+// reaches the caller.
+fn rolled_back_payment() {
+    let mut order = Order::new(Pending, OrderContext::default());
+
+    // The panic is expected, so it isn't reported
+    let report = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        order.handle_event_outcome(Pay {
+            amount_cents: MAX_ORDER_CENTS.saturating_add(1),
+        })
+    }));
+    panic::set_hook(report);
+    assert!(result.is_err(), "Payment should have panicked");
+
+    assert_eq!(
+        (order.state().kind(), order.context().paid_cents),
+        (OrderStateKind::Pending, 0),
+        "Order should be left unpaid"
+    );
+}
+
+// Records every outcome, including vetoed events.
+#[derive(Clone, Default)]
+struct Audit {
+    outcomes: Arc<Mutex<Vec<OrderOutcome>>>,
+}
+
+impl Interceptor<Order> for Audit {
+    fn after(
+        &mut self,
+        _state: &OrderState,
+        _context: &OrderContext,
+        outcome: &OrderOutcome,
+    ) {
+        self.outcomes
+            .lock()
+            .expect("Audit should not be poisoned")
+            .push(*outcome);
+    }
+}
+
+// Only lets cancellations through while payments and
+// shipping are down.
+#[derive(Clone)]
+struct Maintenance;
+
+impl Interceptor<Order> for Maintenance {
+    fn before(
+        &mut self,
+        _state: &OrderState,
+        _context: &OrderContext,
+        event: &OrderEvent,
+    ) -> Verdict {
+        if matches!(event, OrderEvent::Cancel(_)) {
+            Verdict::Proceed
+        } else {
+            Verdict::Veto
+        }
+    }
+}
+
+// The audit is the outer layer, so it also sees the events
+// vetoed by the maintenance layer.
+fn intercepted_events() {
+    let audit = Audit::default();
+    let mut order = Order::new(Pending, OrderContext::default())
+        .layer(audit.clone())
+        .layer(Maintenance);
+
+    _ = order.handle_event(Pay { amount_cents: 900 });
+    _ = order.handle_event(Cancel {});
+
+    assert_eq!(
+        *audit
+            .outcomes
+            .lock()
+            .expect("Audit should not be poisoned"),
+        [
+            OrderOutcome::Vetoed {
+                state: OrderStateKind::Pending
+            },
+            OrderOutcome::Transitioned {
+                from: OrderStateKind::Pending,
+                to: OrderStateKind::Cancelled
+            },
+        ],
+        "Both events should be audited"
+    );
+    assert_eq!(
+        order.context().paid_cents,
+        0,
+        "Payment should have been vetoed"
+    );
+}
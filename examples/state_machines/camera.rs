@@ -3,14 +3,16 @@
 
 use async_trait::async_trait;
 use machine_factory::event_driven_state_machine;
+use machine_factory_runtime::{Clock, SharedClock};
 use std::time::Instant;
 
 #[derive(Debug)]
 pub struct Storage {
     pub total_recorded_seconds: u64,
+    pub rejected_events: u32,
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone)]
 pub struct Standby;
 
 // We are going to make `Camera` async, so we need to add
@@ -22,11 +24,15 @@ impl CameraStateTrait for Standby {
         _context: &Storage,
         event: &CameraEvent,
     ) -> bool {
-        matches!(event, CameraEvent::StartRecording(_))
+        matches!(
+            event,
+            CameraEvent::StartRecording(_)
+                | CameraEvent::StorageQuery(_)
+        )
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Recording {
     started_recording_at: Instant,
 }
@@ -39,11 +45,26 @@ impl Default for Recording {
 
 #[async_trait]
 impl CameraStateTrait for Recording {
-    async fn on_exit(&mut self, context: &mut Storage) {
+    async fn on_enter(
+        &mut self,
+        _context: &mut Storage,
+        clock: &SharedClock,
+    ) {
+        self.started_recording_at = clock.now();
+    }
+
+    async fn on_exit(
+        &mut self,
+        context: &mut Storage,
+        clock: &SharedClock,
+    ) {
         context.total_recorded_seconds =
             context.total_recorded_seconds.saturating_add(
-                self.started_recording_at
-                    .elapsed()
+                clock
+                    .now()
+                    .saturating_duration_since(
+                        self.started_recording_at,
+                    )
                     .as_secs(),
             );
     }
@@ -53,7 +74,13 @@ impl CameraStateTrait for Recording {
         _context: &Storage,
         event: &CameraEvent,
     ) -> bool {
-        matches!(event, CameraEvent::StopRecording(_))
+        // Querying the storage while recording adds the
+        // time recorded so far, and restarts the recording
+        matches!(
+            event,
+            CameraEvent::StopRecording(_)
+                | CameraEvent::StorageQuery(_)
+        )
     }
 }
 
@@ -65,12 +92,29 @@ impl CameraEventTrait for StartRecording {}
 pub struct StopRecording;
 impl CameraEventTrait for StopRecording {}
 
+// A request, replied to with the total recorded seconds
+#[derive(Debug, Clone)]
+pub struct StorageQuery;
+impl CameraEventTrait for StorageQuery {}
+
 event_driven_state_machine!(
     #[derive(Debug)]
     pub async Camera {
         context: Storage,
-        state_enum: #[derive(Debug)] CameraState,
+        state_enum: #[derive(Debug, Clone)] CameraState,
+        // The clock is passed to transition blocks, and to any hook that asks for it
+        // with an extra `&SharedClock` argument, so tests can control time.
+        clock: SharedClock,
         state_trait: trait CameraStateTrait {
+            async fn on_enter(&mut self, _context: &mut Storage, _clock: &SharedClock) {}
+
+            async fn on_exit(&mut self, _context: &mut Storage, _clock: &SharedClock) {}
+
+            // Called instead of the other hooks when `should_exit` rejects an event
+            async fn on_rejected(&mut self, context: &mut Storage, _event: &CameraEvent) {
+                context.rejected_events = context.rejected_events.saturating_add(1);
+            }
+
             // The camera is defaulting to `async`, so we override the default
             // `should_exit` method to not be async, as it's not needed.
             fn should_exit(
@@ -81,6 +125,15 @@ event_driven_state_machine!(
         },
         event_enum: CameraEvent,
         event_trait: trait CameraEventTrait: Send {},
+        // Generates `Camera::spawn`, which runs the camera in its own task
+        // and returns a cloneable `CameraHandle` to send it events.
+        actor: true,
+        // Subscribers can watch the camera's state with `Camera::subscribe`.
+        observable: true,
+        // Generates `Camera::into_service`, which shares the camera between `tower`
+        // requests.
+        service: true,
+        tracing: true,
         states: [
             Standby {
                 StartRecording -> Recording,
@@ -88,8 +141,15 @@ event_driven_state_machine!(
             Recording {
                 StopRecording -> Standby,
             },
-            _ { state }
-        ]
+            _ {
+                if let CameraEvent::StorageQuery(_) = event {
+                    *reply = Some(CameraReply::StorageQuery(context.total_recorded_seconds));
+                }
+
+                state
+            }
+        ],
+        events: [StorageQuery -> u64],
     }
 );
 
@@ -97,7 +157,10 @@ impl Default for Camera {
     fn default() -> Self {
         Self::new(
             Standby {},
-            Storage { total_recorded_seconds: 0 },
+            Storage {
+                total_recorded_seconds: 0,
+                rejected_events: 0,
+            },
         )
     }
 }
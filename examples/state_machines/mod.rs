@@ -1,3 +1,4 @@
 pub mod camera;
 pub mod http_request_builder;
+pub mod order;
 pub mod traffic_light;
@@ -0,0 +1,139 @@
+#![allow(clippy::missing_trait_methods)]
+#![allow(dead_code)] // there are false positives
+
+use machine_factory::event_driven_state_machine;
+use machine_factory_runtime::{FileEventStore, SqlitePersister};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct OrderContext {
+    pub paid_cents: u64,
+    pub refunded_cents: u64,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct Pending;
+
+impl OrderStateTrait for Pending {
+    // Orders are paid before they are shipped
+    fn should_exit(
+        &self,
+        _context: &OrderContext,
+        event: &OrderEvent,
+    ) -> bool {
+        !matches!(event, OrderEvent::Ship(_))
+    }
+}
+
+pub const MAX_ORDER_CENTS: u64 = 1_000_000;
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct Paid;
+
+impl OrderStateTrait for Paid {
+    // Panics after the payment was added to the context,
+    // which is then rolled back, since the order is
+    // transactional
+    fn on_enter(&mut self, context: &mut OrderContext) {
+        assert!(
+            context.paid_cents <= MAX_ORDER_CENTS,
+            "order exceeds the limit"
+        );
+    }
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct Shipped;
+
+impl OrderStateTrait for Shipped {
+    fn should_exit(
+        &self,
+        _context: &OrderContext,
+        _event: &OrderEvent,
+    ) -> bool {
+        false
+    }
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct Cancelled;
+
+impl OrderStateTrait for Cancelled {
+    fn should_exit(
+        &self,
+        _context: &OrderContext,
+        _event: &OrderEvent,
+    ) -> bool {
+        false
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pay {
+    pub amount_cents: u64,
+}
+impl OrderEventTrait for Pay {}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ship;
+impl OrderEventTrait for Ship {}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cancel;
+impl OrderEventTrait for Cancel {}
+
+event_driven_state_machine!(
+    pub Order {
+        context: OrderContext,
+        state_enum: #[derive(Debug, Clone, Serialize, Deserialize)] OrderState,
+        state_trait: pub trait OrderStateTrait {
+            fn on_enter(&mut self, _context: &mut OrderContext) {}
+
+            // Rejected events aren't appended to the event store
+            fn should_exit(&self, _context: &OrderContext, _event: &OrderEvent) -> bool {
+                true
+            }
+        },
+        event_enum: #[derive(Debug, Clone, Serialize, Deserialize)] OrderEvent,
+        event_trait: trait OrderEventTrait {},
+        // Accepted events are appended to the store, and the order is rebuilt from it
+        // with `Order::rebuild`
+        event_store: FileEventStore<OrderEvent, OrderState, OrderContext>,
+        // Orders given a persister with `with_persister` are saved after every transition,
+        // which is rolled back if the save fails (hence `transactional`),
+        // and are loaded back with `Order::load` or `Order::load_all`
+        persister: SqlitePersister<OrderState, OrderContext>,
+        // If a hook panics, the state and context are restored to what they were
+        // before the event
+        transactional: true,
+        // Interceptors added with `layer` can veto events before `should_exit`, and
+        // see every outcome
+        interceptors: true,
+        // Orders are kept in a `MachineRegistry` in the order_registry example
+        registry: true,
+        states: [
+            Pending {
+                Pay {
+                    context.paid_cents = context.paid_cents.saturating_add(event.amount_cents);
+                    Paid {}
+                },
+                Cancel -> Cancelled,
+            },
+            Paid {
+                Ship -> Shipped,
+                Cancel {
+                    context.refunded_cents = context.paid_cents;
+                    Cancelled {}
+                },
+            },
+            // Final states: their `should_exit` rejects every event
+            Shipped {
+                Cancel { state },
+            },
+            Cancelled {
+                Cancel { state },
+            },
+            _ { state }
+        ]
+    }
+);
@@ -4,11 +4,12 @@
 #![allow(dead_code)] // there are false positives
 
 use machine_factory::event_driven_state_machine;
+use machine_factory_runtime::{Clock, SharedClock};
 use std::time::Instant;
 
 // First, we define the context that the traffic light will
 // use
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct TrafficLightContext {
     #[allow(dead_code)] // seems like a false positive
     last_change: Option<Instant>,
@@ -28,12 +29,33 @@ pub struct EmergencyEvent {
     pub requested_color: TrafficLightColor,
 }
 
-impl TrafficLightEvent for EmergencyEvent {}
+impl TrafficLightEvent for EmergencyEvent {
+    // Event hooks can also receive the state: the current
+    // one in `pre_transition`, and the next one in
+    // `post_transition`.
+    fn pre_transition(
+        &mut self,
+        _context: &mut TrafficLightContext,
+        from: &TrafficLightMachineState,
+    ) {
+        println!(
+            "Emergency while {}: requested {:?}",
+            from.name(),
+            self.requested_color
+        );
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct ChaosEvent;
 impl TrafficLightEvent for ChaosEvent {}
 
+// A request: handling it produces a reply for the caller
+// of `handle_request`.
+#[derive(Debug, Clone)]
+pub struct ColorQuery;
+impl TrafficLightEvent for ColorQuery {}
+
 #[derive(Default, Debug, Clone)]
 pub struct Red;
 
@@ -44,9 +66,15 @@ impl TrafficLightState for Red {
     fn on_enter(
         &mut self,
         context: &mut TrafficLightContext,
+        clock: &SharedClock,
+        event: &TrafficLightMachineEvent,
     ) {
-        context.last_change = Some(Instant::now());
-        println!("{:?}: Changed to Red", Instant::now());
+        context.last_change = Some(clock.now());
+        println!(
+            "{:?}: Changed to Red on {}",
+            clock.now(),
+            event.name()
+        );
     }
 
     // This is required, since we don't provide a default
@@ -78,11 +106,15 @@ impl TrafficLightState for Green {
 event_driven_state_machine!(
     pub TrafficLight {
         context: TrafficLightContext,
-        event_trait:  trait TrafficLightEvent {},
+        clock: SharedClock,
+        event_trait:  trait TrafficLightEvent {
+            fn pre_transition(&mut self, _context: &mut TrafficLightContext, _from: &TrafficLightMachineState) {}
+        },
         event_enum: #[derive(Debug, Clone)] TrafficLightMachineEvent,
         state_trait: pub trait TrafficLightState {
-            fn on_enter(&mut self, context: &mut TrafficLightContext) {
-                context.last_change = Some(Instant::now());
+            // Hooks can optionally receive the clock and, for state hooks, the event
+            fn on_enter(&mut self, context: &mut TrafficLightContext, clock: &SharedClock, _event: &TrafficLightMachineEvent) {
+                context.last_change = Some(clock.now());
             }
 
             fn color(&self) -> TrafficLightColor;
@@ -92,39 +124,44 @@ event_driven_state_machine!(
             Red {
                 // From Red to Green when a TimeoutEvent occurs
                 TimeoutEvent {
-                    println!("{:?}: Changing to Green", Instant::now());
+                    println!("{:?}: Changing to Green", clock.now());
                     Green {} // Any state can be returned here
                 },
                 EmergencyEvent {
                     match event.requested_color {
                         TrafficLightColor::Red => {
-                            println!("{:?}: Changing to Red", Instant::now());
+                            println!("{:?}: Changing to Red", clock.now());
                             TrafficLightMachineState::from(state)
                             // Note: even though we don't change the state, the lifecycle methods
                             // (i.e., on_exit, pre_transition, post_transition, on_enter) will still be called
                         }
                         TrafficLightColor::Yellow => {
-                            println!("{:?}: Changing to Yellow", Instant::now());
+                            println!("{:?}: Changing to Yellow", clock.now());
                             Yellow {}.into()
                         }
                         TrafficLightColor::Green => {
-                            println!("{:?}: Changing to Green", Instant::now());
+                            println!("{:?}: Changing to Green", clock.now());
                             Green {}.into()
                         }
                     }
                 },
                 ChaosEvent {
-                    println!("{:?}: ChaosEvent", Instant::now());
+                    println!("{:?}: ChaosEvent", clock.now());
 
                     // We can't return the state object because they may be different types.
                     // Instead, we return the state enum variant.
-                    if context.last_change.as_ref().is_some_and(|c| c.elapsed().as_secs() % 2 == 0) {
-                        println!("{:?}: Changing to Green", Instant::now());
+                    if context.last_change.is_some_and(|c| clock.now().saturating_duration_since(c).as_secs().is_multiple_of(2)) {
+                        println!("{:?}: Changing to Green", clock.now());
                         TrafficLightMachineState::Green(Green {})
                     } else {
-                        println!("{:?}: Changing to Yellow", Instant::now());
+                        println!("{:?}: Changing to Yellow", clock.now());
                         Yellow {}.into()
                     }
+                },
+                // The block of a request can set `reply` to answer it
+                ColorQuery {
+                    *reply = Some(TrafficLightColor::Red);
+                    state
                 }
             },
             Yellow {
@@ -148,16 +185,20 @@ event_driven_state_machine!(
             // We can also define an unhandled_event block, which would be called when an event is not handled by the state
             _ {
                 if let TrafficLightMachineEvent::EmergencyEvent(EmergencyEvent { requested_color }) = event {
-                    println!("{:?}: Emergency event not handled. Requested color: {:?}", Instant::now(), requested_color);
+                    println!("{:?}: Emergency event not handled. Requested color: {:?}", clock.now(), requested_color);
                     TrafficLightMachineState::from(&*requested_color)
+                } else if let TrafficLightMachineEvent::ColorQuery(_) = event {
+                    // The unhandled_event block can answer requests too, with the reply enum
+                    *reply = Some(TrafficLightReply::ColorQuery(state.color()));
+                    state
                 } else {
-                    println!("{:?}: Unhandled event: {:?}", Instant::now(), event);
+                    println!("{:?}: Unhandled event: {:?}", clock.now(), event);
                     state
                 }
             },
         ],
-        // If we had events that weren't in the state blocks...
-        // events: [OtherEvent],
+        // Events that weren't in the state blocks, and requests along with the type of their reply
+        events: [ColorQuery -> TrafficLightColor],
     }
 );
 
@@ -3,10 +3,14 @@
 #![allow(clippy::use_debug)]
 
 use crate::state_machines::traffic_light::{
-    ChaosEvent, EmergencyEvent, TimeoutEvent, TrafficLight,
-    TrafficLightColor, TrafficLightMachineEvent,
-    TrafficLightState,
+    ChaosEvent, ColorQuery, EmergencyEvent, Red,
+    TimeoutEvent, TrafficLight, TrafficLightColor,
+    TrafficLightContext, TrafficLightHandling,
+    TrafficLightMachineEvent, TrafficLightMachineEventKind,
+    TrafficLightMachineStateKind, TrafficLightOutcome,
+    TrafficLightState, TrafficLightTransitionTarget,
 };
+use machine_factory_runtime::SharedClock;
 use tap::Tap;
 
 mod state_machines;
@@ -90,6 +94,126 @@ fn main() {
             );
         });
 
+    query_color(&mut traffic_light);
+
+    outcomes();
+    step_over_values();
+    kinds();
+    handled_events();
+
     let state = traffic_light.into_state();
     println!("Final state: {:?}", state.color());
 }
+
+/// `handle_event_outcome` returns the outcome of handling
+/// the event, instead of the machine.
+fn outcomes() {
+    let mut traffic_light = TrafficLight::default();
+
+    assert_eq!(
+        traffic_light.handle_event_outcome(TimeoutEvent {}),
+        TrafficLightOutcome::Transitioned {
+            from: TrafficLightMachineStateKind::Red,
+            to: TrafficLightMachineStateKind::Green,
+        },
+        "Event should cause a transition"
+    );
+}
+
+fn step_over_values() {
+    let (state, _, outcome) = TrafficLight::step(
+        Red.into(),
+        TrafficLightContext::default(),
+        TimeoutEvent {},
+        &SharedClock::default(),
+    );
+    assert_eq!(
+        (state.color(), outcome),
+        (
+            TrafficLightColor::Green,
+            TrafficLightOutcome::Transitioned {
+                from: TrafficLightMachineStateKind::Red,
+                to: TrafficLightMachineStateKind::Green,
+            }
+        ),
+        "Step should transition to green"
+    );
+}
+
+// Kinds name the states and events, and can be parsed back
+// from their names.
+fn kinds() {
+    let traffic_light = TrafficLight::default();
+    assert_eq!(
+        traffic_light.state().name(),
+        "Red",
+        "State should be named Red"
+    );
+    assert_eq!(
+        "TimeoutEvent".parse(),
+        Ok(TrafficLightMachineEventKind::TimeoutEvent),
+        "Event kind should be parsed from its name"
+    );
+
+    // The machine's states and transitions can be listed at
+    // runtime.
+    assert_eq!(
+        TrafficLight::STATES,
+        ["Red", "Yellow", "Green"],
+        "States should be listed in order"
+    );
+    assert!(
+        TrafficLight::TRANSITIONS.iter().any(|transition| {
+            transition.from == "Yellow"
+                && transition.event == "TimeoutEvent"
+                && transition.target
+                    == TrafficLightTransitionTarget::State("Red")
+        }),
+        "Yellow should turn red on timeout"
+    );
+}
+
+// The events handled by the current state can be queried
+// without handling them.
+fn handled_events() {
+    let mut traffic_light = TrafficLight::default();
+    _ = traffic_light.handle_event(TimeoutEvent {});
+
+    assert_eq!(
+        traffic_light.allowed_events(),
+        [
+            TrafficLightMachineEventKind::TimeoutEvent,
+            TrafficLightMachineEventKind::ChaosEvent
+        ],
+        "Green should only handle timeouts and chaos"
+    );
+    assert_eq!(
+        traffic_light.can_handle(&TimeoutEvent {}.into()),
+        TrafficLightHandling::Transition,
+        "Green should handle timeouts"
+    );
+    assert_eq!(
+        traffic_light.can_handle_kind(
+            TrafficLightMachineEventKind::EmergencyEvent
+        ),
+        TrafficLightHandling::Fallback,
+        "Emergencies should fall through to `_` when green"
+    );
+}
+
+// Requests are handled like any other event, but return the
+// reply set by the transition block.
+fn query_color(traffic_light: &mut TrafficLight) {
+    assert_eq!(
+        traffic_light.handle_request(ColorQuery {}),
+        Some(TrafficLightColor::Yellow),
+        "Query should reply with yellow"
+    );
+
+    _ = traffic_light.handle_event(TimeoutEvent {});
+    assert_eq!(
+        traffic_light.handle_request(ColorQuery {}),
+        Some(TrafficLightColor::Red),
+        "Query should reply with red"
+    );
+}
@@ -0,0 +1,124 @@
+//! This example demonstrates undoing and redoing the
+//! transitions of a traffic light with `history`,
+//! optionally running the state hooks as if the light
+//! changed.
+#![allow(clippy::missing_trait_methods)]
+#![allow(missing_docs)]
+#![allow(clippy::print_stdout)]
+
+use machine_factory::event_driven_state_machine;
+use machine_factory_runtime::UndoPolicy;
+
+#[derive(Default, Clone)]
+struct Context {
+    timeouts: u32,
+}
+
+#[derive(Debug, Default, Clone)]
+struct Red;
+
+impl TrafficLightState for Red {
+    fn on_enter(&mut self, _context: &mut Context) {
+        println!("Changed to Red");
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+struct Green;
+
+impl TrafficLightState for Green {
+    fn on_enter(&mut self, _context: &mut Context) {
+        println!("Changed to Green");
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+struct Yellow;
+
+impl TrafficLightState for Yellow {
+    fn on_enter(&mut self, _context: &mut Context) {
+        println!("Changed to Yellow");
+    }
+}
+
+#[derive(Debug, Clone)]
+struct TimeoutEvent;
+
+impl TrafficLightEvent for TimeoutEvent {
+    fn pre_transition(&mut self, context: &mut Context) {
+        context.timeouts =
+            context.timeouts.saturating_add(1);
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ChaosEvent;
+impl TrafficLightEvent for ChaosEvent {}
+
+event_driven_state_machine!(
+    TrafficLight {
+        context: Context,
+        // The last 8 transitions can be undone with `undo`, and redone with `redo`
+        history: 8,
+        event_trait: trait TrafficLightEvent {},
+        event_enum: #[derive(Debug, Clone)] TrafficLightMachineEvent,
+        state_trait: trait TrafficLightState {},
+        state_enum: #[derive(Debug, Clone)] TrafficLightMachineState,
+        states: [
+            Red {
+                TimeoutEvent -> Green,
+            },
+            Green {
+                TimeoutEvent -> Yellow,
+                ChaosEvent -> Red,
+            },
+            Yellow {
+                TimeoutEvent -> Red,
+            },
+            _ {
+                state
+            },
+        ],
+    }
+);
+
+fn main() {
+    let mut traffic_light =
+        TrafficLight::new(Red, Context::default())
+            .with_undo_policy(UndoPolicy::RunHooks);
+    _ = traffic_light
+        .handle_event(TimeoutEvent)
+        .handle_event(TimeoutEvent);
+
+    assert!(
+        traffic_light.undo(),
+        "Yellow should be undone"
+    );
+    assert!(traffic_light.undo(), "Green should be undone");
+    assert!(
+        !traffic_light.undo(),
+        "Nothing should be left"
+    );
+    assert_eq!(
+        (
+            traffic_light.state().kind(),
+            traffic_light.context().timeouts
+        ),
+        (TrafficLightMachineStateKind::Red, 0),
+        "Undoing should restore the state and context"
+    );
+
+    assert!(traffic_light.redo(), "Green should be redone");
+    assert_eq!(
+        traffic_light.state().kind(),
+        TrafficLightMachineStateKind::Green,
+        "Redoing should restore the state"
+    );
+
+    // A new transition forgets the undone ones
+    _ = traffic_light.handle_event(ChaosEvent);
+    assert!(
+        !traffic_light.redo(),
+        "Yellow should be forgotten"
+    );
+}
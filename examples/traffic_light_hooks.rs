@@ -0,0 +1,86 @@
+//! This example demonstrates machine-level hooks with
+//! `hooks`: the traffic light's audit sees both ends of
+//! every transition, whatever the states.
+#![allow(clippy::missing_trait_methods)]
+#![allow(missing_docs)]
+#![allow(clippy::print_stdout)]
+
+use machine_factory::event_driven_state_machine;
+
+#[derive(Default)]
+struct Context {
+    transitions: u32,
+}
+
+#[derive(Debug, Default, Clone)]
+struct Red;
+impl TrafficLightState for Red {}
+
+#[derive(Debug, Default, Clone)]
+struct Green;
+impl TrafficLightState for Green {}
+
+#[derive(Debug, Default, Clone)]
+struct Yellow;
+impl TrafficLightState for Yellow {}
+
+#[derive(Debug, Clone)]
+struct TimeoutEvent;
+impl TrafficLightEvent for TimeoutEvent {}
+
+// Machine-level hooks see both ends of every transition
+struct TrafficLightAudit;
+
+impl TrafficLightHooks for TrafficLightAudit {
+    fn on_transition(
+        from: &TrafficLightMachineStateKind,
+        to: &TrafficLightMachineStateKind,
+        event: &TrafficLightMachineEvent,
+        context: &mut Context,
+    ) {
+        println!(
+            "Audit: {from} -> {to} on {}",
+            event.name()
+        );
+        context.transitions =
+            context.transitions.saturating_add(1);
+    }
+}
+
+event_driven_state_machine!(
+    TrafficLight {
+        context: Context,
+        hooks: TrafficLightAudit,
+        event_trait: trait TrafficLightEvent {},
+        event_enum: #[derive(Debug, Clone)] TrafficLightMachineEvent,
+        state_trait: trait TrafficLightState {},
+        state_enum: #[derive(Debug, Clone)] TrafficLightMachineState,
+        states: [
+            Red {
+                TimeoutEvent -> Green,
+            },
+            Green {
+                TimeoutEvent -> Yellow,
+            },
+            Yellow {
+                TimeoutEvent -> Red,
+            },
+        ],
+    }
+);
+
+fn main() {
+    let mut traffic_light =
+        TrafficLight::new(Red, Context::default());
+
+    _ = traffic_light
+        .handle_event(TimeoutEvent)
+        .handle_event(TimeoutEvent)
+        .handle_event(TimeoutEvent);
+
+    assert_eq!(
+        traffic_light.context().transitions,
+        3,
+        "Every transition should have been audited"
+    );
+}
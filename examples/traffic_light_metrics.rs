@@ -0,0 +1,106 @@
+//! This example demonstrates timing a traffic light with
+//! `metrics: true`: the machine counts its transitions and
+//! times its states, and exports both through the
+//! `metrics` facade.
+#![allow(clippy::missing_trait_methods)]
+#![allow(missing_docs)]
+
+use core::time::Duration;
+use machine_factory::event_driven_state_machine;
+use machine_factory_runtime::{DwellTime, ManualClock};
+use metrics_util::debugging::{
+    DebugValue, DebuggingRecorder,
+};
+
+#[derive(Default)]
+struct Context;
+
+#[derive(Debug, Default, Clone)]
+struct Red;
+impl TrafficLightState for Red {}
+
+#[derive(Debug, Default, Clone)]
+struct Green;
+impl TrafficLightState for Green {}
+
+#[derive(Debug, Default, Clone)]
+struct Yellow;
+impl TrafficLightState for Yellow {}
+
+#[derive(Debug, Clone)]
+struct TimeoutEvent;
+impl TrafficLightEvent for TimeoutEvent {}
+
+event_driven_state_machine!(
+    TrafficLight {
+        context: Context,
+        // States are timed with the machine's clock
+        clock: ManualClock,
+        metrics: true,
+        event_trait: trait TrafficLightEvent {},
+        event_enum: #[derive(Debug, Clone)] TrafficLightMachineEvent,
+        state_trait: trait TrafficLightState {},
+        state_enum: #[derive(Debug, Clone)] TrafficLightMachineState,
+        states: [
+            Red {
+                TimeoutEvent -> Green,
+            },
+            Green {
+                TimeoutEvent -> Yellow,
+            },
+            Yellow {
+                TimeoutEvent -> Red,
+            },
+        ],
+    }
+);
+
+fn main() {
+    let clock = ManualClock::new();
+    let mut traffic_light = TrafficLight::new(Red, Context)
+        .with_clock(clock.clone());
+
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+
+    metrics::with_local_recorder(&recorder, || {
+        clock.advance(Duration::from_secs(30));
+        _ = traffic_light.handle_event(TimeoutEvent);
+        clock.advance(Duration::from_secs(20));
+        _ = traffic_light.handle_event(TimeoutEvent);
+    });
+
+    let metrics = traffic_light.metrics();
+    assert_eq!(
+        metrics.count(
+            TrafficLightMachineStateKind::Red,
+            TrafficLightMachineEventKind::TimeoutEvent,
+            TrafficLightMachineStateKind::Green,
+        ),
+        1,
+        "Red should have turned green once"
+    );
+    assert_eq!(
+        metrics
+            .dwell_time(TrafficLightMachineStateKind::Green)
+            .map(DwellTime::total),
+        Some(Duration::from_secs(20)),
+        "Green should have lasted 20 seconds"
+    );
+
+    let exported = snapshotter
+        .snapshot()
+        .into_vec()
+        .into_iter()
+        .filter(|(key, ..)| {
+            key.key().name()
+                == "state_machine_transitions_total"
+        })
+        .map(|(.., value)| value)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        exported,
+        [DebugValue::Counter(1), DebugValue::Counter(1)],
+        "Both transitions should have been exported"
+    );
+}
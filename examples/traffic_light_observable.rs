@@ -0,0 +1,75 @@
+//! This example demonstrates listening to the transitions
+//! of a traffic light with `observable: true`.
+#![allow(clippy::missing_trait_methods)]
+#![allow(missing_docs)]
+#![allow(clippy::print_stdout)]
+#![allow(clippy::use_debug)]
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use machine_factory::event_driven_state_machine;
+
+static TRANSITIONS: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Default)]
+struct Context;
+
+#[derive(Debug, Default, Clone)]
+struct Red;
+impl TrafficLightState for Red {}
+
+#[derive(Debug, Default, Clone)]
+struct Green;
+impl TrafficLightState for Green {}
+
+#[derive(Debug, Default, Clone)]
+struct Yellow;
+impl TrafficLightState for Yellow {}
+
+#[derive(Debug, Clone)]
+struct TimeoutEvent;
+impl TrafficLightEvent for TimeoutEvent {}
+
+event_driven_state_machine!(
+    TrafficLight {
+        context: Context,
+        // Listeners can be added with `add_listener` to be notified of every transition
+        observable: true,
+        event_trait: trait TrafficLightEvent {},
+        event_enum: #[derive(Debug, Clone)] TrafficLightMachineEvent,
+        state_trait: trait TrafficLightState {},
+        state_enum: #[derive(Debug, Clone)] TrafficLightMachineState,
+        states: [
+            Red {
+                TimeoutEvent -> Green,
+            },
+            Green {
+                TimeoutEvent -> Yellow,
+            },
+            Yellow {
+                TimeoutEvent -> Red,
+            },
+        ],
+    }
+);
+
+fn main() {
+    let mut traffic_light = TrafficLight::new(Red, Context);
+
+    traffic_light.add_listener(|transition| {
+        println!(
+            "{:?} -> {:?}",
+            transition.from, transition.to
+        );
+        _ = TRANSITIONS.fetch_add(1, Ordering::Relaxed);
+    });
+
+    _ = traffic_light
+        .handle_event(TimeoutEvent)
+        .handle_event(TimeoutEvent);
+
+    assert_eq!(
+        TRANSITIONS.load(Ordering::Relaxed),
+        2,
+        "The listener should have seen every transition"
+    );
+}
@@ -0,0 +1,93 @@
+//! This example demonstrates recording the events handled
+//! by a traffic light with `recording: true`: the recorded
+//! trace can be serialized, and replayed from the same
+//! initial state to reproduce the machine's state.
+#![allow(clippy::missing_trait_methods)]
+#![allow(missing_docs)]
+
+use machine_factory::event_driven_state_machine;
+use machine_factory_runtime::Trace;
+use serde::{Deserialize, Serialize};
+
+#[derive(Default)]
+struct Context;
+
+#[derive(Debug, Default, Clone)]
+struct Red;
+impl TrafficLightState for Red {}
+
+#[derive(Debug, Default, Clone)]
+struct Green;
+impl TrafficLightState for Green {}
+
+#[derive(Debug, Default, Clone)]
+struct Yellow;
+impl TrafficLightState for Yellow {}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TimeoutEvent;
+impl TrafficLightEvent for TimeoutEvent {}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct EmergencyEvent;
+impl TrafficLightEvent for EmergencyEvent {}
+
+event_driven_state_machine!(
+    TrafficLight {
+        context: Context,
+        recording: true,
+        event_trait: trait TrafficLightEvent {},
+        event_enum: #[derive(Debug, Clone, Serialize, Deserialize)] TrafficLightMachineEvent,
+        state_trait: trait TrafficLightState {},
+        state_enum: #[derive(Debug, Clone)] TrafficLightMachineState,
+        states: [
+            Red {
+                TimeoutEvent -> Green,
+                EmergencyEvent -> Red,
+            },
+            Green {
+                TimeoutEvent -> Yellow,
+                EmergencyEvent -> Red,
+            },
+            Yellow {
+                TimeoutEvent -> Red,
+                EmergencyEvent -> Red,
+            },
+        ],
+    }
+);
+
+fn main() {
+    let mut traffic_light = TrafficLight::new(Red, Context);
+    traffic_light.start_recording();
+    _ = traffic_light
+        .handle_event(TimeoutEvent)
+        .handle_event(TimeoutEvent)
+        .handle_event(EmergencyEvent);
+
+    let trace = traffic_light
+        .stop_recording()
+        .expect("Traffic light should be recording");
+    let json = serde_json::to_string(&trace)
+        .expect("Trace should serialize");
+    let trace: Trace<_, _> = serde_json::from_str(&json)
+        .expect("Trace should deserialize");
+
+    let replayed = TrafficLight::new(Red, Context)
+        .replay(&trace)
+        .expect("Replay should not diverge");
+    assert_eq!(
+        replayed.state().kind(),
+        traffic_light.state().kind(),
+        "Replay should end in the same state"
+    );
+
+    let divergence = TrafficLight::new(Green, Context)
+        .replay(&trace)
+        .err()
+        .expect("Replay from green should diverge");
+    assert_eq!(
+        divergence.index, 0,
+        "The first event should diverge"
+    );
+}
@@ -0,0 +1,114 @@
+//! This example demonstrates sharing a traffic light
+//! between threads with `shared: true`: one thread handles
+//! timeouts, while another watches the state changes, and
+//! the current color is read without waiting for events to
+//! be handled.
+#![allow(clippy::missing_trait_methods)]
+#![allow(missing_docs)]
+#![allow(clippy::print_stdout)]
+#![allow(clippy::use_debug)]
+
+use machine_factory::event_driven_state_machine;
+use std::thread;
+
+#[derive(Default)]
+struct Context {
+    timeouts: u32,
+}
+
+#[derive(Debug, Default, Clone)]
+struct Red;
+impl TrafficLightState for Red {}
+
+#[derive(Debug, Default, Clone)]
+struct Green;
+impl TrafficLightState for Green {}
+
+#[derive(Debug, Default, Clone)]
+struct Yellow;
+impl TrafficLightState for Yellow {}
+
+#[derive(Debug, Clone)]
+struct TimeoutEvent;
+
+impl TrafficLightEvent for TimeoutEvent {
+    fn pre_transition(&mut self, context: &mut Context) {
+        context.timeouts =
+            context.timeouts.saturating_add(1);
+    }
+}
+
+event_driven_state_machine!(
+    TrafficLight {
+        context: Context,
+        // Generates `into_shared`, to handle events from many threads
+        shared: true,
+        event_trait: trait TrafficLightEvent: Send {},
+        event_enum: #[derive(Debug, Clone)] TrafficLightMachineEvent,
+        state_trait: trait TrafficLightState {},
+        state_enum: #[derive(Debug, Clone)] TrafficLightMachineState,
+        states: [
+            Red {
+                TimeoutEvent -> Green,
+            },
+            Green {
+                TimeoutEvent -> Yellow,
+            },
+            Yellow {
+                TimeoutEvent -> Red,
+            },
+        ],
+    }
+);
+
+fn main() {
+    let traffic_light =
+        TrafficLight::new(Red, Context::default())
+            .into_shared();
+    let changes = traffic_light.subscribe();
+
+    let timer = thread::spawn({
+        let traffic_light = traffic_light.clone();
+
+        move || {
+            for _ in 0_u32..3 {
+                _ = traffic_light
+                    .handle_event(TimeoutEvent);
+            }
+        }
+    });
+
+    let watcher = thread::spawn(move || {
+        // The receiver is disconnected once the last handle
+        // is dropped.
+        changes.iter().collect::<Vec<_>>()
+    });
+
+    timer.join().expect("timer thread panicked");
+    assert_eq!(
+        traffic_light.state_kind(),
+        TrafficLightMachineStateKind::Red,
+        "The light should be red again"
+    );
+    assert_eq!(
+        traffic_light.with(|traffic_light| {
+            traffic_light.context().timeouts
+        }),
+        3,
+        "Every timeout should have been handled"
+    );
+    drop(traffic_light);
+
+    let changes =
+        watcher.join().expect("watcher thread panicked");
+    println!("State changes: {changes:?}");
+    assert_eq!(
+        changes,
+        [
+            TrafficLightMachineStateKind::Green,
+            TrafficLightMachineStateKind::Yellow,
+            TrafficLightMachineStateKind::Red,
+        ],
+        "Every state change should have been reported"
+    );
+}
@@ -0,0 +1,87 @@
+//! This example demonstrates driving a traffic light from a
+//! stream of events with `stream: true`.
+#![allow(clippy::missing_trait_methods)]
+#![allow(missing_docs)]
+#![allow(clippy::print_stdout)]
+#![allow(clippy::use_debug)]
+
+use futures::{StreamExt, executor::block_on, stream};
+use machine_factory::event_driven_state_machine;
+
+#[derive(Default)]
+struct Context;
+
+#[derive(Debug, Default, Clone)]
+struct Red;
+impl TrafficLightState for Red {}
+
+#[derive(Debug, Default, Clone)]
+struct Green;
+impl TrafficLightState for Green {}
+
+#[derive(Debug, Default, Clone)]
+struct Yellow;
+impl TrafficLightState for Yellow {}
+
+#[derive(Debug, Clone)]
+struct TimeoutEvent;
+impl TrafficLightEvent for TimeoutEvent {}
+
+event_driven_state_machine!(
+    TrafficLight {
+        context: Context,
+        // Generates `run_stream`, to drive the traffic light from a stream of events
+        stream: true,
+        event_trait: trait TrafficLightEvent {},
+        event_enum: #[derive(Debug, Clone)] TrafficLightMachineEvent,
+        state_trait: trait TrafficLightState {},
+        state_enum: #[derive(Debug, Clone)] TrafficLightMachineState,
+        states: [
+            Red {
+                TimeoutEvent -> Green,
+            },
+            Green {
+                TimeoutEvent -> Yellow,
+            },
+            Yellow {
+                TimeoutEvent -> Red,
+            },
+        ],
+    }
+);
+
+fn main() {
+    let mut traffic_light = TrafficLight::new(Red, Context);
+
+    let events = stream::repeat_with(|| {
+        TrafficLightMachineEvent::from(TimeoutEvent)
+    });
+
+    // Events are handled until the light turns yellow; the
+    // input stream never ends on its own.
+    let outcomes = block_on(
+        traffic_light
+            .run_stream(events, |state| {
+                state.kind()
+                    == TrafficLightMachineStateKind::Yellow
+            })
+            .collect::<Vec<_>>(),
+    );
+
+    assert_eq!(
+        outcomes,
+        [
+            TrafficLightOutcome::Transitioned {
+                from: TrafficLightMachineStateKind::Red,
+                to: TrafficLightMachineStateKind::Green,
+            },
+            TrafficLightOutcome::Transitioned {
+                from: TrafficLightMachineStateKind::Green,
+                to: TrafficLightMachineStateKind::Yellow,
+            }
+        ],
+        "Red -> Green -> Yellow"
+    );
+
+    println!("Outcomes: {outcomes:?}");
+}
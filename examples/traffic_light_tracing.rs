@@ -0,0 +1,60 @@
+//! This example demonstrates tracing a traffic light with
+//! `tracing: true`: each event is handled in a span, with
+//! events for each step.
+#![allow(clippy::missing_trait_methods)]
+#![allow(missing_docs)]
+
+use machine_factory::event_driven_state_machine;
+
+#[derive(Default)]
+struct Context;
+
+#[derive(Debug, Default, Clone)]
+struct Red;
+impl TrafficLightState for Red {}
+
+#[derive(Debug, Default, Clone)]
+struct Green;
+impl TrafficLightState for Green {}
+
+#[derive(Debug, Default, Clone)]
+struct Yellow;
+impl TrafficLightState for Yellow {}
+
+#[derive(Debug, Clone)]
+struct TimeoutEvent;
+impl TrafficLightEvent for TimeoutEvent {}
+
+event_driven_state_machine!(
+    TrafficLight {
+        context: Context,
+        tracing: true,
+        event_trait: trait TrafficLightEvent {},
+        event_enum: #[derive(Debug, Clone)] TrafficLightMachineEvent,
+        state_trait: trait TrafficLightState {},
+        state_enum: #[derive(Debug, Clone)] TrafficLightMachineState,
+        states: [
+            Red {
+                TimeoutEvent -> Green,
+            },
+            Green {
+                TimeoutEvent -> Yellow,
+            },
+            Yellow {
+                TimeoutEvent -> Red,
+            },
+        ],
+    }
+);
+
+fn main() {
+    tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::TRACE)
+        .init();
+
+    let mut traffic_light = TrafficLight::new(Red, Context);
+
+    _ = traffic_light
+        .handle_event(TimeoutEvent)
+        .handle_event(TimeoutEvent);
+}
@@ -3,6 +3,12 @@
 //! to control a camera that starts recording when the
 //! traffic light is red. See the [`camera`] module for the
 //! camera state machine.
+//!
+//! Stopping the recording is an effect: the transition
+//! block only describes it, and `CameraExecutor` runs it.
+//! This keeps the transition testable with
+//! `TrafficLight::transition`, which doesn't touch the
+//! camera.
 #![allow(clippy::missing_trait_methods)]
 #![allow(missing_docs)]
 #![allow(clippy::print_stdout)]
@@ -51,11 +57,13 @@ impl TrafficLightStateTrait for Yellow {
         &self,
         _context: &Context,
         event: &TrafficLightEvent,
-    ) -> bool {
-        !matches!(
-            event,
-            TrafficLightEvent::StopRecording(_)
-        )
+    ) -> Result<(), Rejection> {
+        match event {
+            TrafficLightEvent::StopRecording(_) => {
+                Err(Rejection::NotRecording)
+            }
+            TrafficLightEvent::Next(_) => Ok(()),
+        }
     }
 }
 
@@ -68,32 +76,73 @@ impl TrafficLightStateTrait for Green {
         &self,
         _context: &Context,
         event: &TrafficLightEvent,
-    ) -> bool {
-        !matches!(
-            event,
-            TrafficLightEvent::StopRecording(_)
-        )
+    ) -> Result<(), Rejection> {
+        match event {
+            TrafficLightEvent::StopRecording(_) => {
+                Err(Rejection::NotRecording)
+            }
+            TrafficLightEvent::Next(_) => Ok(()),
+        }
     }
 }
 
+/// Why the traffic light ignored an event, returned in the
+/// outcome of `handle_event_outcome`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Rejection {
+    NotRecording,
+}
+
 // Events
 struct Next;
 impl TrafficLightEventTrait for Next {}
 
 impl TrafficLightEventTrait for StopRecording {}
 
+// Effects
+#[derive(Debug, PartialEq, Eq)]
+enum Effect {
+    StopCamera,
+}
+
+struct CameraExecutor;
+
+#[async_trait]
+impl TrafficLightExecutor for CameraExecutor {
+    async fn execute(
+        &mut self,
+        effect: Effect,
+        context: &mut Context,
+    ) {
+        match effect {
+            Effect::StopCamera => {
+                _ = context
+                    .camera
+                    .handle_event(StopRecording {})
+                    .await;
+            }
+        }
+    }
+}
+
 event_driven_state_machine!(async TrafficLight {
     context: Context ,
+    // Transition blocks return the next state along with the effects to run
+    effects: Effect,
     state_enum: TrafficLightState,
-    state_trait: trait TrafficLightStateTrait {},
+    state_trait: trait TrafficLightStateTrait {
+        // Returning a `Result` instead of a `bool` gives the reason events are rejected
+        async fn should_exit(&self, _context: &Context, _event: &TrafficLightEvent) -> Result<(), Rejection> {
+            Ok(())
+        }
+    },
     event_enum: TrafficLightEvent,
     event_trait: trait TrafficLightEventTrait: Send {},
     states: [
         Red {
             Next -> Green,
             StopRecording {
-                _ = context.camera.handle_event(event.clone()).await;
-                state
+                (state, vec![Effect::StopCamera])
             }
         },
         Yellow {
@@ -103,7 +152,7 @@ event_driven_state_machine!(async TrafficLight {
             Next -> Yellow,
         },
         _ {
-            state
+            (state, vec![])
         },
     ],
 });
@@ -118,7 +167,23 @@ async fn main() {
     let mut state = Red {};
     state.on_enter(&mut context).await;
 
-    let mut traffic_light = TrafficLight::new(Red, context);
+    // The transition can be checked without running its
+    // effects.
+    let mut test_context = Context { camera: Camera::default() };
+    let (_, effects) = TrafficLight::transition(
+        Red.into(),
+        &mut test_context,
+        StopRecording {},
+    )
+    .await;
+    assert_eq!(
+        effects,
+        [Effect::StopCamera],
+        "Stopping should stop the camera"
+    );
+
+    let mut traffic_light =
+        TrafficLight::new(Red, context).with_executor(CameraExecutor);
 
     let mut count = 0_i32;
     while count < 10_i32 {
@@ -127,7 +192,17 @@ async fn main() {
         sleep(Duration::from_secs(1));
     }
 
-    _ = traffic_light.handle_event(StopRecording {}).await;
+    // The camera only records while the light is red
+    let outcome =
+        traffic_light.handle_event_outcome(StopRecording {}).await;
+    assert_eq!(
+        outcome,
+        TrafficLightOutcome::Rejected {
+            state: TrafficLightStateKind::Green,
+            reason: Rejection::NotRecording,
+        },
+        "The light should be green after 10 changes"
+    );
 
     println!("Traffic light stopped recording.");
     println!(
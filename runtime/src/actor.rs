@@ -0,0 +1,27 @@
+use core::{error::Error, fmt, num::NonZeroUsize};
+
+/// The default number of messages that can be queued in
+/// the mailbox of a spawned machine before senders have to
+/// wait.
+pub const DEFAULT_MAILBOX_CAPACITY: NonZeroUsize =
+    NonZeroUsize::new(64).expect("64 is not zero");
+
+/// Returned by the handle of a spawned machine when the
+/// machine's task is no longer running (e.g., after it was
+/// shut down).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ActorStopped;
+
+impl fmt::Display for ActorStopped {
+    #[inline]
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        f.write_str(
+            "the machine's task is no longer running",
+        )
+    }
+}
+
+impl Error for ActorStopped {}
@@ -0,0 +1,162 @@
+use alloc::sync::Arc;
+use core::{fmt, time::Duration};
+use std::{sync::Mutex, time::Instant};
+
+/// A source of time for a state machine.
+///
+/// Machines generated with a `clock` label hold a clock
+/// and pass it to transition blocks and to any hook that
+/// asks for it, so that all of the time a machine observes
+/// can be controlled in tests (see [`ManualClock`]).
+pub trait Clock {
+    /// Returns the current instant, according to this
+    /// clock.
+    fn now(&self) -> Instant;
+}
+
+/// A [`Clock`] backed by [`Instant::now`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    #[inline]
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A [`Clock`] that only moves when told to.
+///
+/// Clones share the same time, so a test can keep a clone
+/// and advance the clock held by a machine.
+///
+/// ```rust
+/// use core::time::Duration;
+/// use machine_factory_runtime::{Clock, ManualClock};
+///
+/// let clock = ManualClock::new();
+/// let start = clock.now();
+///
+/// clock.clone().advance(Duration::from_secs(2));
+///
+/// assert_eq!(clock.now() - start, Duration::from_secs(2));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    now: Arc<Mutex<Instant>>,
+}
+
+impl ManualClock {
+    /// Moves the clock forward by `duration`.
+    ///
+    /// # Panics
+    /// Panics if the resulting instant cannot be
+    /// represented.
+    #[inline]
+    pub fn advance(&self, duration: Duration) {
+        let mut now =
+            self.now.lock().expect("clock lock poisoned");
+        *now = now
+            .checked_add(duration)
+            .expect("clock overflowed");
+    }
+
+    /// Creates a clock frozen at the current instant.
+    #[must_use]
+    #[inline]
+    pub fn new() -> Self {
+        Self::starting_at(Instant::now())
+    }
+
+    /// Sets the clock to `instant`.
+    ///
+    /// # Panics
+    /// Panics if another thread panicked while using the
+    /// clock.
+    #[inline]
+    pub fn set(&self, instant: Instant) {
+        *self.now.lock().expect("clock lock poisoned") =
+            instant;
+    }
+
+    /// Creates a clock frozen at `instant`.
+    #[must_use]
+    #[inline]
+    pub fn starting_at(instant: Instant) -> Self {
+        Self { now: Arc::new(Mutex::new(instant)) }
+    }
+}
+
+impl Default for ManualClock {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    #[inline]
+    fn now(&self) -> Instant {
+        *self.now.lock().expect("clock lock poisoned")
+    }
+}
+
+/// A cloneable, type-erased [`Clock`].
+///
+/// This is the most convenient type to use for a machine's
+/// `clock` label: it defaults to a [`SystemClock`], and
+/// tests can swap in a [`ManualClock`].
+#[derive(Clone)]
+pub struct SharedClock {
+    clock: Arc<dyn Clock + Send + Sync>,
+}
+
+impl SharedClock {
+    /// Wraps `clock`.
+    #[inline]
+    pub fn new<C>(clock: C) -> Self
+    where
+        C: Clock + Send + Sync + 'static,
+    {
+        Self { clock: Arc::new(clock) }
+    }
+}
+
+impl Default for SharedClock {
+    #[inline]
+    fn default() -> Self {
+        Self::new(SystemClock)
+    }
+}
+
+impl fmt::Debug for SharedClock {
+    #[inline]
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        f.debug_struct("SharedClock")
+            .finish_non_exhaustive()
+    }
+}
+
+impl Clock for SharedClock {
+    #[inline]
+    fn now(&self) -> Instant {
+        self.clock.now()
+    }
+}
+
+impl From<SystemClock> for SharedClock {
+    #[inline]
+    fn from(clock: SystemClock) -> Self {
+        Self::new(clock)
+    }
+}
+
+impl From<ManualClock> for SharedClock {
+    #[inline]
+    fn from(clock: ManualClock) -> Self {
+        Self::new(clock)
+    }
+}
@@ -0,0 +1,82 @@
+use core::{fmt, mem};
+
+/// The effects of a machine generated with an `effects`
+/// label: the executor that runs them, and the effects
+/// that were produced while no executor was set.
+///
+/// Clones of a machine start without an executor or
+/// pending effects.
+pub struct Effects<Executor: ?Sized, Effect> {
+    executor: Option<Box<Executor>>,
+    pending: Vec<Effect>,
+}
+
+impl<Executor: ?Sized, Effect> Effects<Executor, Effect> {
+    /// Keeps `effects` until they are taken with
+    /// [`Effects::take_pending`].
+    #[inline]
+    pub fn defer<Iter>(&mut self, effects: Iter)
+    where
+        Iter: IntoIterator<Item = Effect>,
+    {
+        self.pending.extend(effects);
+    }
+
+    /// Returns the executor, if one was set.
+    #[inline]
+    pub fn executor_mut(
+        &mut self,
+    ) -> Option<&mut Executor> {
+        self.executor.as_deref_mut()
+    }
+
+    /// Sets the executor that runs the effects of every
+    /// following transition.
+    #[inline]
+    pub fn set_executor(
+        &mut self,
+        executor: Box<Executor>,
+    ) {
+        self.executor = Some(executor);
+    }
+
+    /// Takes the effects that were deferred, in the order
+    /// they were produced.
+    #[inline]
+    pub fn take_pending(&mut self) -> Vec<Effect> {
+        mem::take(&mut self.pending)
+    }
+}
+
+impl<Executor: ?Sized, Effect> Default
+    for Effects<Executor, Effect>
+{
+    #[inline]
+    fn default() -> Self {
+        Self { executor: None, pending: Vec::new() }
+    }
+}
+
+impl<Executor: ?Sized, Effect> Clone
+    for Effects<Executor, Effect>
+{
+    #[inline]
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl<Executor: ?Sized, Effect> fmt::Debug
+    for Effects<Executor, Effect>
+{
+    #[inline]
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        f.debug_struct("Effects")
+            .field("has_executor", &self.executor.is_some())
+            .field("pending", &self.pending.len())
+            .finish()
+    }
+}
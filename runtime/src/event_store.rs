@@ -0,0 +1,411 @@
+#[cfg(feature = "serde")]
+use core::marker::PhantomData;
+use core::{convert::Infallible, error::Error, fmt};
+#[cfg(feature = "serde")]
+use serde::{Serialize, de::DeserializeOwned};
+#[cfg(feature = "serde")]
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{
+        self, BufRead as _, BufReader, BufWriter,
+        Write as _,
+    },
+    path::{Path, PathBuf},
+};
+
+/// What an [`EventStore`] loads to rebuild a machine: the
+/// latest snapshot, if any, and the events stored after
+/// it, in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stored<Event, State, Context> {
+    /// The events stored after the snapshot.
+    pub events: Vec<Event>,
+    /// The state and context of the latest snapshot.
+    pub snapshot: Option<(State, Context)>,
+}
+
+/// Returned when a machine can't be rebuilt from its
+/// [`EventStore`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RebuildError<StoreError, Event, Outcome> {
+    /// A stored event no longer causes a transition, e.g.
+    /// because the machine's `should_exit` changed since
+    /// the event was stored.
+    Diverged {
+        /// The index of the event after the snapshot.
+        index: usize,
+        /// The event that didn't cause a transition.
+        event: Event,
+        /// The outcome of folding the event.
+        outcome: Outcome,
+    },
+    /// The store couldn't be read.
+    Store(StoreError),
+}
+
+impl<StoreError, Event, Outcome> fmt::Display
+    for RebuildError<StoreError, Event, Outcome>
+where
+    StoreError: fmt::Display,
+    Event: fmt::Debug,
+    Outcome: fmt::Debug,
+{
+    #[expect(
+        clippy::use_debug,
+        reason = "events and outcomes are only required to be `Debug`"
+    )]
+    #[inline]
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        match self {
+            Self::Store(error) => {
+                write!(
+                    f,
+                    "couldn't load the events: {error}"
+                )
+            }
+            Self::Diverged { index, event, outcome } => {
+                write!(
+                    f,
+                    "stored event {index} ({event:?}) no longer \
+                     transitions: {outcome:?}"
+                )
+            }
+        }
+    }
+}
+
+impl<StoreError, Event, Outcome> Error
+    for RebuildError<StoreError, Event, Outcome>
+where
+    StoreError: fmt::Debug + fmt::Display,
+    Event: fmt::Debug,
+    Outcome: fmt::Debug,
+{
+}
+
+/// An append-only log of the events accepted by a machine,
+/// along with snapshots of the machine.
+///
+/// Machines generated with an `event_store` label append
+/// every accepted event to their store, and can be rebuilt
+/// from it by folding the stored events into the latest
+/// snapshot.
+pub trait EventStore<Event, State, Context> {
+    /// The error returned when the store can't be read or
+    /// written.
+    type Error;
+
+    /// Appends an accepted event to the log.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the event couldn't be stored.
+    fn append(
+        &mut self,
+        event: &Event,
+    ) -> Result<(), Self::Error>;
+
+    /// Loads the latest snapshot, and the events appended
+    /// after it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store couldn't be read.
+    fn load(
+        &self,
+    ) -> Result<Stored<Event, State, Context>, Self::Error>;
+
+    /// Stores a snapshot of the machine, after all of the
+    /// events appended so far.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the snapshot couldn't be
+    /// stored.
+    fn save_snapshot(
+        &mut self,
+        state: &State,
+        context: &Context,
+    ) -> Result<(), Self::Error>;
+}
+
+/// An [`EventStore`] keeping everything in memory.
+///
+/// ```rust
+/// use machine_factory_runtime::{
+///     EventStore, InMemoryEventStore,
+/// };
+///
+/// let mut store =
+///     InMemoryEventStore::<&str, u8, ()>::new();
+/// store.append(&"start").unwrap();
+/// store.save_snapshot(&1, &()).unwrap();
+/// store.append(&"stop").unwrap();
+///
+/// let stored = store.load().unwrap();
+/// assert_eq!(stored.snapshot, Some((1, ())));
+/// assert_eq!(stored.events, ["stop"]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct InMemoryEventStore<Event, State, Context> {
+    events: Vec<Event>,
+    snapshot: Option<(usize, State, Context)>,
+}
+
+impl<Event, State, Context>
+    InMemoryEventStore<Event, State, Context>
+{
+    /// All of the stored events, including those before
+    /// the latest snapshot.
+    #[must_use]
+    #[inline]
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    /// Creates an empty store.
+    #[must_use]
+    #[inline]
+    pub const fn new() -> Self {
+        Self { events: Vec::new(), snapshot: None }
+    }
+}
+
+impl<Event, State, Context> Default
+    for InMemoryEventStore<Event, State, Context>
+{
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Event: Clone, State: Clone, Context: Clone>
+    EventStore<Event, State, Context>
+    for InMemoryEventStore<Event, State, Context>
+{
+    type Error = Infallible;
+
+    #[inline]
+    fn append(
+        &mut self,
+        event: &Event,
+    ) -> Result<(), Self::Error> {
+        self.events.push(event.clone());
+        Ok(())
+    }
+
+    #[inline]
+    fn load(
+        &self,
+    ) -> Result<Stored<Event, State, Context>, Self::Error>
+    {
+        let (version, snapshot) =
+            self.snapshot.as_ref().map_or(
+                (0, None),
+                |(version, state, context)| {
+                    (
+                        *version,
+                        Some((
+                            state.clone(),
+                            context.clone(),
+                        )),
+                    )
+                },
+            );
+
+        Ok(Stored {
+            snapshot,
+            events: self
+                .events
+                .get(version..)
+                .unwrap_or_default()
+                .to_vec(),
+        })
+    }
+
+    #[inline]
+    fn save_snapshot(
+        &mut self,
+        state: &State,
+        context: &Context,
+    ) -> Result<(), Self::Error> {
+        self.snapshot = Some((
+            self.events.len(),
+            state.clone(),
+            context.clone(),
+        ));
+        Ok(())
+    }
+}
+
+#[cfg(feature = "serde")]
+type StoredTypes<Event, State, Context> =
+    fn() -> (Event, State, Context);
+
+/// An [`EventStore`] keeping events and snapshots as JSON
+/// in a directory: events are appended to `events.jsonl`,
+/// one per line, and the latest snapshot replaces
+/// `snapshot.json`.
+///
+/// Requires the `serde` feature.
+#[cfg(feature = "serde")]
+#[derive(Debug)]
+pub struct FileEventStore<Event, State, Context> {
+    directory: PathBuf,
+    len: usize,
+    types: PhantomData<StoredTypes<Event, State, Context>>,
+}
+
+#[cfg(feature = "serde")]
+impl<Event, State, Context>
+    FileEventStore<Event, State, Context>
+{
+    const EVENTS: &'static str = "events.jsonl";
+
+    const SNAPSHOT: &'static str = "snapshot.json";
+
+    /// The directory holding the store.
+    #[must_use]
+    #[inline]
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Opens the store in `directory`, creating the
+    /// directory if needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory couldn't be
+    /// created, or the stored events couldn't be read.
+    #[inline]
+    pub fn open<Directory: AsRef<Path>>(
+        directory: Directory,
+    ) -> io::Result<Self> {
+        let directory = directory.as_ref().to_owned();
+        fs::create_dir_all(&directory)?;
+
+        let len = match File::open(
+            directory.join(Self::EVENTS),
+        ) {
+            Ok(file) => {
+                BufReader::new(file).lines().count()
+            }
+            Err(error)
+                if error.kind()
+                    == io::ErrorKind::NotFound =>
+            {
+                0
+            }
+            Err(error) => return Err(error),
+        };
+
+        Ok(Self { directory, len, types: PhantomData })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<Event, State, Context>
+    EventStore<Event, State, Context>
+    for FileEventStore<Event, State, Context>
+where
+    Event: Serialize + DeserializeOwned,
+    State: Serialize + DeserializeOwned,
+    Context: Serialize + DeserializeOwned,
+{
+    type Error = io::Error;
+
+    #[inline]
+    fn append(
+        &mut self,
+        event: &Event,
+    ) -> Result<(), Self::Error> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.directory.join(Self::EVENTS))?;
+
+        let mut line = serde_json::to_vec(event)?;
+        line.push(b'\n');
+        file.write_all(&line)?;
+        file.sync_data()?;
+
+        self.len = self.len.saturating_add(1);
+        Ok(())
+    }
+
+    #[inline]
+    fn load(
+        &self,
+    ) -> Result<Stored<Event, State, Context>, Self::Error>
+    {
+        let (version, snapshot) = match File::open(
+            self.directory.join(Self::SNAPSHOT),
+        ) {
+            Ok(file) => {
+                let (version, state, context) =
+                    serde_json::from_reader(
+                        BufReader::new(file),
+                    )?;
+                (version, Some((state, context)))
+            }
+            Err(error)
+                if error.kind()
+                    == io::ErrorKind::NotFound =>
+            {
+                (0, None)
+            }
+            Err(error) => return Err(error),
+        };
+
+        let events = match File::open(
+            self.directory.join(Self::EVENTS),
+        ) {
+            Ok(file) => BufReader::new(file)
+                .lines()
+                .skip(version)
+                .map(|line| {
+                    Ok(serde_json::from_str(&line?)?)
+                })
+                .collect::<io::Result<_>>()?,
+            Err(error)
+                if error.kind()
+                    == io::ErrorKind::NotFound =>
+            {
+                Vec::new()
+            }
+            Err(error) => return Err(error),
+        };
+
+        Ok(Stored { events, snapshot })
+    }
+
+    #[inline]
+    fn save_snapshot(
+        &mut self,
+        state: &State,
+        context: &Context,
+    ) -> Result<(), Self::Error> {
+        // Written next to the snapshot, and then renamed
+        // over it, so that a snapshot is never partially
+        // written
+        let temporary =
+            self.directory.join("snapshot.json.tmp");
+        let mut writer =
+            BufWriter::new(File::create(&temporary)?);
+        serde_json::to_writer(
+            &mut writer,
+            &(self.len, state, context),
+        )?;
+        writer.into_inner()?.sync_all()?;
+
+        fs::rename(
+            temporary,
+            self.directory.join(Self::SNAPSHOT),
+        )
+    }
+}
@@ -0,0 +1,209 @@
+use alloc::collections::VecDeque;
+
+/// Whether undoing and redoing transitions runs the state
+/// hooks.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash,
+)]
+pub enum UndoPolicy {
+    /// Only restores the state and the context.
+    #[default]
+    Restore,
+    /// Restores the context, and then calls `on_exit` on
+    /// the state being left and `on_enter` on the restored
+    /// state, with the event of the undone (or redone)
+    /// transition.
+    RunHooks,
+}
+
+/// A state and context of a machine, along with the event
+/// that moved the machine from (or to) them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryEntry<State, Context, Event> {
+    /// The context of the machine.
+    pub context: Context,
+    /// The event of the transition.
+    pub event: Event,
+    /// The state of the machine.
+    pub state: State,
+}
+
+/// A bounded history of the transitions of a machine,
+/// which can be undone and redone.
+///
+/// Machines generated with a `history` label record the
+/// state and context they had before each transition, up to
+/// the given number of transitions, and get `undo` and
+/// `redo` methods.
+///
+/// ```rust
+/// use machine_factory_runtime::{History, HistoryEntry};
+///
+/// let mut history = History::new(2);
+/// history.record(HistoryEntry {
+///     state: "a",
+///     context: 0,
+///     event: "next",
+/// });
+///
+/// let undone = history.undo("b", 1).unwrap();
+/// assert_eq!((undone.state, undone.context), ("a", 0));
+///
+/// let redone = history.redo("a", 0).unwrap();
+/// assert_eq!((redone.state, redone.context), ("b", 1));
+/// ```
+#[derive(Debug, Clone)]
+pub struct History<State, Context, Event> {
+    capacity: usize,
+    policy: UndoPolicy,
+    redo: Vec<HistoryEntry<State, Context, Event>>,
+    undo: VecDeque<HistoryEntry<State, Context, Event>>,
+}
+
+impl<State, Context, Event: Clone>
+    History<State, Context, Event>
+{
+    /// Whether there is an undone transition to redo.
+    #[must_use]
+    #[inline]
+    pub const fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Whether there is a transition to undo.
+    #[must_use]
+    #[inline]
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    /// How many transitions are kept to undo.
+    #[must_use]
+    #[inline]
+    pub const fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Forgets every transition.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    /// Creates an empty history, keeping up to `capacity`
+    /// transitions to undo.
+    #[must_use]
+    #[inline]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            undo: VecDeque::with_capacity(capacity),
+            redo: Vec::new(),
+            capacity,
+            policy: UndoPolicy::default(),
+        }
+    }
+
+    /// Whether undoing and redoing runs the state hooks.
+    #[must_use]
+    #[inline]
+    pub const fn policy(&self) -> UndoPolicy {
+        self.policy
+    }
+
+    fn push_undo(
+        &mut self,
+        entry: HistoryEntry<State, Context, Event>,
+    ) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.undo.len() == self.capacity {
+            drop(self.undo.pop_front());
+        }
+
+        self.undo.push_back(entry);
+    }
+
+    /// Records the state and context before a transition,
+    /// forgetting the oldest transition if the history is
+    /// full, and the undone transitions.
+    #[inline]
+    pub fn record(
+        &mut self,
+        entry: HistoryEntry<State, Context, Event>,
+    ) {
+        self.redo.clear();
+        self.push_undo(entry);
+    }
+
+    /// Redoes the last undone transition: returns the state
+    /// and context to restore, and keeps the current
+    /// `state` and `context` to undo it again. If there is
+    /// nothing to redo, they are returned instead.
+    ///
+    /// # Errors
+    ///
+    /// Returns `state` and `context` if there is no
+    /// transition to redo.
+    #[inline]
+    pub fn redo(
+        &mut self,
+        state: State,
+        context: Context,
+    ) -> Result<
+        HistoryEntry<State, Context, Event>,
+        (State, Context),
+    > {
+        match self.redo.pop() {
+            Some(entry) => {
+                self.push_undo(HistoryEntry {
+                    state,
+                    context,
+                    event: entry.event.clone(),
+                });
+                Ok(entry)
+            }
+            None => Err((state, context)),
+        }
+    }
+
+    /// Sets whether undoing and redoing runs the state
+    /// hooks.
+    #[inline]
+    pub const fn set_policy(&mut self, policy: UndoPolicy) {
+        self.policy = policy;
+    }
+
+    /// Undoes the last transition: returns the state and
+    /// context to restore, and keeps the current `state`
+    /// and `context` to redo it. If there is nothing to
+    /// undo, they are returned instead.
+    ///
+    /// # Errors
+    ///
+    /// Returns `state` and `context` if there is no
+    /// transition to undo.
+    #[inline]
+    pub fn undo(
+        &mut self,
+        state: State,
+        context: Context,
+    ) -> Result<
+        HistoryEntry<State, Context, Event>,
+        (State, Context),
+    > {
+        match self.undo.pop_back() {
+            Some(entry) => {
+                self.redo.push(HistoryEntry {
+                    state,
+                    context,
+                    event: entry.event.clone(),
+                });
+                Ok(entry)
+            }
+            None => Err((state, context)),
+        }
+    }
+}
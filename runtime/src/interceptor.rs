@@ -0,0 +1,197 @@
+use crate::StateMachine;
+use alloc::boxed::Box;
+use core::{fmt, mem};
+
+/// Whether an [`Interceptor`] lets an event through to the
+/// machine.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, Default,
+)]
+pub enum Verdict {
+    /// The event is handled by the machine (and the next
+    /// interceptors).
+    #[default]
+    Proceed,
+    /// The event is dropped before `should_exit`, and the
+    /// machine returns a `Vetoed` outcome.
+    Veto,
+}
+
+/// A layer around the `handle_event` of a machine generated
+/// with `interceptors: true`, for cross-cutting concerns
+/// such as authorization, rate limiting, auditing or
+/// deduplication.
+///
+/// Interceptors are added when the machine is built, with
+/// its `layer` method. Like `tower` layers, the first one
+/// added is the outermost: `before` is called in the order
+/// the interceptors were added, and `after` in the reverse
+/// order, only for the interceptors whose `before` was
+/// called and let the event through.
+pub trait Interceptor<Machine: StateMachine> {
+    /// Called after the machine handled an event (or after
+    /// an inner interceptor vetoed it), with the new state
+    /// and the outcome.
+    #[inline]
+    fn after(
+        &mut self,
+        state: &Machine::State,
+        context: &Machine::Context,
+        outcome: &Machine::Outcome,
+    ) {
+        _ = (state, context, outcome);
+    }
+
+    /// Called before the machine handles `event`, i.e.
+    /// before `should_exit`. Returning [`Verdict::Veto`]
+    /// drops the event.
+    #[inline]
+    fn before(
+        &mut self,
+        state: &Machine::State,
+        context: &Machine::Context,
+        event: &Machine::Event,
+    ) -> Verdict {
+        _ = (state, context, event);
+        Verdict::Proceed
+    }
+}
+
+/// The [`Interceptor`]s of a machine, in the order they
+/// were added.
+///
+/// Clones of a machine get clones of its interceptors.
+pub struct Interceptors<Machine: StateMachine> {
+    /// How many interceptors the current event went
+    /// through.
+    entered: usize,
+    interceptors: Vec<Box<dyn BoxedInterceptor<Machine>>>,
+}
+
+impl<Machine: StateMachine> Interceptors<Machine> {
+    /// Calls [`Interceptor::after`] on each interceptor
+    /// that let the current event through, innermost first.
+    #[inline]
+    pub fn after(
+        &mut self,
+        state: &Machine::State,
+        context: &Machine::Context,
+        outcome: &Machine::Outcome,
+    ) {
+        let entered = mem::take(&mut self.entered);
+
+        for interceptor in
+            self.interceptors.iter_mut().take(entered).rev()
+        {
+            interceptor.after(state, context, outcome);
+        }
+    }
+
+    /// Calls [`Interceptor::before`] on each interceptor,
+    /// until one of them vetoes the event.
+    #[inline]
+    pub fn before(
+        &mut self,
+        state: &Machine::State,
+        context: &Machine::Context,
+        event: &Machine::Event,
+    ) -> Verdict {
+        self.entered = 0;
+
+        for interceptor in &mut self.interceptors {
+            if interceptor.before(state, context, event)
+                == Verdict::Veto
+            {
+                return Verdict::Veto;
+            }
+
+            self.entered = self.entered.saturating_add(1);
+        }
+
+        Verdict::Proceed
+    }
+
+    /// Whether there are no interceptors.
+    #[must_use]
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.interceptors.is_empty()
+    }
+
+    /// The number of interceptors.
+    #[must_use]
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.interceptors.len()
+    }
+
+    /// Adds `interceptor` inside the ones already added.
+    #[inline]
+    pub fn push<Layer>(&mut self, interceptor: Layer)
+    where
+        Layer:
+            Interceptor<Machine> + Clone + Send + 'static,
+    {
+        self.interceptors.push(Box::new(interceptor));
+    }
+}
+
+impl<Machine: StateMachine> Default
+    for Interceptors<Machine>
+{
+    #[inline]
+    fn default() -> Self {
+        Self { interceptors: Vec::new(), entered: 0 }
+    }
+}
+
+impl<Machine: StateMachine> Clone
+    for Interceptors<Machine>
+{
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            interceptors: self
+                .interceptors
+                .iter()
+                .map(|interceptor| interceptor.clone_box())
+                .collect(),
+            entered: 0,
+        }
+    }
+}
+
+impl<Machine: StateMachine> fmt::Debug
+    for Interceptors<Machine>
+{
+    #[inline]
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        f.debug_struct("Interceptors")
+            .field("len", &self.interceptors.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// An [`Interceptor`] that can be cloned behind a `Box`.
+trait BoxedInterceptor<Machine: StateMachine>:
+    Interceptor<Machine> + Send
+{
+    fn clone_box(
+        &self,
+    ) -> Box<dyn BoxedInterceptor<Machine>>;
+}
+
+impl<Machine, Layer> BoxedInterceptor<Machine> for Layer
+where
+    Machine: StateMachine,
+    Layer: Interceptor<Machine> + Clone + Send + 'static,
+{
+    fn clone_box(
+        &self,
+    ) -> Box<dyn BoxedInterceptor<Machine>> {
+        Box::new(self.clone())
+    }
+}
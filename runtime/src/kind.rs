@@ -0,0 +1,18 @@
+use core::hash::Hash;
+
+/// A fieldless enum naming the states or events of a
+/// generated machine.
+///
+/// The macros implement this for the `...Kind` enums they
+/// generate, so runtime types can label what they record
+/// with the kinds' names, or store kinds as integers.
+pub trait Kind: Copy + Eq + Hash {
+    /// The variant at `index` in the enum, if there is one.
+    fn from_index(index: usize) -> Option<Self>;
+
+    /// The position of the variant in its enum.
+    fn index(self) -> usize;
+
+    /// The name of the state or event.
+    fn name(self) -> &'static str;
+}
@@ -0,0 +1,60 @@
+//! Runtime support for state machines generated by the
+//! `machine-factory` macros.
+//!
+//! The macros only generate code; anything that needs to
+//! be shared between generated machines (traits, helper
+//! types, etc.) lives in this crate, and the generated
+//! code refers to it as `::machine_factory_runtime`.
+
+#![expect(
+    clippy::missing_trait_methods,
+    reason = "default trait methods are meant to be inherited"
+)]
+
+extern crate alloc;
+
+mod actor;
+mod clock;
+mod effects;
+mod event_store;
+mod history;
+mod interceptor;
+mod kind;
+mod machine;
+mod metrics;
+mod observer;
+mod persister;
+#[cfg(feature = "tokio")]
+mod publisher;
+mod registry;
+mod shared;
+mod trace;
+
+pub use actor::{ActorStopped, DEFAULT_MAILBOX_CAPACITY};
+pub use clock::{
+    Clock, ManualClock, SharedClock, SystemClock,
+};
+pub use effects::Effects;
+#[cfg(feature = "serde")]
+pub use event_store::FileEventStore;
+pub use event_store::{
+    EventStore, InMemoryEventStore, RebuildError, Stored,
+};
+pub use history::{History, HistoryEntry, UndoPolicy};
+pub use interceptor::{Interceptor, Interceptors, Verdict};
+pub use kind::Kind;
+pub use machine::{HandleEvent, StateMachine};
+pub use metrics::{
+    DWELL_TIME_BUCKETS, DwellTime, TransitionMetrics,
+};
+pub use observer::Listeners;
+pub use persister::Persister;
+#[cfg(feature = "sqlite")]
+pub use persister::{
+    SqlitePersister, SqlitePersisterError,
+};
+#[cfg(feature = "tokio")]
+pub use publisher::StatePublisher;
+pub use registry::{MachineRegistry, RestoreError};
+pub use shared::Shared;
+pub use trace::{Divergence, Trace, TraceEntry};
@@ -0,0 +1,59 @@
+use crate::Kind;
+
+/// A machine generated by `event_driven_state_machine!`.
+///
+/// Lets generic code, such as [`MachineRegistry`], read
+/// the state and context of machines, and restore them.
+///
+/// [`MachineRegistry`]: crate::MachineRegistry
+pub trait StateMachine {
+    /// Whether the machine was generated with
+    /// `transactional: true`, i.e. whether a panic while
+    /// handling an event leaves its state and context as
+    /// they were before the event.
+    const TRANSACTIONAL: bool;
+
+    /// The context of the machine.
+    type Context;
+
+    /// The event enum of the machine.
+    type Event;
+
+    /// The outcome of handling an event.
+    type Outcome;
+
+    /// The state enum of the machine.
+    type State;
+
+    /// The kind enum of the state enum.
+    type StateKind: Kind;
+
+    /// The context of the machine.
+    fn context(&self) -> &Self::Context;
+
+    /// Replaces the state and context of the machine, e.g.
+    /// with ones loaded from a persister. No hooks are run,
+    /// and no transition is recorded or published, but the
+    /// rest of the machine (e.g., its observers) is kept.
+    fn restore_parts(
+        &mut self,
+        state: Self::State,
+        context: Self::Context,
+    );
+
+    /// The current state of the machine.
+    fn state(&self) -> &Self::State;
+
+    /// The kind of the current state of the machine.
+    fn state_kind(&self) -> Self::StateKind;
+}
+
+/// A [`StateMachine`] handling events synchronously, i.e.
+/// that isn't `async`.
+pub trait HandleEvent: StateMachine {
+    /// Handles `event`, and returns the outcome.
+    fn handle_event_outcome(
+        &mut self,
+        event: Self::Event,
+    ) -> Self::Outcome;
+}
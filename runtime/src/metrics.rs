@@ -0,0 +1,318 @@
+use crate::Kind;
+use core::time::Duration;
+use std::{collections::HashMap, time::Instant};
+
+/// The upper bounds of the [`DwellTime`] buckets. A last,
+/// unbounded bucket holds anything longer.
+pub const DWELL_TIME_BUCKETS: [Duration; 8] = [
+    Duration::from_millis(1),
+    Duration::from_millis(10),
+    Duration::from_millis(100),
+    Duration::from_secs(1),
+    Duration::from_secs(10),
+    Duration::from_secs(60),
+    Duration::from_secs(10 * 60),
+    Duration::from_secs(60 * 60),
+];
+
+/// A histogram of how long a machine stayed in a state,
+/// recorded each time it left the state.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DwellTime {
+    buckets: [u64; DWELL_TIME_BUCKETS.len() + 1],
+    count: u64,
+    max: Option<Duration>,
+    min: Option<Duration>,
+    total: Duration,
+}
+
+impl DwellTime {
+    /// The number of stays in each bucket, along with the
+    /// bucket's upper bound (`None` for the last bucket).
+    #[inline]
+    pub fn buckets(
+        &self,
+    ) -> impl Iterator<Item = (Option<Duration>, u64)> + '_
+    {
+        DWELL_TIME_BUCKETS
+            .iter()
+            .copied()
+            .map(Some)
+            .chain([None])
+            .zip(self.buckets.iter().copied())
+    }
+
+    /// How many stays were recorded.
+    #[must_use]
+    #[inline]
+    pub const fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// The longest recorded stay.
+    #[must_use]
+    #[inline]
+    pub const fn max(&self) -> Option<Duration> {
+        self.max
+    }
+
+    /// The average recorded stay.
+    #[must_use]
+    #[inline]
+    pub fn mean(&self) -> Option<Duration> {
+        let count = u32::try_from(self.count).ok()?;
+        self.total.checked_div(count)
+    }
+
+    /// The shortest recorded stay.
+    #[must_use]
+    #[inline]
+    pub const fn min(&self) -> Option<Duration> {
+        self.min
+    }
+
+    /// Records one stay of `elapsed`.
+    #[inline]
+    pub fn record(&mut self, elapsed: Duration) {
+        self.count = self.count.saturating_add(1);
+        self.total = self.total.saturating_add(elapsed);
+        self.min = Some(
+            self.min
+                .map_or(elapsed, |min| min.min(elapsed)),
+        );
+        self.max = Some(
+            self.max
+                .map_or(elapsed, |max| max.max(elapsed)),
+        );
+
+        let bucket = DWELL_TIME_BUCKETS
+            .iter()
+            .position(|bound| elapsed <= *bound)
+            .unwrap_or(DWELL_TIME_BUCKETS.len());
+
+        if let Some(count) = self.buckets.get_mut(bucket) {
+            *count = count.saturating_add(1);
+        }
+    }
+
+    /// The sum of all recorded stays.
+    #[must_use]
+    #[inline]
+    pub const fn total(&self) -> Duration {
+        self.total
+    }
+}
+
+/// Counts the transitions of a machine and how long it
+/// stays in each state.
+///
+/// Machines generated with `metrics: true` hold one of
+/// these and record every transition in it. With the
+/// `metrics` feature enabled, transitions are also
+/// exported through the [`metrics`](https://docs.rs/metrics)
+/// facade, as the `state_machine_transitions_total`
+/// counter and the `state_machine_dwell_seconds`
+/// histogram.
+///
+/// ```rust
+/// use core::time::Duration;
+/// use machine_factory_runtime::{
+///     Kind, TransitionMetrics,
+/// };
+/// use std::time::Instant;
+///
+/// #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// enum Light {
+///     Red,
+///     Green,
+/// }
+///
+/// impl Kind for Light {
+///     fn name(self) -> &'static str {
+///         match self {
+///             Self::Red => "Red",
+///             Self::Green => "Green",
+///         }
+///     }
+///
+///     fn index(self) -> usize {
+///         match self {
+///             Self::Red => 0,
+///             Self::Green => 1,
+///         }
+///     }
+///
+///     fn from_index(index: usize) -> Option<Self> {
+///         [Self::Red, Self::Green].get(index).copied()
+///     }
+/// }
+///
+/// let start = Instant::now();
+/// let mut metrics =
+///     TransitionMetrics::new("Light", start);
+///
+/// metrics.record(
+///     Light::Red,
+///     Light::Green,
+///     Light::Green,
+///     start + Duration::from_secs(3),
+/// );
+///
+/// assert_eq!(
+///     metrics.count(
+///         Light::Red,
+///         Light::Green,
+///         Light::Green
+///     ),
+///     1
+/// );
+///
+/// let red = metrics.dwell_time(Light::Red).unwrap();
+/// assert_eq!(red.total(), Duration::from_secs(3));
+/// ```
+#[derive(Debug, Clone)]
+pub struct TransitionMetrics<StateKind, EventKind> {
+    dwell_times: HashMap<StateKind, DwellTime>,
+    entered_at: Instant,
+    machine: &'static str,
+    transitions:
+        HashMap<(StateKind, EventKind, StateKind), u64>,
+}
+
+impl<StateKind: Kind, EventKind: Kind>
+    TransitionMetrics<StateKind, EventKind>
+{
+    /// How many times `event` moved the machine from
+    /// `from` to `to`.
+    #[must_use]
+    #[inline]
+    pub fn count(
+        &self,
+        from: StateKind,
+        event: EventKind,
+        to: StateKind,
+    ) -> u64 {
+        self.transitions
+            .get(&(from, event, to))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// How long the machine stayed in `state`, each time it
+    /// left it. The current stay isn't included until the
+    /// machine leaves.
+    #[must_use]
+    #[inline]
+    pub fn dwell_time(
+        &self,
+        state: StateKind,
+    ) -> Option<&DwellTime> {
+        self.dwell_times.get(&state)
+    }
+
+    /// Records the time spent in `from` until `now`, and
+    /// starts timing the current state.
+    fn leave(&mut self, from: StateKind, now: Instant) {
+        let elapsed =
+            now.saturating_duration_since(self.entered_at);
+        self.entered_at = now;
+
+        self.dwell_times
+            .entry(from)
+            .or_default()
+            .record(elapsed);
+
+        #[cfg(feature = "metrics")]
+        metrics::histogram!(
+            "state_machine_dwell_seconds",
+            "machine" => self.machine,
+            "state" => from.name(),
+        )
+        .record(elapsed.as_secs_f64());
+    }
+
+    /// The name of the machine.
+    #[must_use]
+    #[inline]
+    pub const fn machine(&self) -> &'static str {
+        self.machine
+    }
+
+    /// Creates metrics for the machine named `machine`,
+    /// which entered its current state at `now`.
+    #[must_use]
+    #[inline]
+    pub fn new(
+        machine: &'static str,
+        now: Instant,
+    ) -> Self {
+        Self {
+            machine,
+            transitions: HashMap::new(),
+            dwell_times: HashMap::new(),
+            entered_at: now,
+        }
+    }
+
+    /// Records a transition from `from` to `to`, triggered
+    /// by `event`, at `now`.
+    #[inline]
+    pub fn record(
+        &mut self,
+        from: StateKind,
+        event: EventKind,
+        to: StateKind,
+        now: Instant,
+    ) {
+        let count = self
+            .transitions
+            .entry((from, event, to))
+            .or_default();
+        *count = count.saturating_add(1);
+
+        #[cfg(feature = "metrics")]
+        metrics::counter!(
+            "state_machine_transitions_total",
+            "machine" => self.machine,
+            "from" => from.name(),
+            "event" => event.name(),
+            "to" => to.name(),
+        )
+        .increment(1);
+
+        self.leave(from, now);
+    }
+
+    /// Records that the machine left `from` at `now`
+    /// without an event (e.g., on an undo), so that only
+    /// its dwell time is recorded.
+    #[inline]
+    pub fn record_restore(
+        &mut self,
+        from: StateKind,
+        now: Instant,
+    ) {
+        self.leave(from, now);
+    }
+
+    /// Starts timing the current state again from `now`,
+    /// e.g. after the machine's clock was replaced.
+    #[inline]
+    pub const fn restart(&mut self, now: Instant) {
+        self.entered_at = now;
+    }
+
+    /// Every recorded `(from, event, to)` transition, along
+    /// with how many times it happened, in no particular
+    /// order.
+    #[inline]
+    pub fn transitions(
+        &self,
+    ) -> impl Iterator<
+        Item = ((StateKind, EventKind, StateKind), u64),
+    > + '_ {
+        self.transitions.iter().map(
+            |(transition, count)| (*transition, *count),
+        )
+    }
+}
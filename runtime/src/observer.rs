@@ -0,0 +1,61 @@
+use alloc::sync::Arc;
+use core::fmt;
+
+/// Listeners notified of each transition of a
+/// (non-`async`) machine generated with
+/// `observable: true`.
+///
+/// Clones of a machine start without listeners.
+pub struct Listeners<Transition> {
+    listeners: Vec<Listener<Transition>>,
+}
+
+type Listener<Transition> =
+    Arc<dyn Fn(&Transition) + Send + Sync>;
+
+impl<Transition> Listeners<Transition> {
+    /// Adds a listener, which is called after every
+    /// transition.
+    #[inline]
+    pub fn add<Listener>(&mut self, listener: Listener)
+    where
+        Listener: Fn(&Transition) + Send + Sync + 'static,
+    {
+        self.listeners.push(Arc::new(listener));
+    }
+
+    /// Calls every listener with `transition`, in the order
+    /// they were added.
+    #[inline]
+    pub fn notify(&self, transition: &Transition) {
+        for listener in &self.listeners {
+            listener(transition);
+        }
+    }
+}
+
+impl<Transition> Default for Listeners<Transition> {
+    #[inline]
+    fn default() -> Self {
+        Self { listeners: Vec::new() }
+    }
+}
+
+impl<Transition> Clone for Listeners<Transition> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl<Transition> fmt::Debug for Listeners<Transition> {
+    #[inline]
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        f.debug_struct("Listeners")
+            .field("len", &self.listeners.len())
+            .finish()
+    }
+}
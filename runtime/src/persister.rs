@@ -0,0 +1,343 @@
+#[cfg(feature = "sqlite")]
+use alloc::sync::Arc;
+#[cfg(feature = "sqlite")]
+use core::{error::Error, fmt, marker::PhantomData};
+#[cfg(feature = "sqlite")]
+use rusqlite::{
+    Connection, OptionalExtension as _, params,
+};
+#[cfg(feature = "sqlite")]
+use serde::{Serialize, de::DeserializeOwned};
+#[cfg(feature = "sqlite")]
+use std::{
+    path::Path,
+    sync::{Mutex, PoisonError},
+};
+
+/// Stores the state and context of machines, keyed by the
+/// machines' ids.
+///
+/// Machines generated with a `persister` label save
+/// themselves with their persister after every transition,
+/// and can be loaded back from it. Persisters are cloned
+/// to be shared by the machines they load, so they should
+/// be handles to the underlying storage.
+pub trait Persister<State, Context>: Clone {
+    /// The error returned when the storage can't be read
+    /// or written.
+    type Error;
+
+    /// Loads the state and context of the machine `id`, if
+    /// it was saved.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the storage couldn't be read.
+    fn load(
+        &self,
+        id: &str,
+    ) -> Result<Option<(State, Context)>, Self::Error>;
+
+    /// Loads every saved machine, along with its id.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the storage couldn't be read.
+    fn load_all(
+        &self,
+    ) -> Result<Vec<(String, State, Context)>, Self::Error>;
+
+    /// Removes the machine `id`, if it was saved.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the machine couldn't be
+    /// removed.
+    fn remove(
+        &mut self,
+        id: &str,
+    ) -> Result<(), Self::Error>;
+
+    /// Saves the state and context of the machine `id`,
+    /// replacing what was saved before.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the machine couldn't be saved.
+    fn save(
+        &mut self,
+        id: &str,
+        state: &State,
+        context: &Context,
+    ) -> Result<(), Self::Error>;
+}
+
+/// Returned by [`SqlitePersister`].
+#[cfg(feature = "sqlite")]
+#[derive(Debug)]
+pub enum SqlitePersisterError {
+    /// A state or context couldn't be converted to or from
+    /// JSON.
+    Json(serde_json::Error),
+    /// The database couldn't be read or written.
+    Sqlite(rusqlite::Error),
+}
+
+#[cfg(feature = "sqlite")]
+impl fmt::Display for SqlitePersisterError {
+    #[inline]
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        match self {
+            Self::Sqlite(error) => {
+                write!(f, "sqlite error: {error}")
+            }
+            Self::Json(error) => {
+                write!(f, "json error: {error}")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl Error for SqlitePersisterError {
+    #[inline]
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Sqlite(error) => Some(error),
+            Self::Json(error) => Some(error),
+        }
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl From<rusqlite::Error> for SqlitePersisterError {
+    #[inline]
+    fn from(error: rusqlite::Error) -> Self {
+        Self::Sqlite(error)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl From<serde_json::Error> for SqlitePersisterError {
+    #[inline]
+    fn from(error: serde_json::Error) -> Self {
+        Self::Json(error)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+type PersistedTypes<State, Context> =
+    fn() -> (State, Context);
+
+/// A [`Persister`] saving machines as JSON in a `SQLite`
+/// database.
+///
+/// Machines are saved in a `machines` table shared by
+/// every kind of machine: rows are keyed by the `machine`
+/// name given to [`SqlitePersister::open`], and the
+/// machine's id.
+///
+/// Every save is a single statement, so it either fully
+/// replaces the saved machine or doesn't change it.
+///
+/// Requires the `sqlite` feature.
+#[cfg(feature = "sqlite")]
+#[derive(Debug)]
+pub struct SqlitePersister<State, Context> {
+    connection: Arc<Mutex<Connection>>,
+    machine: String,
+    types: PhantomData<PersistedTypes<State, Context>>,
+}
+
+#[cfg(feature = "sqlite")]
+impl<State, Context> SqlitePersister<State, Context> {
+    /// The name of the persisted machines.
+    #[must_use]
+    #[inline]
+    pub fn machine(&self) -> &str {
+        &self.machine
+    }
+
+    /// Opens the database at `path`, creating it if
+    /// needed, to persist the machines named `machine`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database couldn't be opened,
+    /// or the table couldn't be created.
+    #[inline]
+    pub fn open<DatabasePath: AsRef<Path>>(
+        path: DatabasePath,
+        machine: &str,
+    ) -> Result<Self, SqlitePersisterError> {
+        Self::with_connection(
+            Connection::open(path)?,
+            machine,
+        )
+    }
+
+    /// Persists the machines named `machine` with an open
+    /// `connection`, e.g. to an in-memory database.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the table couldn't be created.
+    #[inline]
+    pub fn with_connection(
+        connection: Connection,
+        machine: &str,
+    ) -> Result<Self, SqlitePersisterError> {
+        _ = connection.execute(
+            "CREATE TABLE IF NOT EXISTS machines (
+                machine TEXT NOT NULL,
+                id TEXT NOT NULL,
+                state TEXT NOT NULL,
+                context TEXT NOT NULL,
+                PRIMARY KEY (machine, id)
+            )",
+            (),
+        )?;
+
+        Ok(Self {
+            connection: Arc::new(Mutex::new(connection)),
+            machine: machine.to_owned(),
+            types: PhantomData,
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<State, Context> Clone
+    for SqlitePersister<State, Context>
+{
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            connection: Arc::clone(&self.connection),
+            machine: self.machine.clone(),
+            types: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<State, Context> Persister<State, Context>
+    for SqlitePersister<State, Context>
+where
+    State: Serialize + DeserializeOwned,
+    Context: Serialize + DeserializeOwned,
+{
+    type Error = SqlitePersisterError;
+
+    #[inline]
+    fn load(
+        &self,
+        id: &str,
+    ) -> Result<Option<(State, Context)>, Self::Error> {
+        let row = self
+            .connection
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .query_row(
+                "SELECT state, context FROM machines
+                WHERE machine = ?1 AND id = ?2",
+                params![self.machine, id],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                    ))
+                },
+            )
+            .optional()?;
+
+        row.map(|(state, context)| {
+            Ok((
+                serde_json::from_str(&state)?,
+                serde_json::from_str(&context)?,
+            ))
+        })
+        .transpose()
+    }
+
+    #[inline]
+    fn load_all(
+        &self,
+    ) -> Result<Vec<(String, State, Context)>, Self::Error>
+    {
+        let connection = self
+            .connection
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let mut statement = connection.prepare(
+            "SELECT id, state, context FROM machines
+            WHERE machine = ?1 ORDER BY id",
+        )?;
+        let rows = statement
+            .query_map(params![self.machine], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        drop(statement);
+        drop(connection);
+
+        rows.into_iter()
+            .map(|(id, state, context)| {
+                Ok((
+                    id,
+                    serde_json::from_str(&state)?,
+                    serde_json::from_str(&context)?,
+                ))
+            })
+            .collect()
+    }
+
+    #[inline]
+    fn remove(
+        &mut self,
+        id: &str,
+    ) -> Result<(), Self::Error> {
+        _ = self
+            .connection
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .execute(
+                "DELETE FROM machines WHERE machine = ?1 AND id = ?2",
+                params![self.machine, id],
+            )?;
+
+        Ok(())
+    }
+
+    #[inline]
+    fn save(
+        &mut self,
+        id: &str,
+        state: &State,
+        context: &Context,
+    ) -> Result<(), Self::Error> {
+        let state = serde_json::to_string(state)?;
+        let context = serde_json::to_string(context)?;
+
+        _ = self
+            .connection
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .execute(
+                "INSERT INTO machines (machine, id, state, context)
+                VALUES (?1, ?2, ?3, ?4)
+                ON CONFLICT (machine, id) DO UPDATE
+                SET state = excluded.state, context = excluded.context",
+                params![self.machine, id, state, context],
+            )?;
+
+        Ok(())
+    }
+}
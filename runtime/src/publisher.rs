@@ -0,0 +1,85 @@
+use tokio::sync::{broadcast, watch};
+
+/// Publishes the state of an `async` machine generated
+/// with `observable: true`.
+///
+/// The current state kind is published through a `watch`
+/// channel, and each transition through a `broadcast`
+/// channel. Clones of a machine start without
+/// subscribers.
+#[derive(Debug)]
+pub struct StatePublisher<Kind, Transition> {
+    state: watch::Sender<Kind>,
+    transitions: broadcast::Sender<Transition>,
+}
+
+impl<Kind, Transition> StatePublisher<Kind, Transition>
+where
+    Transition: Clone,
+{
+    /// The number of transitions a lagging subscriber can
+    /// fall behind before it starts missing them.
+    pub const TRANSITIONS_CAPACITY: usize = 64;
+
+    /// Creates a publisher whose current state is
+    /// `initial`.
+    #[must_use]
+    #[inline]
+    pub fn new(initial: Kind) -> Self {
+        let (state, _) = watch::channel(initial);
+        let (transitions, _) =
+            broadcast::channel(Self::TRANSITIONS_CAPACITY);
+
+        Self { state, transitions }
+    }
+
+    /// Publishes a transition into the `to` state.
+    #[inline]
+    pub fn publish(
+        &self,
+        to: Kind,
+        transition: Transition,
+    ) {
+        drop(self.state.send_replace(to));
+        // An error only means that there are no
+        // subscribers.
+        drop(self.transitions.send(transition));
+    }
+
+    /// Publishes `current` as the current state kind,
+    /// without a transition, e.g. after the machine's state
+    /// was replaced.
+    #[inline]
+    pub fn reset(&self, current: Kind) {
+        drop(self.state.send_replace(current));
+    }
+
+    /// Subscribes to the current state kind.
+    #[must_use]
+    #[inline]
+    pub fn subscribe(&self) -> watch::Receiver<Kind> {
+        self.state.subscribe()
+    }
+
+    /// Subscribes to the transitions that happen after this
+    /// call.
+    #[must_use]
+    #[inline]
+    pub fn subscribe_transitions(
+        &self,
+    ) -> broadcast::Receiver<Transition> {
+        self.transitions.subscribe()
+    }
+}
+
+impl<Kind, Transition> Clone
+    for StatePublisher<Kind, Transition>
+where
+    Kind: Clone,
+    Transition: Clone,
+{
+    #[inline]
+    fn clone(&self) -> Self {
+        Self::new(self.state.borrow().clone())
+    }
+}
@@ -0,0 +1,318 @@
+use crate::{HandleEvent, Persister, StateMachine};
+use alloc::boxed::Box;
+use core::{
+    error::Error,
+    fmt::{self, Display},
+    hash::Hash,
+    mem,
+    str::FromStr,
+};
+use std::collections::{HashMap, hash_map::Entry};
+
+type Factory<Id, Machine> =
+    Box<dyn FnMut(&Id) -> Machine + Send>;
+type Finished<Machine> =
+    Box<dyn Fn(&Machine) -> bool + Send>;
+
+/// Returned by [`MachineRegistry::restore`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RestoreError<PersisterError, IdError> {
+    /// A saved id couldn't be parsed.
+    Id(IdError),
+    /// The persister couldn't load the machines.
+    Persister(PersisterError),
+}
+
+impl<PersisterError: Display, IdError: Display> Display
+    for RestoreError<PersisterError, IdError>
+{
+    #[inline]
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        match self {
+            Self::Persister(error) => {
+                write!(
+                    f,
+                    "couldn't load the machines: {error}"
+                )
+            }
+            Self::Id(error) => {
+                write!(f, "invalid machine id: {error}")
+            }
+        }
+    }
+}
+
+impl<PersisterError, IdError> Error
+    for RestoreError<PersisterError, IdError>
+where
+    PersisterError: fmt::Debug + Display,
+    IdError: fmt::Debug + Display,
+{
+}
+
+/// Machines keyed by id, e.g. one per connection or order.
+///
+/// Events are routed to machines by id, and a machine is
+/// created by the registry's factory the first time an
+/// event is routed to it. Machines that the registry's
+/// `finished` predicate (see [`MachineRegistry::evicting`])
+/// holds for are removed after handling an event, or with
+/// [`MachineRegistry::evict_finished`].
+///
+/// [`MachineRegistry::handle_event`] requires a
+/// synchronous machine; `async` machines are reached with
+/// [`MachineRegistry::get_or_create`] instead.
+pub struct MachineRegistry<Id, Machine> {
+    factory: Factory<Id, Machine>,
+    finished: Option<Finished<Machine>>,
+    machines: HashMap<Id, Machine>,
+}
+
+impl<Id: Eq + Hash, Machine> MachineRegistry<Id, Machine> {
+    /// Removes the finished machines, and returns them
+    /// along with their ids.
+    #[inline]
+    pub fn evict_finished(&mut self) -> Vec<(Id, Machine)> {
+        let Some(finished) = self.finished.as_ref() else {
+            return Vec::new();
+        };
+
+        let (evicted, machines): (HashMap<_, _>, _) =
+            mem::take(&mut self.machines)
+                .into_iter()
+                .partition(|(_, machine)| {
+                    finished(machine)
+                });
+        self.machines = machines;
+
+        evicted.into_iter().collect()
+    }
+
+    /// Evicts the machines `finished` holds for.
+    #[must_use]
+    #[inline]
+    pub fn evicting<IsFinished>(
+        mut self,
+        finished: IsFinished,
+    ) -> Self
+    where
+        IsFinished: Fn(&Machine) -> bool + Send + 'static,
+    {
+        self.finished = Some(Box::new(finished));
+        self
+    }
+
+    /// The machine `id`, if there is one.
+    #[must_use]
+    #[inline]
+    pub fn get(&self, id: &Id) -> Option<&Machine> {
+        self.machines.get(id)
+    }
+
+    /// The machine `id`, if there is one.
+    #[inline]
+    pub fn get_mut(
+        &mut self,
+        id: &Id,
+    ) -> Option<&mut Machine> {
+        self.machines.get_mut(id)
+    }
+
+    /// The machine `id`, created with the factory if there
+    /// is none.
+    #[inline]
+    pub fn get_or_create(
+        &mut self,
+        id: Id,
+    ) -> &mut Machine {
+        let factory = &mut self.factory;
+        self.machines
+            .entry(id)
+            .or_insert_with_key(|id| factory(id))
+    }
+
+    /// Adds the machine `id`, returning the machine it
+    /// replaced, if any.
+    #[inline]
+    pub fn insert(
+        &mut self,
+        id: Id,
+        machine: Machine,
+    ) -> Option<Machine> {
+        self.machines.insert(id, machine)
+    }
+
+    /// Whether there are no machines.
+    #[must_use]
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.machines.is_empty()
+    }
+
+    /// Every machine, along with its id, in no particular
+    /// order.
+    #[inline]
+    pub fn iter(
+        &self,
+    ) -> impl Iterator<Item = (&Id, &Machine)> {
+        self.machines.iter()
+    }
+
+    /// How many machines there are.
+    #[must_use]
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.machines.len()
+    }
+
+    /// Creates an empty registry, creating machines with
+    /// `factory`.
+    #[must_use]
+    #[inline]
+    pub fn new<NewMachine>(factory: NewMachine) -> Self
+    where
+        NewMachine: FnMut(&Id) -> Machine + Send + 'static,
+    {
+        Self {
+            machines: HashMap::new(),
+            factory: Box::new(factory),
+            finished: None,
+        }
+    }
+
+    /// Removes the machine `id`, if there is one.
+    #[inline]
+    pub fn remove(&mut self, id: &Id) -> Option<Machine> {
+        self.machines.remove(id)
+    }
+}
+
+impl<Id, Machine> MachineRegistry<Id, Machine>
+where
+    Id: Eq + Hash,
+    Machine: HandleEvent,
+{
+    /// Routes `event` to the machine `id`, created with the
+    /// factory if there is none, and returns the outcome.
+    /// The machine is removed if it is then finished.
+    #[inline]
+    pub fn handle_event<Event: Into<Machine::Event>>(
+        &mut self,
+        id: Id,
+        event: Event,
+    ) -> Machine::Outcome {
+        let mut entry = match self.machines.entry(id) {
+            Entry::Occupied(entry) => entry,
+            Entry::Vacant(entry) => {
+                let machine = (self.factory)(entry.key());
+                entry.insert_entry(machine)
+            }
+        };
+        let outcome = entry
+            .get_mut()
+            .handle_event_outcome(event.into());
+
+        if self
+            .finished
+            .as_ref()
+            .is_some_and(|finished| finished(entry.get()))
+        {
+            drop(entry.remove());
+        }
+
+        outcome
+    }
+}
+
+impl<Id, Machine> MachineRegistry<Id, Machine>
+where
+    Id: Eq + Hash + Display + FromStr,
+    Machine: StateMachine,
+{
+    /// Adds every machine saved with `persister`, replacing
+    /// the machines with the same ids, and returns how many
+    /// there were. Each machine is created with the
+    /// factory, and then given its saved state and
+    /// context.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the persister couldn't load the
+    /// machines, or a saved id couldn't be parsed, in which
+    /// case no machine is added.
+    #[inline]
+    pub fn restore<Store>(
+        &mut self,
+        persister: &Store,
+    ) -> Result<usize, RestoreError<Store::Error, Id::Err>>
+    where
+        Store: Persister<Machine::State, Machine::Context>,
+    {
+        let machines = persister
+            .load_all()
+            .map_err(RestoreError::Persister)?
+            .into_iter()
+            .map(|(id, state, context)| {
+                let id =
+                    id.parse().map_err(RestoreError::Id)?;
+                // The factory configures the machine (e.g.,
+                // its clock or observers), and the saved
+                // state and context replace its own.
+                let mut machine = (self.factory)(&id);
+                machine.restore_parts(state, context);
+                Ok((id, machine))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let restored = machines.len();
+        self.machines.extend(machines);
+
+        Ok(restored)
+    }
+
+    /// Saves every machine with `persister`, keyed by its
+    /// id's string.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error of the persister.
+    #[inline]
+    pub fn save<Store>(
+        &self,
+        persister: &mut Store,
+    ) -> Result<(), Store::Error>
+    where
+        Store: Persister<Machine::State, Machine::Context>,
+    {
+        self.machines.iter().try_for_each(
+            |(id, machine)| {
+                persister.save(
+                    &id.to_string(),
+                    machine.state(),
+                    machine.context(),
+                )
+            },
+        )
+    }
+}
+
+impl<Id: fmt::Debug, Machine> fmt::Debug
+    for MachineRegistry<Id, Machine>
+{
+    #[inline]
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        f.debug_struct("MachineRegistry")
+            .field(
+                "ids",
+                &self.machines.keys().collect::<Vec<_>>(),
+            )
+            .field("evicting", &self.finished.is_some())
+            .finish_non_exhaustive()
+    }
+}
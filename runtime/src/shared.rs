@@ -0,0 +1,212 @@
+use crate::{HandleEvent, Kind as _, StateMachine};
+use alloc::sync::Arc;
+use core::{
+    fmt,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use std::sync::{Mutex, MutexGuard, PoisonError, mpsc};
+
+/// A machine that can be shared between threads, generated
+/// for (non-`async`) machines with `shared: true`.
+///
+/// Events are handled one at a time, behind a lock, but
+/// the kind of the current state is also kept in an atomic,
+/// so [`Shared::state_kind`] never waits for an event to be
+/// handled. Clones refer to the same machine.
+///
+/// If handling an event panics, the machine is only used
+/// again if it's transactional (see
+/// [`StateMachine::TRANSACTIONAL`]): a panic leaves other
+/// machines without a state, so the methods that reach the
+/// machine then panic too.
+pub struct Shared<Machine: StateMachine> {
+    inner: Arc<Inner<Machine>>,
+}
+
+struct Inner<Machine: StateMachine> {
+    machine: Mutex<Machine>,
+    state_kind: AtomicUsize,
+    subscribers:
+        Mutex<Vec<mpsc::Sender<Machine::StateKind>>>,
+}
+
+impl<Machine: StateMachine> Shared<Machine> {
+    fn lock_machine(&self) -> MutexGuard<'_, Machine> {
+        self.inner
+            .machine
+            .lock()
+            .unwrap_or_else(recover::<Machine, _>)
+    }
+
+    /// Shares `machine`.
+    #[must_use]
+    #[inline]
+    pub fn new(machine: Machine) -> Self {
+        let state_kind = machine.state_kind().index();
+
+        Self {
+            inner: Arc::new(Inner {
+                machine: Mutex::new(machine),
+                state_kind: AtomicUsize::new(state_kind),
+                subscribers: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+
+    fn publish(&self, state_kind: Machine::StateKind) {
+        let previous = self
+            .inner
+            .state_kind
+            .swap(state_kind.index(), Ordering::AcqRel);
+
+        if previous != state_kind.index() {
+            // Dropped receivers are unsubscribed.
+            lock(&self.inner.subscribers).retain(
+                |subscriber| {
+                    subscriber.send(state_kind).is_ok()
+                },
+            );
+        }
+    }
+
+    /// The kind of the current state, read without taking
+    /// the lock.
+    ///
+    /// # Panics
+    ///
+    /// Never, unless the generated `Kind` implementation is
+    /// inconsistent.
+    #[must_use]
+    #[inline]
+    pub fn state_kind(&self) -> Machine::StateKind {
+        let index =
+            self.inner.state_kind.load(Ordering::Acquire);
+        Machine::StateKind::from_index(index)
+            .expect("state kind index is out of range")
+    }
+
+    /// Subscribes to state changes: the receiver gets the
+    /// kind of each state the machine moves into, after
+    /// this call.
+    ///
+    /// Events that leave the machine in a state of the same
+    /// kind aren't reported.
+    #[must_use]
+    #[inline]
+    pub fn subscribe(
+        &self,
+    ) -> mpsc::Receiver<Machine::StateKind> {
+        let (sender, receiver) = mpsc::channel();
+        lock(&self.inner.subscribers).push(sender);
+        receiver
+    }
+
+    /// Unwraps the machine, if there are no other clones of
+    /// this handle.
+    ///
+    /// # Errors
+    ///
+    /// Returns the handle back if it has other clones.
+    ///
+    /// # Panics
+    ///
+    /// If handling an event panicked, and the machine isn't
+    /// transactional.
+    #[inline]
+    pub fn try_into_inner(self) -> Result<Machine, Self> {
+        Arc::try_unwrap(self.inner)
+            .map(|inner| {
+                inner
+                    .machine
+                    .into_inner()
+                    .unwrap_or_else(recover::<Machine, _>)
+            })
+            .map_err(|inner| Self { inner })
+    }
+
+    /// Calls `f` with the machine, e.g. to read its state
+    /// or context. Events are not handled while `f` runs.
+    ///
+    /// # Panics
+    ///
+    /// If handling an event panicked, and the machine isn't
+    /// transactional.
+    #[inline]
+    pub fn with<Output, F>(&self, f: F) -> Output
+    where
+        F: FnOnce(&Machine) -> Output,
+    {
+        f(&self.lock_machine())
+    }
+}
+
+impl<Machine: HandleEvent> Shared<Machine> {
+    /// Handles `event`, waiting for any other event being
+    /// handled to finish, and returns the outcome.
+    ///
+    /// # Panics
+    ///
+    /// If handling `event` panics, or an earlier event
+    /// panicked and the machine isn't transactional.
+    #[inline]
+    pub fn handle_event<Event>(
+        &self,
+        event: Event,
+    ) -> Machine::Outcome
+    where
+        Event: Into<Machine::Event>,
+    {
+        let mut machine = self.lock_machine();
+        let outcome =
+            machine.handle_event_outcome(event.into());
+        // Publishing while still holding the lock keeps the
+        // order of notifications the order of transitions.
+        self.publish(machine.state_kind());
+        drop(machine);
+        outcome
+    }
+}
+
+impl<Machine: StateMachine> Clone for Shared<Machine> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self { inner: Arc::clone(&self.inner) }
+    }
+}
+
+impl<Machine: StateMachine> fmt::Debug for Shared<Machine>
+where
+    Machine::StateKind: fmt::Debug,
+{
+    #[inline]
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        f.debug_struct("Shared")
+            .field("state_kind", &self.state_kind())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Locks `mutex`, ignoring poisoning: subscribers are only
+/// ever pushed or removed.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Recovers the machine from a poisoned lock, if it's
+/// transactional: the panic then restored its state and
+/// context. Otherwise, the panic is propagated, since the
+/// machine lost its state.
+fn recover<Machine: StateMachine, Guard>(
+    poisoned: PoisonError<Guard>,
+) -> Guard {
+    assert!(
+        Machine::TRANSACTIONAL,
+        "a panic while handling an event left the machine \
+         without a state; set `transactional: true` to \
+         recover from it"
+    );
+    poisoned.into_inner()
+}
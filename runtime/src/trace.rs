@@ -0,0 +1,129 @@
+use core::{error::Error, fmt};
+
+/// An event handled by a machine, along with its outcome.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct TraceEntry<Event, Outcome> {
+    /// The handled event.
+    pub event: Event,
+    /// The outcome of handling `event`.
+    pub outcome: Outcome,
+}
+
+/// The events handled by a machine, in order, along with
+/// their outcomes.
+///
+/// Machines generated with `recording: true` can record a
+/// trace of the events they handle, which can then be
+/// replayed on a machine in the same initial state to
+/// reproduce how it got to its current state. With the
+/// `serde` feature enabled, traces can be serialized, e.g.
+/// to be logged.
+///
+/// ```rust
+/// use machine_factory_runtime::Trace;
+///
+/// let mut trace = Trace::new();
+/// trace.push("open", true);
+/// trace.push("open", false);
+///
+/// assert_eq!(trace.len(), 2);
+/// assert!(!trace.entries()[1].outcome);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct Trace<Event, Outcome> {
+    entries: Vec<TraceEntry<Event, Outcome>>,
+}
+
+impl<Event, Outcome> Trace<Event, Outcome> {
+    /// The recorded entries, in the order the events were
+    /// handled.
+    #[must_use]
+    #[inline]
+    pub fn entries(&self) -> &[TraceEntry<Event, Outcome>] {
+        &self.entries
+    }
+
+    /// Whether no events were recorded.
+    #[must_use]
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// How many events were recorded.
+    #[must_use]
+    #[inline]
+    pub const fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Creates an empty trace.
+    #[must_use]
+    #[inline]
+    pub const fn new() -> Self {
+        Self { entries: Vec::new() }
+    }
+
+    /// Records that `event` was handled with `outcome`.
+    #[inline]
+    pub fn push(&mut self, event: Event, outcome: Outcome) {
+        self.entries.push(TraceEntry { event, outcome });
+    }
+}
+
+impl<Event, Outcome> Default for Trace<Event, Outcome> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returned when replaying a [`Trace`] handles an event
+/// with a different outcome than the recorded one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence<Event, Outcome> {
+    /// The outcome of the replay.
+    pub actual: Outcome,
+    /// The event that was handled differently.
+    pub event: Event,
+    /// The recorded outcome.
+    pub expected: Outcome,
+    /// The index of the event in the trace.
+    pub index: usize,
+}
+
+impl<Event: fmt::Debug, Outcome: fmt::Debug> fmt::Display
+    for Divergence<Event, Outcome>
+{
+    #[expect(
+        clippy::use_debug,
+        reason = "events and outcomes are only required to be `Debug`"
+    )]
+    #[inline]
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        write!(
+            f,
+            "event {} ({:?}) diverged: expected {:?}, got {:?}",
+            self.index,
+            self.event,
+            self.expected,
+            self.actual
+        )
+    }
+}
+
+impl<Event: fmt::Debug, Outcome: fmt::Debug> Error
+    for Divergence<Event, Outcome>
+{
+}
@@ -0,0 +1,137 @@
+use quote::{format_ident, quote};
+use syn::{Ident, Visibility};
+
+pub struct ActorInput {
+    pub event_enum_ident: Ident,
+    pub machine_ident: Ident,
+    pub outcome_ident: Ident,
+    pub state_enum_ident: Ident,
+    pub visibility: Option<Visibility>,
+}
+
+pub fn actor(input: ActorInput) -> proc_macro2::TokenStream {
+    let ActorInput {
+        visibility,
+        machine_ident,
+        state_enum_ident,
+        event_enum_ident,
+        outcome_ident,
+    } = input;
+
+    let handle_ident = format_ident!("{}Handle", machine_ident);
+    let message_ident =
+        format_ident!("{}ActorMessage", machine_ident);
+
+    quote! {
+        enum #message_ident {
+            Event(#event_enum_ident, ::core::option::Option<::tokio::sync::oneshot::Sender<#outcome_ident>>),
+            Inspect(::std::boxed::Box<dyn ::core::ops::FnOnce(&#machine_ident) + ::core::marker::Send>),
+            Shutdown(::tokio::sync::oneshot::Sender<#machine_ident>),
+        }
+
+        #[derive(Debug, Clone)]
+        #visibility struct #handle_ident {
+            sender: ::tokio::sync::mpsc::Sender<#message_ident>,
+        }
+
+        impl #machine_ident {
+            pub fn spawn(self) -> #handle_ident {
+                self.spawn_with_capacity(::machine_factory_runtime::DEFAULT_MAILBOX_CAPACITY)
+            }
+
+            pub fn spawn_with_capacity(self, capacity: ::core::num::NonZeroUsize) -> #handle_ident {
+                let (sender, mut receiver) = ::tokio::sync::mpsc::channel(capacity.get());
+
+                _ = ::tokio::spawn(async move {
+                    let mut machine = self;
+                    let mut shutdown = ::core::option::Option::None;
+
+                    while let ::core::option::Option::Some(message) = receiver.recv().await {
+                        match message {
+                            #message_ident::Event(event, reply) => {
+                                let outcome = machine.handle_event_outcome(event).await;
+
+                                if let ::core::option::Option::Some(reply) = reply {
+                                    _ = reply.send(outcome);
+                                }
+                            }
+                            #message_ident::Inspect(inspect) => inspect(&machine),
+                            #message_ident::Shutdown(reply) => {
+                                // Stop accepting messages, but handle the ones that are
+                                // already in the mailbox before shutting down.
+                                receiver.close();
+                                shutdown = shutdown.or(::core::option::Option::Some(reply));
+                            }
+                        }
+                    }
+
+                    if let ::core::option::Option::Some(reply) = shutdown {
+                        _ = reply.send(machine);
+                    }
+                });
+
+                #handle_ident { sender }
+            }
+        }
+
+        impl #handle_ident {
+            pub async fn send<Event: Into<#event_enum_ident>>(
+                &self,
+                event: Event,
+            ) -> ::core::result::Result<(), ::machine_factory_runtime::ActorStopped> {
+                self.sender
+                    .send(#message_ident::Event(event.into(), ::core::option::Option::None))
+                    .await
+                    .map_err(|_| ::machine_factory_runtime::ActorStopped)
+            }
+
+            pub async fn ask<Event: Into<#event_enum_ident>>(
+                &self,
+                event: Event,
+            ) -> ::core::result::Result<#outcome_ident, ::machine_factory_runtime::ActorStopped> {
+                let (reply, response) = ::tokio::sync::oneshot::channel();
+
+                self.sender
+                    .send(#message_ident::Event(event.into(), ::core::option::Option::Some(reply)))
+                    .await
+                    .map_err(|_| ::machine_factory_runtime::ActorStopped)?;
+
+                response.await.map_err(|_| ::machine_factory_runtime::ActorStopped)
+            }
+
+            // The higher-ranked bound defers the `Clone` requirement to the
+            // callers of this method, instead of every actor machine.
+            pub async fn state_snapshot(
+                &self,
+            ) -> ::core::result::Result<#state_enum_ident, ::machine_factory_runtime::ActorStopped>
+            where
+                for<'state> #state_enum_ident: ::core::clone::Clone,
+            {
+                let (reply, response) = ::tokio::sync::oneshot::channel();
+                let inspect = move |machine: &#machine_ident| {
+                    _ = reply.send(::core::clone::Clone::clone(machine.state()));
+                };
+
+                self.sender
+                    .send(#message_ident::Inspect(::std::boxed::Box::new(inspect)))
+                    .await
+                    .map_err(|_| ::machine_factory_runtime::ActorStopped)?;
+
+                response.await.map_err(|_| ::machine_factory_runtime::ActorStopped)
+            }
+
+            pub async fn shutdown(
+                &self,
+            ) -> ::core::result::Result<#machine_ident, ::machine_factory_runtime::ActorStopped> {
+                let (reply, response) = ::tokio::sync::oneshot::channel();
+
+                self.sender
+                    .send(#message_ident::Shutdown(reply))
+                    .await
+                    .map_err(|_| ::machine_factory_runtime::ActorStopped)?;
+
+                response.await.map_err(|_| ::machine_factory_runtime::ActorStopped)
+            }
+        }
+    }
+}
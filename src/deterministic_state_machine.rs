@@ -1,3 +1,7 @@
+use crate::introspection::{
+    introspection, Introspection, IntrospectionInput,
+    TransitionDescription,
+};
 use proc_macro::TokenStream;
 use quote::quote;
 use syn::{
@@ -6,8 +10,9 @@ use syn::{
     parse_macro_input,
     punctuated::Punctuated,
     token::Comma,
-    Attribute, Ident, ImplItemFn, ItemTrait, Path, Token,
-    Visibility,
+    Attribute, FnArg, GenericArgument, Ident, ImplItemFn,
+    ItemTrait, Path, PathArguments, ReturnType, Signature,
+    Token, Type, Visibility,
 };
 
 struct Machine {
@@ -139,7 +144,117 @@ impl Parse for StateTransitions {
     }
 }
 
-#[allow(clippy::too_many_lines)]
+/// Finds the state of the machine named `name` in a
+/// transition's return type (e.g., `Pending` in
+/// `Result<HttpRequest<Pending>, Error>`).
+#[expect(
+    clippy::wildcard_enum_match_arm,
+    reason = "other types and generic arguments can't name a state"
+)]
+fn target_state(ty: &Type, name: &Ident) -> Option<Ident> {
+    match ty {
+        Type::Group(group) => target_state(&group.elem, name),
+        Type::Paren(paren) => target_state(&paren.elem, name),
+        Type::Tuple(tuple) => tuple
+            .elems
+            .iter()
+            .find_map(|elem| target_state(elem, name)),
+        Type::Path(path) => {
+            path.path.segments.iter().find_map(|segment| {
+                let PathArguments::AngleBracketed(args) =
+                    &segment.arguments
+                else {
+                    return None;
+                };
+
+                let mut types =
+                    args.args.iter().filter_map(|arg| match arg {
+                        GenericArgument::Type(ty) => Some(ty),
+                        _ => None,
+                    });
+
+                if segment.ident == *name {
+                    let Some(Type::Path(state)) = types.next()
+                    else {
+                        return None;
+                    };
+
+                    return state
+                        .path
+                        .segments
+                        .last()
+                        .map(|s| s.ident.clone());
+                }
+
+                types.find_map(|ty| target_state(ty, name))
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Whether `ty` mentions the machine named `name` (or
+/// `Self`), e.g. `Result<HttpRequest<Pending>, Error>`.
+#[expect(
+    clippy::wildcard_enum_match_arm,
+    reason = "other types and generic arguments can't name the machine"
+)]
+fn names_machine(ty: &Type, name: &Ident) -> bool {
+    match ty {
+        Type::Group(group) => names_machine(&group.elem, name),
+        Type::Paren(paren) => names_machine(&paren.elem, name),
+        Type::Tuple(tuple) => tuple
+            .elems
+            .iter()
+            .any(|elem| names_machine(elem, name)),
+        Type::Path(path) => {
+            path.path.segments.iter().any(|segment| {
+                if segment.ident == *name
+                    || segment.ident == "Self"
+                {
+                    return true;
+                }
+
+                let PathArguments::AngleBracketed(args) =
+                    &segment.arguments
+                else {
+                    return false;
+                };
+
+                args.args.iter().any(|arg| match arg {
+                    GenericArgument::Type(ty) => {
+                        names_machine(ty, name)
+                    }
+                    _ => false,
+                })
+            })
+        }
+        _ => false,
+    }
+}
+
+/// Whether the function with the signature `sig` is a
+/// transition: it takes `self` by value, and returns the
+/// machine (in any state). Other functions, such as
+/// queries taking `&self`, aren't listed in `TRANSITIONS`.
+fn is_transition(sig: &Signature, name: &Ident) -> bool {
+    let takes_self = matches!(
+        sig.inputs.first(),
+        Some(FnArg::Receiver(receiver))
+            if receiver.reference.is_none()
+    );
+
+    takes_self
+        && match &sig.output {
+            ReturnType::Type(_, ty) => names_machine(ty, name),
+            ReturnType::Default => false,
+        }
+}
+
+#[expect(
+    clippy::too_many_lines,
+    reason = "generates the whole machine from one quote"
+)]
 pub fn deterministic_state_machine(
     input: TokenStream,
 ) -> TokenStream {
@@ -152,6 +267,58 @@ pub fn deterministic_state_machine(
         state_transitions,
     } = parse_macro_input!(input as Machine);
 
+    let state_names = state_transitions
+        .iter()
+        .filter_map(|StateTransitions { state, .. }| {
+            state.segments.last().map(|s| s.ident.to_string())
+        })
+        .collect::<Vec<_>>();
+
+    let transitions = state_transitions
+        .iter()
+        .zip(&state_names)
+        .flat_map(|(StateTransitions { transitions, .. }, from)| {
+            transitions
+                .iter()
+                .filter(|transition| is_transition(&transition.sig, &name))
+                .map(|transition| {
+                    let target = match &transition.sig.output {
+                        ReturnType::Type(_, ty) => target_state(ty, &name),
+                        ReturnType::Default => None,
+                    };
+
+                    TransitionDescription {
+                        from: from.clone(),
+                        event: transition.sig.ident.to_string(),
+                        target: target.as_ref().map(ToString::to_string),
+                        line: transition.sig.ident.span().start().line,
+                    }
+                })
+        })
+        .collect::<Vec<_>>();
+
+    let events = transitions.iter().fold(
+        Vec::new(),
+        |mut events, TransitionDescription { event, .. }| {
+            if !events.contains(event) {
+                events.push(event.clone());
+            }
+
+            events
+        },
+    );
+
+    let Introspection {
+        types: introspection_types,
+        constants: introspection_constants,
+    } = introspection(IntrospectionInput {
+        visibility: visibility.clone(),
+        machine_ident: name.clone(),
+        states: state_names,
+        events,
+        transitions,
+    });
+
     let next_impls = state_transitions
         .into_iter()
         .map(|StateTransitions { state, transitions }| {
@@ -181,6 +348,8 @@ pub fn deterministic_state_machine(
         impl<State> #name<State>
         #state_trait_where_clause
         {
+            #introspection_constants
+
             pub fn new(intial_state: State, context: #context) -> Self {
                 Self {
                     context,
@@ -209,6 +378,8 @@ pub fn deterministic_state_machine(
             }
         }
 
+        #introspection_types
+
         #state_trait
 
         #(#next_impls)*
@@ -0,0 +1,82 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{token::Async, Ident, Path, Type, Visibility};
+
+pub struct EffectsInput {
+    pub asyncness: Option<Async>,
+    pub context_path: Path,
+    pub effect_ty: Type,
+    pub executor_ident: Ident,
+    pub visibility: Option<Visibility>,
+}
+
+pub struct Effects {
+    /// Runs the effects of a transition, given an
+    /// `effects` variable.
+    pub execute: TokenStream,
+    /// The machine's field holding the executor.
+    pub field: TokenStream,
+    /// Initializes `field`.
+    pub init: TokenStream,
+    /// Items to add next to the machine.
+    pub items: TokenStream,
+    /// Methods to add to the machine.
+    pub methods: TokenStream,
+}
+
+/// Generates the executor trait for the effects returned
+/// by transition blocks, and how the machine runs them.
+///
+/// Effects produced while no executor is set are kept
+/// until they are taken with `take_effects`.
+pub fn effects(input: EffectsInput) -> Effects {
+    let EffectsInput {
+        visibility,
+        asyncness,
+        executor_ident,
+        effect_ty,
+        context_path,
+    } = input;
+
+    let async_trait_attr = asyncness
+        .is_some()
+        .then(|| quote!(#[::async_trait::async_trait]));
+    let async_postfix = asyncness.is_some().then(|| quote!(.await));
+
+    Effects {
+        items: quote! {
+            #async_trait_attr
+            #visibility trait #executor_ident: Send {
+                #asyncness fn execute(&mut self, effect: #effect_ty, context: &mut #context_path);
+            }
+        },
+        field: quote! {
+            effects: ::machine_factory_runtime::Effects<dyn #executor_ident, #effect_ty>,
+        },
+        init: quote! {
+            effects: ::core::default::Default::default(),
+        },
+        methods: quote! {
+            pub fn with_executor<Executor>(mut self, executor: Executor) -> Self
+            where
+                Executor: #executor_ident + 'static,
+            {
+                self.effects.set_executor(::std::boxed::Box::new(executor));
+                self
+            }
+
+            pub fn take_effects(&mut self) -> ::std::vec::Vec<#effect_ty> {
+                self.effects.take_pending()
+            }
+        },
+        execute: quote! {
+            if let ::core::option::Option::Some(executor) = self.effects.executor_mut() {
+                for effect in effects {
+                    executor.execute(effect, &mut self.context)#async_postfix;
+                }
+            } else {
+                self.effects.defer(effects);
+            }
+        },
+    }
+}
@@ -1,13 +1,35 @@
 use crate::{
+    actor::{actor, ActorInput},
+    effects::{effects, EffectsInput},
     event_enum::{event_enum, EventEnumInput},
+    event_store::{event_store, EventStoreInput},
     event_trait::ensure_event_trait,
+    handling::{handling, Handling, HandlingInput},
+    history::{history, HistoryInput},
+    hook_args::{hook_arg_values, HookArgs, HookValues},
+    hooks::{hooks, HooksInput},
+    instrument::{Instrument, InstrumentInput},
+    interceptor::{interceptors, InterceptorsInput},
+    introspection::{introspection, Introspection, IntrospectionInput, TransitionDescription},
+    kind::{ensure_no_kind_methods, kind, KindInput},
+    machine_traits::{machine_traits, MachineTraitsInput},
+    metrics::{metrics, MetricsInput},
+    observer::{observer, ObserverInput},
+    outcome::{outcome, OutcomeInput},
+    persister::{persister, PersisterInput},
+    recording::{recording, RecordingInput},
+    request::{request, Request, RequestInput},
+    service::{service, ServiceInput},
+    shared::{shared, SharedInput},
     state_enum::{state_enum, StateEnumInput},
     state_trait::ensure_state_trait,
+    stream::{stream, StreamInput},
+    transaction::{transaction, TransactionInput},
 };
 use core::iter::once;
 use heck::ToSnakeCase;
 use proc_macro::TokenStream;
-use quote::quote;
+use quote::{format_ident, quote};
 use std::collections::{HashMap, HashSet};
 use syn::{
     braced, bracketed,
@@ -16,24 +38,45 @@ use syn::{
     punctuated::Punctuated,
     spanned::Spanned,
     token::{Async, Brace, Comma},
-    Attribute, Block, FnArg, Ident, Path, Token, TraitItem,
-    Visibility,
+    Attribute, Block, FnArg, Ident, LitBool, LitInt, Path, Token,
+    TraitItem, Type, Visibility,
 };
 
+#[expect(
+    clippy::struct_excessive_bools,
+    reason = "each bool is an opt-in flag of the macro"
+)]
 struct Machine {
-    attributes: Vec<Attribute>,
-    visibility: Option<Visibility>,
+    actor: bool,
     asyncness: Option<Async>,
-    name: Ident,
+    attributes: Vec<Attribute>,
+    clock: Option<Type>,
     context_path: Path,
-    state_enum_attrs: Vec<Attribute>,
-    state_enum_ident: Ident,
-    state_trait: syn::ItemTrait,
+    effects: Option<Type>,
     event_enum_attrs: Vec<Attribute>,
     event_enum_ident: Ident,
+    event_store: Option<Type>,
     event_trait: syn::ItemTrait,
+    history: Option<LitInt>,
+    hooks: Option<Type>,
+    interceptors: bool,
+    metrics: bool,
+    name: Ident,
+    observable: bool,
+    other_events: Vec<EventDeclaration>,
+    persister: Option<Type>,
+    recording: bool,
+    registry: bool,
+    service: bool,
+    shared: bool,
+    state_enum_attrs: Vec<Attribute>,
+    state_enum_ident: Ident,
+    state_trait: syn::ItemTrait,
     state_transitions: Vec<StateTransitions>,
-    other_events: Vec<Path>,
+    stream: bool,
+    tracing: bool,
+    transactional: bool,
+    visibility: Option<Visibility>,
 }
 
 impl Parse for Machine {
@@ -70,6 +113,23 @@ impl Parse for Machine {
         let mut event_trait_path = None;
         let mut state_transitions = None;
         let mut other_events = None;
+        let mut clock = None;
+        let mut effects = None;
+        let mut hooks = None;
+        let mut event_store = None;
+        let mut persister = None;
+        let mut history = None;
+        let mut tracing = false;
+        let mut metrics = false;
+        let mut recording = false;
+        let mut transactional = false;
+        let mut interceptors = false;
+        let mut actor = false;
+        let mut observable = false;
+        let mut registry = false;
+        let mut service = false;
+        let mut shared = false;
+        let mut stream = false;
 
         while content.peek(Ident) {
             let label: Ident = content.parse()?;
@@ -122,7 +182,7 @@ impl Parse for Machine {
                     let _ = bracketed!(content2 in content);
                     let parsed_events =
                         Punctuated::<
-                            Path,
+                            EventDeclaration,
                             Comma,
                         >::parse_terminated(
                             &content2
@@ -135,6 +195,57 @@ impl Parse for Machine {
 
                     other_events = Some(parsed_events);
                 }
+                "clock" => {
+                    clock = Some(content.parse()?);
+                }
+                "effects" => {
+                    effects = Some(content.parse()?);
+                }
+                "hooks" => {
+                    hooks = Some(content.parse()?);
+                }
+                "event_store" => {
+                    event_store = Some(content.parse()?);
+                }
+                "persister" => {
+                    persister = Some(content.parse()?);
+                }
+                "history" => {
+                    history = Some(content.parse()?);
+                }
+                "tracing" => {
+                    tracing = content.parse::<LitBool>()?.value;
+                }
+                "metrics" => {
+                    metrics = content.parse::<LitBool>()?.value;
+                }
+                "recording" => {
+                    recording = content.parse::<LitBool>()?.value;
+                }
+                "transactional" => {
+                    transactional = content.parse::<LitBool>()?.value;
+                }
+                "interceptors" => {
+                    interceptors = content.parse::<LitBool>()?.value;
+                }
+                "actor" => {
+                    actor = content.parse::<LitBool>()?.value;
+                }
+                "observable" => {
+                    observable = content.parse::<LitBool>()?.value;
+                }
+                "registry" => {
+                    registry = content.parse::<LitBool>()?.value;
+                }
+                "service" => {
+                    service = content.parse::<LitBool>()?.value;
+                }
+                "shared" => {
+                    shared = content.parse::<LitBool>()?.value;
+                }
+                "stream" => {
+                    stream = content.parse::<LitBool>()?.value;
+                }
                 _ => {
                     return Err(syn::Error::new(
                         label.span(),
@@ -210,10 +321,48 @@ impl Parse for Machine {
             event_trait,
             state_transitions,
             other_events: other_events.unwrap_or_default(),
+            clock,
+            effects,
+            hooks,
+            event_store,
+            persister,
+            history,
+            tracing,
+            metrics,
+            recording,
+            transactional,
+            interceptors,
+            actor,
+            observable,
+            registry,
+            service,
+            shared,
+            stream,
         })
     }
 }
 
+struct EventDeclaration {
+    event_path: Path,
+    output: Option<Type>,
+}
+
+impl Parse for EventDeclaration {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let event_path = input.parse()?;
+
+        let output = input
+            .peek(Token![->])
+            .then(|| {
+                let _: Token![->] = input.parse()?;
+                input.parse()
+            })
+            .transpose()?;
+
+        Ok(Self { event_path, output })
+    }
+}
+
 enum StateTransitions {
     Default(Block),
     State(StateStateTransitions),
@@ -291,9 +440,15 @@ struct StateEvent {
     event_ident: Ident,
     block: Block,
     is_default: bool,
+    /// The target of a default transition.
+    target: Option<Ident>,
 }
 
-#[allow(clippy::too_many_lines)]
+#[expect(
+    clippy::too_many_lines,
+    clippy::cognitive_complexity,
+    reason = "splices the code generated for every label into the machine"
+)]
 pub(super) fn event_driven_state_machine(
     input: TokenStream,
 ) -> TokenStream {
@@ -311,23 +466,126 @@ pub(super) fn event_driven_state_machine(
         mut event_trait,
         state_transitions,
         other_events,
+        clock,
+        effects: effect_ty,
+        hooks: hooks_ty,
+        event_store: store_ty,
+        persister: persister_ty,
+        history: history_capacity,
+        tracing,
+        metrics: is_metered,
+        recording: is_recording,
+        transactional: is_transactional,
+        interceptors: is_intercepted,
+        actor: is_actor,
+        observable,
+        registry: is_registry,
+        service: is_service,
+        shared: is_shared,
+        stream: is_stream,
     } = parse_macro_input!(input as Machine);
 
+    if is_actor && asyncness.is_none() {
+        return syn::Error::new(
+            name.span(),
+            "actor requires an async machine",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    if is_service && asyncness.is_none() {
+        return syn::Error::new(
+            name.span(),
+            "service requires an async machine",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    // Undoing a transition isn't an event, so it can't be
+    // appended to the event store.
+    if let (Some(capacity), Some(_)) = (&history_capacity, &store_ty) {
+        return syn::Error::new(
+            capacity.span(),
+            "history can't be combined with event_store",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    // A failed save rolls the transition back to the
+    // snapshot taken by the transaction.
+    if let (Some(persister_ty), false) = (&persister_ty, is_transactional) {
+        return syn::Error::new(
+            persister_ty.span(),
+            "persister requires a transactional machine",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    if is_shared && asyncness.is_some() {
+        return syn::Error::new(
+            name.span(),
+            "shared requires a machine that isn't async",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    // The labels whose generated code relies on
+    // `machine_factory_runtime`; without them, the machine
+    // doesn't need the runtime crate.
+    let uses_runtime = effect_ty.is_some()
+        || store_ty.is_some()
+        || persister_ty.is_some()
+        || history_capacity.is_some()
+        || is_metered
+        || is_recording
+        || is_intercepted
+        || is_actor
+        || observable
+        || is_registry
+        || is_shared;
+
+    let outcome_ident = format_ident!("{}Outcome", name);
+    let request_trait_ident = format_ident!("{}Request", name);
+    let reply_ident = format_ident!("{}Reply", name);
+    let state_kind_ident = format_ident!("{}Kind", state_enum_ident);
+
     let async_postfix = asyncness.is_some().then(|| quote!(.await));
 
-    if let Err(e) = ensure_state_trait(
+    let event_enum_ty: Type = parse_quote!(#event_enum_ident);
+    let state_enum_ty: Type = parse_quote!(#state_enum_ident);
+    // State hooks may accept the event, and event hooks the
+    // state.
+    let state_hook_args =
+        HookArgs { clock: clock.as_ref(), event: Some(&event_enum_ty), state: None };
+    let event_hook_args =
+        HookArgs { clock: clock.as_ref(), event: None, state: Some(&state_enum_ty) };
+
+    if let Err(e) = ensure_no_kind_methods(&state_trait, &state_enum_ident)
+        .and_then(|()| ensure_no_kind_methods(&event_trait, &event_enum_ident))
+    {
+        return e.to_compile_error().into();
+    }
+
+    let rejection_reason = match ensure_state_trait(
         asyncness,
         &mut state_trait,
         &context_path,
         &event_enum_ident,
+        state_hook_args,
     ) {
-        return e.to_compile_error().into();
-    }
+        Ok(x) => x,
+        Err(e) => return e.to_compile_error().into(),
+    };
 
     let state_trait_path = &state_trait.ident;
 
     if let Err(e) =
-        ensure_event_trait(asyncness, &mut event_trait, &context_path)
+        ensure_event_trait(asyncness, &mut event_trait, &context_path, event_hook_args)
     {
         return e.to_compile_error().into();
     }
@@ -390,12 +648,14 @@ pub(super) fn event_driven_state_machine(
                 return Err(syn::Error::new(event_path.span(), "event path is empty"));
             };
 
-            let (block, is_default) = match block {
-                TransitionBlock::Block(block) => (block, false),
+            let (block, is_default, target) = match block {
+                TransitionBlock::Block(block) => (block, false, None),
                 TransitionBlock::Default(target) => {
+                    let target_ident = target.segments.last().map(|s| s.ident.clone());
+
                     (syn::parse_quote! {{
                         #target::default()
-                    }}, true)
+                    }}, true, target_ident)
                 }
             };
 
@@ -406,6 +666,7 @@ pub(super) fn event_driven_state_machine(
                 event_ident,
                 block,
                 is_default,
+                target,
             })
         })
         .collect::<syn::Result<Vec<_>>>()
@@ -419,8 +680,54 @@ pub(super) fn event_driven_state_machine(
         Err(e) => return e.to_compile_error().into(),
     };
 
+    let state_idents = state_events.iter().fold(
+        Vec::new(),
+        |mut idents, StateEvent { state_ident, .. }| {
+            if !idents.contains(state_ident) {
+                idents.push(state_ident.clone());
+            }
+
+            idents
+        },
+    );
+
+    let request_outputs = other_events
+        .iter()
+        .filter_map(|EventDeclaration { event_path, output }| {
+            output.as_ref().map(|output| (event_path.clone(), output.clone()))
+        })
+        .collect::<HashMap<_, _>>();
+
+    let clock_param = clock.as_ref().map(|clock| quote!(clock: &#clock,));
+    let clock_arg = clock.as_ref().map(|_| quote!(clock,));
+    let reply_param = (!request_outputs.is_empty())
+        .then(|| quote!(reply: &mut ::core::option::Option<#reply_ident>,));
+    let reply_arg = reply_param.as_ref().map(|_| quote!(reply,));
+    let no_reply_arg = reply_param.as_ref().map(|_| quote!(&mut ::core::option::Option::None,));
+    let effects_param = effect_ty
+        .as_ref()
+        .map(|effect_ty| quote!(effects: &mut ::std::vec::Vec<#effect_ty>,));
+    let effects_arg = effects_param.as_ref().map(|_| quote!(effects,));
+    let block_output = effect_ty.as_ref().map_or_else(
+        || quote!(impl Into<#state_enum_ident>),
+        |effect_ty| quote!((impl Into<#state_enum_ident>, ::std::vec::Vec<#effect_ty>)),
+    );
+    // Keeps the effects returned by a transition block, and
+    // evaluates to the block's state.
+    let collect_effects = |call: proc_macro2::TokenStream| {
+        if effect_ty.is_some() {
+            quote! {{
+                let (state, block_effects) = #call;
+                effects.extend(block_effects);
+                state
+            }}
+        } else {
+            call
+        }
+    };
+
     let handle_event_match_arms = state_events.iter()
-        .map(|StateEvent { state_path, state_ident, event_path, event_ident, block, is_default }| {
+        .map(|StateEvent { state_path, state_ident, event_path, event_ident, block, is_default, .. }| {
             if *is_default {
                 quote! {
                     (#state_enum_ident::#state_ident(state), #event_enum_ident::#event_ident(event)) => {
@@ -436,6 +743,41 @@ pub(super) fn event_driven_state_machine(
                     state_ident.span(),
                 );
 
+                // The block of a request answers with its own
+                // output, which is wrapped in the reply enum.
+                if let Some(output) = request_outputs.get(event_path) {
+                    let call = collect_effects(
+                        quote!(#function_ident(state, event, context, #clock_arg &mut output)#async_postfix),
+                    );
+
+                    return quote! {
+                        (#state_enum_ident::#state_ident(state), #event_enum_ident::#event_ident(event)) => {
+                            #[allow(non_snake_case)]
+                            #[allow(clippy::unused_async)]
+                            #asyncness fn #function_ident(
+                                mut state: #state_path,
+                                event: &mut #event_path,
+                                context: &mut #context_path,
+                                #clock_param
+                                reply: &mut ::core::option::Option<#output>,
+                            ) -> #block_output #block
+
+                            let mut output = ::core::option::Option::None;
+                            let state = #call;
+
+                            if let ::core::option::Option::Some(output) = output {
+                                *reply = ::core::option::Option::Some(#reply_ident::#event_ident(output));
+                            }
+
+                            state.into()
+                        }
+                    };
+                }
+
+                let call = collect_effects(
+                    quote!(#function_ident(state, event, context, #clock_arg)#async_postfix),
+                );
+
                 quote! {
                     (#state_enum_ident::#state_ident(state), #event_enum_ident::#event_ident(event)) => {
                         #[allow(non_snake_case)]
@@ -444,9 +786,10 @@ pub(super) fn event_driven_state_machine(
                             mut state: #state_path,
                             event: &mut #event_path,
                             context: &mut #context_path,
-                        ) -> impl Into<#state_enum_ident> #block
+                            #clock_param
+                        ) -> #block_output #block
                     
-                        #function_ident(state, event, &mut self.context)#async_postfix.into()
+                        #call.into()
                     }
                 }
             }
@@ -458,12 +801,12 @@ pub(super) fn event_driven_state_machine(
         .map(|StateEvent { event_path, .. }| {
             event_path.clone()
         })
-        .chain(other_events.iter().cloned())
+        .chain(other_events.iter().map(|EventDeclaration { event_path, .. }| event_path.clone()))
         .collect::<HashSet<_>>()
         .into_iter()
         .collect::<Vec<_>>();
 
-    let other_event_path_ident = other_events.iter().map(|event_path| {
+    let other_event_path_ident = other_events.iter().map(|EventDeclaration { event_path, .. }| {
         let Some(event_ident) = event_path.segments.last().map(|s| s.ident.clone()) else {
             return Err(syn::Error::new(event_path.span(), "event path is empty"));
         };
@@ -477,6 +820,73 @@ pub(super) fn event_driven_state_machine(
         Err(e) => return e.to_compile_error().into(),
     };
 
+    let requests = other_events
+        .into_iter()
+        .zip(&other_event_path_ident)
+        .filter_map(|(EventDeclaration { event_path, output }, (_, event_ident))| {
+            output.map(|output| Request { event_path, event_ident: event_ident.clone(), output })
+        })
+        .collect::<Vec<_>>();
+
+    let event_idents = state_events
+        .iter()
+        .map(|StateEvent { event_ident, .. }| event_ident)
+        .chain(other_event_path_ident.iter().map(|(_, event_ident)| event_ident))
+        .fold(Vec::new(), |mut idents, event_ident| {
+            if !idents.contains(event_ident) {
+                idents.push(event_ident.clone());
+            }
+
+            idents
+        });
+
+    let introspection = introspection(IntrospectionInput {
+        visibility: visibility.clone(),
+        machine_ident: name.clone(),
+        states: state_idents.iter().map(ToString::to_string).collect(),
+        events: event_idents.iter().map(ToString::to_string).collect(),
+        transitions: state_events
+            .iter()
+            .map(|StateEvent { state_ident, event_path, event_ident, target, .. }| TransitionDescription {
+                from: state_ident.to_string(),
+                event: event_ident.to_string(),
+                target: target.as_ref().map(ToString::to_string),
+                line: event_path.span().start().line,
+            })
+            .collect(),
+    });
+
+    let Introspection {
+        types: introspection_types,
+        constants: introspection_constants,
+    } = introspection;
+
+    let event_kind_ident = format_ident!("{}Kind", event_enum_ident);
+
+    let Handling {
+        enum_item: handling_enum,
+        methods: handling_methods,
+    } = handling(HandlingInput {
+        visibility: visibility.clone(),
+        ident: format_ident!("{}Handling", name),
+        state_kind_ident: state_kind_ident.clone(),
+        event_kind_ident: event_kind_ident.clone(),
+        event_enum_ident: event_enum_ident.clone(),
+        transitions: state_idents
+            .iter()
+            .map(|state_ident| {
+                let event_idents = state_events
+                    .iter()
+                    .filter(|state_event| state_event.state_ident == *state_ident)
+                    .map(|StateEvent { event_ident, .. }| event_ident.clone())
+                    .collect();
+
+                (state_ident.clone(), event_idents)
+            })
+            .collect(),
+        has_fallback: unhandled_event.is_some(),
+    });
+
     let event_path_ident = state_events
         .iter()
         .map(
@@ -518,6 +928,24 @@ pub(super) fn event_driven_state_machine(
         .find(|sig| sig.ident == "post_transition")
         .and_then(|sig| sig.asyncness.and_then(|_| async_postfix.clone()));
 
+    let hook_values = HookValues {
+        clock: quote!(clock),
+        event: quote!(&event),
+        state: quote!(&state),
+    };
+
+    let hook_arg_values_of = |sigs: &[syn::Signature], ident: &str, required: usize, hook_args| {
+        sigs.iter()
+            .find(|sig| sig.ident == ident)
+            .map(|sig| hook_arg_values(sig, required, hook_args, &hook_values))
+            .unwrap_or_default()
+    };
+
+    let pre_transition_args =
+        hook_arg_values_of(&event_trait_function_sigs, "pre_transition", 2, event_hook_args);
+    let post_transition_args =
+        hook_arg_values_of(&event_trait_function_sigs, "post_transition", 2, event_hook_args);
+
     let event_enum_trait_functions = event_trait_function_sigs.iter().map(|sig| {
         let ident = &sig.ident;
         let args = sig.inputs.iter().skip(1).map(|input| {
@@ -558,7 +986,7 @@ pub(super) fn event_driven_state_machine(
     let event_enum_trait_impl = quote! {
         #maybe_async_trait_attr
         impl #event_trait_path for #event_enum_ident {
-            #(#event_enum_trait_functions)*
+            #(#[allow(clippy::used_underscore_binding)] #event_enum_trait_functions)*
         }
     };
 
@@ -616,6 +1044,28 @@ pub(super) fn event_driven_state_machine(
         .find(|sig| sig.ident == "should_exit")
         .and_then(|sig| sig.asyncness.and_then(|_| async_postfix.clone()));
 
+    let on_rejected_postfix = state_trait_function_sigs
+        .iter()
+        .find(|sig| sig.ident == "on_rejected")
+        .and_then(|sig| sig.asyncness.and_then(|_| async_postfix.clone()));
+
+    let on_enter_args =
+        hook_arg_values_of(&state_trait_function_sigs, "on_enter", 2, state_hook_args);
+    let on_exit_args =
+        hook_arg_values_of(&state_trait_function_sigs, "on_exit", 2, state_hook_args);
+    let on_rejected_args = hook_arg_values_of(
+        &state_trait_function_sigs,
+        "on_rejected",
+        3,
+        HookArgs { event: None, ..state_hook_args },
+    );
+    let should_exit_args = hook_arg_values_of(
+        &state_trait_function_sigs,
+        "should_exit",
+        3,
+        HookArgs { event: None, ..state_hook_args },
+    );
+
     let state_enum_trait_functions = state_trait_function_sigs.iter().map(|sig| {
         let ident = &sig.ident;
         let args = sig.inputs.iter().skip(1).map(|input| {
@@ -665,6 +1115,9 @@ pub(super) fn event_driven_state_machine(
             "handle__unhandled_event",
             block.span(),
         );
+        let call = collect_effects(
+            quote!(#function_ident(state, event, context, #clock_arg #reply_arg)#async_postfix),
+        );
 
         quote! {
             (state, event) => {
@@ -674,9 +1127,11 @@ pub(super) fn event_driven_state_machine(
                     mut state: #state_enum_ident,
                     event: &mut #event_enum_ident,
                     context: &mut #context_path,
-                ) -> impl Into<#state_enum_ident> #block
+                    #clock_param
+                    #reply_param
+                ) -> #block_output #block
                     
-                #function_ident(state, event, &mut self.context)#async_postfix.into()
+                #call.into()
             }
         }
     });
@@ -701,6 +1156,410 @@ pub(super) fn event_driven_state_machine(
         Err(e) => return e.to_compile_error().into(),
     };
 
+    let state_kind = kind(KindInput {
+        visibility: visibility.clone(),
+        enum_ident: state_enum_ident.clone(),
+        ident: state_kind_ident.clone(),
+        variants: state_idents,
+        serializable: is_recording,
+        runtime: uses_runtime,
+    });
+
+    let event_kind = kind(KindInput {
+        visibility: visibility.clone(),
+        enum_ident: event_enum_ident.clone(),
+        ident: event_kind_ident.clone(),
+        variants: event_idents,
+        serializable: is_recording,
+        runtime: uses_runtime,
+    });
+
+    let observer = observable.then(|| {
+        observer(ObserverInput {
+            visibility: visibility.clone(),
+            asyncness,
+            ident: format_ident!("{}Transition", name),
+            state_kind_ident: state_kind_ident.clone(),
+        })
+    });
+
+    let observer_items = observer.as_ref().map(|observer| &observer.items);
+    let observer_field = observer.as_ref().map(|observer| &observer.field);
+    let observer_init = observer.as_ref().map(|observer| &observer.init);
+    let observer_methods = observer.as_ref().map(|observer| &observer.methods);
+    let observe = observer.as_ref().map(|observer| {
+        let notify = &observer.notify;
+
+        quote! {
+            if let #outcome_ident::Transitioned { from, to } = outcome {
+                #notify
+            }
+        }
+    });
+
+    let outcome = outcome(OutcomeInput {
+        visibility: visibility.clone(),
+        ident: outcome_ident.clone(),
+        state_kind_ident: state_kind_ident.clone(),
+        rejection_reason: rejection_reason.clone(),
+        serializable: is_recording,
+        vetoable: is_intercepted,
+        persisted: persister_ty.is_some(),
+    });
+
+    let instrument = Instrument::new(InstrumentInput {
+        enabled: tracing,
+        asyncness,
+        machine_ident: name.clone(),
+    });
+    let trace_rejected = instrument.rejected(rejection_reason.is_some());
+    let trace_on_rejected = instrument.hook("on_rejected");
+    let trace_on_exit = instrument.hook("on_exit");
+    let trace_pre_transition = instrument.hook("pre_transition");
+    let trace_post_transition = instrument.hook("post_transition");
+    let trace_on_enter = instrument.hook("on_enter");
+    let trace_transitioned = instrument.transitioned();
+
+    let on_rejected = quote! {
+        #trace_rejected
+        #trace_on_rejected
+        #state_trait_path::on_rejected(&mut state, context, &event, #(#on_rejected_args),*)#on_rejected_postfix;
+    };
+    let should_exit = quote! {
+        #state_enum_ident::should_exit(&state, context, &event, #(#should_exit_args),*)#should_exit_postfix
+    };
+    let guard = if rejection_reason.is_some() {
+        quote! {
+            if let ::core::result::Result::Err(reason) = #should_exit {
+                #on_rejected
+                return (state, #outcome_ident::Rejected { state: from, reason });
+            }
+        }
+    } else {
+        quote! {
+            if !#should_exit {
+                #on_rejected
+                return (state, #outcome_ident::Rejected { state: from });
+            }
+        }
+    };
+
+    let actor = is_actor.then(|| {
+        actor(ActorInput {
+            visibility: visibility.clone(),
+            machine_ident: name.clone(),
+            state_enum_ident: state_enum_ident.clone(),
+            event_enum_ident: event_enum_ident.clone(),
+            outcome_ident: outcome_ident.clone(),
+        })
+    });
+
+    let shared = is_shared.then(|| {
+        shared(SharedInput {
+            visibility: visibility.clone(),
+            machine_ident: name.clone(),
+        })
+    });
+
+    let stream_fns = is_stream.then(|| {
+        stream(StreamInput {
+            asyncness,
+            state_enum_ident: state_enum_ident.clone(),
+            event_enum_ident: event_enum_ident.clone(),
+            outcome_ident: outcome_ident.clone(),
+        })
+    });
+
+    let service = is_service.then(|| {
+        service(ServiceInput {
+            visibility: visibility.clone(),
+            machine_ident: name.clone(),
+            event_enum_ident: event_enum_ident.clone(),
+            event_trait_ident: event_trait_path.clone(),
+            outcome_ident: outcome_ident.clone(),
+            request_trait_ident: (!requests.is_empty()).then(|| request_trait_ident.clone()),
+        })
+    });
+
+    let request_items = (!requests.is_empty()).then(|| {
+        request(RequestInput {
+            visibility: visibility.clone(),
+            trait_ident: request_trait_ident.clone(),
+            reply_ident: reply_ident.clone(),
+            event_enum_ident: event_enum_ident.clone(),
+            requests,
+        })
+    });
+
+    let self_clock_arg = clock.as_ref().map(|_| quote!(&self.clock,));
+
+    let handle_request_fn = reply_param.as_ref().map(|_| {
+        quote! {
+            pub #asyncness fn handle_request<Request>(&mut self, request: Request) -> ::core::option::Option<Request::Output>
+            where
+                Request: #request_trait_ident + #event_trait_path,
+            {
+                let mut reply = ::core::option::Option::None;
+                _ = self.dispatch(request.into(), &mut reply)#async_postfix;
+                reply.and_then(Request::from_reply)
+            }
+        }
+    });
+
+    let hooks_ident = format_ident!("{}Hooks", name);
+    let hooks_items = hooks_ty.as_ref().map(|_| {
+        hooks(HooksInput {
+            visibility: visibility.clone(),
+            asyncness,
+            ident: hooks_ident.clone(),
+            state_kind_ident: state_kind_ident.clone(),
+            event_enum_ident: event_enum_ident.clone(),
+            context_path: context_path.clone(),
+        })
+    });
+    let on_transition = hooks_ty.as_ref().map(|hooks_ty| {
+        quote! {
+            <#hooks_ty as #hooks_ident>::on_transition(&from, &to, &event, context)#async_postfix;
+        }
+    });
+
+    let effects = effect_ty.as_ref().map(|effect_ty| {
+        effects(EffectsInput {
+            visibility: visibility.clone(),
+            asyncness,
+            executor_ident: format_ident!("{}Executor", name),
+            effect_ty: effect_ty.clone(),
+            context_path: context_path.clone(),
+        })
+    });
+
+    let effects_items = effects.as_ref().map(|effects| &effects.items);
+    let effects_field = effects.as_ref().map(|effects| &effects.field);
+    let effects_init = effects.as_ref().map(|effects| &effects.init);
+    let effects_methods = effects.as_ref().map(|effects| &effects.methods);
+    let collect_in_dispatch = effects.as_ref().map(|_| quote!(let mut effects = ::std::vec::Vec::new();));
+    let execute_effects = effects.as_ref().map(|effects| &effects.execute);
+    let dispatch_effects_arg = effects.as_ref().map(|_| quote!(&mut effects,));
+
+    let transition_fn = effect_ty.as_ref().map(|effect_ty| {
+        quote! {
+            pub #asyncness fn transition<Event: Into<#event_enum_ident> + #event_trait_path>(
+                state: #state_enum_ident,
+                context: &mut #context_path,
+                event: Event,
+                #clock_param
+            ) -> (#state_enum_ident, ::std::vec::Vec<#effect_ty>) {
+                let mut event = event.into();
+                let mut effects = ::std::vec::Vec::new();
+                let state = Self::transition_block(state, &mut event, context, #clock_arg #no_reply_arg &mut effects)#async_postfix;
+                (state, effects)
+            }
+        }
+    });
+
+    let step_effects_init = effects.as_ref().map(|_| quote!(let mut effects = ::std::vec::Vec::new();));
+    let step_effects_output = effect_ty.as_ref().map(|effect_ty| quote!(, ::std::vec::Vec<#effect_ty>));
+    let step_effects_value = effects.as_ref().map(|_| quote!(, effects));
+
+    let metrics = is_metered.then(|| {
+        metrics(MetricsInput {
+            machine_ident: name.clone(),
+            state_kind_ident,
+            event_kind_ident,
+            outcome_ident: outcome_ident.clone(),
+            clock: clock.clone(),
+        })
+    });
+
+    let metrics_field = metrics.as_ref().map(|metrics| &metrics.field);
+    let metrics_init = metrics.as_ref().map(|metrics| &metrics.init);
+    let metrics_methods = metrics.as_ref().map(|metrics| &metrics.methods);
+    let metrics_capture = metrics.as_ref().map(|metrics| &metrics.capture);
+    let metrics_record = metrics.as_ref().map(|metrics| &metrics.record);
+    let metrics_record_restore = metrics.as_ref().map(|metrics| &metrics.record_restore);
+    let metrics_restart = metrics.as_ref().and_then(|metrics| metrics.restart.as_ref());
+
+    let recording = is_recording.then(|| {
+        recording(RecordingInput {
+            asyncness,
+            event_enum_ident: event_enum_ident.clone(),
+            outcome_ident: outcome_ident.clone(),
+            no_reply_arg: no_reply_arg.clone(),
+        })
+    });
+
+    let recording_field = recording.as_ref().map(|recording| &recording.field);
+    let recording_init = recording.as_ref().map(|recording| &recording.init);
+    let recording_methods = recording.as_ref().map(|recording| &recording.methods);
+    let recording_capture = recording.as_ref().map(|recording| &recording.capture);
+    let recording_record = recording.as_ref().map(|recording| &recording.record);
+
+    let persister = persister_ty.map(|persister_ty| {
+        persister(PersisterInput {
+            persister_ty,
+            state_enum_ident: state_enum_ident.clone(),
+            outcome_ident: outcome_ident.clone(),
+            context_path: context_path.clone(),
+            discard_effects: effects.as_ref().map(|_| quote!(effects.clear();)),
+        })
+    });
+
+    let persister_fields = persister.as_ref().map(|persister| &persister.fields);
+    let persister_init = persister.as_ref().map(|persister| &persister.init);
+    let persister_methods = persister.as_ref().map(|persister| &persister.methods);
+    let persister_param = persister.as_ref().map(|persister| &persister.param);
+    let persister_arg = persister.as_ref().map(|persister| &persister.arg);
+    let persister_no_arg = persister.as_ref().map(|persister| &persister.no_arg);
+    let persister_save = persister.as_ref().map(|persister| &persister.save);
+    let persister_roll_back = persister.as_ref().map(|persister| &persister.roll_back);
+    let persister_save_restored = persister.as_ref().map(|persister| &persister.save_restored);
+
+    let new_effects_arg = effects.as_ref().map(|_| quote!(&mut ::std::vec::Vec::new(),));
+    let observer_reset = observer.as_ref().and_then(|observer| observer.reset.as_ref());
+    let metrics_reenter = metrics.as_ref().map(|metrics| &metrics.reenter);
+    let event_store = store_ty.map(|store_ty| {
+        event_store(EventStoreInput {
+            asyncness,
+            store_ty,
+            state_enum_ident: state_enum_ident.clone(),
+            event_enum_ident: event_enum_ident.clone(),
+            outcome_ident: outcome_ident.clone(),
+            context_path: context_path.clone(),
+            replay: quote! {
+                Self::run_pipeline(state, &mut self.context, event, #self_clock_arg #no_reply_arg #new_effects_arg #persister_no_arg)#async_postfix
+            },
+            after_rebuild: (observer_reset.is_some() || metrics_reenter.is_some()).then(|| {
+                quote! {
+                    #observer_reset
+                    #metrics_reenter
+                }
+            }),
+        })
+    });
+
+    let event_store_fields = event_store.as_ref().map(|event_store| &event_store.fields);
+    let event_store_init = event_store.as_ref().map(|event_store| &event_store.init);
+    let event_store_methods = event_store.as_ref().map(|event_store| &event_store.methods);
+    let event_store_capture = event_store.as_ref().map(|event_store| &event_store.capture);
+    let event_store_append = event_store.as_ref().map(|event_store| &event_store.append);
+
+    let run_pipeline = transaction(TransactionInput {
+        asyncness,
+        enabled: is_transactional,
+        run: quote! {
+            Self::run_pipeline(state, &mut self.context, event, #self_clock_arg #reply_arg #dispatch_effects_arg #persister_arg)#async_postfix
+        },
+    });
+
+    let history = history_capacity.map(|capacity| {
+        history(HistoryInput {
+            asyncness,
+            capacity,
+            state_enum_ident: state_enum_ident.clone(),
+            event_enum_ident: event_enum_ident.clone(),
+            outcome_ident: outcome_ident.clone(),
+            context_path: context_path.clone(),
+            bind_clock: clock.as_ref().map(|_| quote!(let clock = &self.clock;)),
+            on_exit: quote! {
+                #state_trait_path::on_exit(&mut state, context, #(#on_exit_args),*)#on_exit_postfix;
+            },
+            on_enter: quote! {
+                #state_trait_path::on_enter(&mut state, context, #(#on_enter_args),*)#on_enter_postfix;
+            },
+            bookkeeping: quote! {
+                #observe
+                #metrics_record_restore
+                #persister_save_restored
+            },
+            refused: is_recording.then(|| {
+                quote! {
+                    if self.trace.is_some() {
+                        return false;
+                    }
+                }
+            }),
+        })
+    });
+
+    let history_field = history.as_ref().map(|history| &history.field);
+    let history_init = history.as_ref().map(|history| &history.init);
+    let history_methods = history.as_ref().map(|history| &history.methods);
+    let history_capture = history.as_ref().map(|history| &history.capture);
+    let history_record = history.as_ref().map(|history| &history.record);
+
+    let interceptors = is_intercepted.then(|| {
+        interceptors(InterceptorsInput {
+            outcome_ident: outcome_ident.clone(),
+        })
+    });
+
+    let interceptors_field = interceptors.as_ref().map(|interceptors| &interceptors.field);
+    let interceptors_init = interceptors.as_ref().map(|interceptors| &interceptors.init);
+    let interceptors_methods = interceptors.as_ref().map(|interceptors| &interceptors.methods);
+    let intercept_before = interceptors.as_ref().map(|interceptors| &interceptors.before);
+    let intercept_after = interceptors.as_ref().map(|interceptors| &interceptors.after);
+
+    let history_clear = history.is_some().then(|| quote!(self.history.clear();));
+
+    let machine_traits = uses_runtime.then(|| {
+        machine_traits(MachineTraitsInput {
+            asyncness,
+            ident: name.clone(),
+            state_enum_ident: state_enum_ident.clone(),
+            event_enum_ident: event_enum_ident.clone(),
+            outcome_ident: outcome_ident.clone(),
+            state_kind_ident: format_ident!("{}Kind", state_enum_ident),
+            context_path: context_path.clone(),
+            transactional: is_transactional,
+            after_restore: quote! {
+                #observer_reset
+                #metrics_reenter
+                #history_clear
+            },
+        })
+    });
+
+    let clock_field = clock.as_ref().map(|clock| quote!(clock: #clock,));
+    let clock_init = clock.as_ref().map(|_| quote!(clock: ::core::default::Default::default(),));
+    let clock_fns = clock.as_ref().map(|clock| {
+        quote! {
+            pub fn with_clock(mut self, clock: #clock) -> Self {
+                self.clock = clock;
+                #metrics_restart
+                self
+            }
+
+            pub fn clock(&self) -> &#clock {
+                &self.clock
+            }
+        }
+    });
+
+    let trace_on_transition = on_transition.as_ref().and_then(|_| instrument.hook("on_transition"));
+    let pipeline = instrument.wrap(&quote! {
+        #guard
+
+        #trace_on_exit
+        #state_trait_path::on_exit(&mut state, context, #(#on_exit_args),*)#on_exit_postfix;
+        #trace_pre_transition
+        #event_trait_path::pre_transition(&mut event, context, #(#pre_transition_args),*)#pre_transition_postfix;
+
+        let mut state = Self::transition_block(state, &mut event, context, #clock_arg #reply_arg #effects_arg)#async_postfix;
+
+        #trace_post_transition
+        #event_trait_path::post_transition(&mut event, context, #(#post_transition_args),*)#post_transition_postfix;
+        #trace_on_enter
+        #state_trait_path::on_enter(&mut state, context, #(#on_enter_args),*)#on_enter_postfix;
+
+        let to = state.kind();
+        #persister_save
+        #trace_on_transition
+        #on_transition
+        #trace_transitioned
+
+        (state, #outcome_ident::Transitioned { from, to })
+    });
+
     let expanded = quote! {
         #event_trait
         #event_enum
@@ -711,18 +1570,67 @@ pub(super) fn event_driven_state_machine(
         #state_enum
         #(#state_from_impls)*
         #state_enum_trait_impl
+        #state_kind
+        #introspection_types
+        #handling_enum
+        #event_kind
+
+        #outcome
+        #observer_items
+        #request_items
+        #effects_items
+        #hooks_items
 
         #(#attributes)*
         #visibility struct #name {
             context: #context_path,
             state: ::core::option::Option<#state_enum_ident>,
+            #clock_field
+            #observer_field
+            #effects_field
+            #metrics_field
+            #recording_field
+            #event_store_fields
+            #persister_fields
+            #history_field
+            #interceptors_field
         }
 
+        #[allow(clippy::same_name_method)]
         impl #name {
+            #introspection_constants
+
+            #handling_methods
+
             pub fn new<State: Into<#state_enum_ident> + #state_trait_path>(state: State, context: #context_path) -> Self {
-                Self { context, state: ::core::option::Option::Some(state.into()) }
+                let state = state.into();
+
+                Self {
+                    context,
+                    #observer_init
+                    state: ::core::option::Option::Some(state),
+                    #clock_init
+                    #effects_init
+                    #metrics_init
+                    #recording_init
+                    #event_store_init
+                    #persister_init
+                    #history_init
+                    #interceptors_init
+                }
             }
 
+            #clock_fns
+            #observer_methods
+            #effects_methods
+            #metrics_methods
+            #recording_methods
+            #event_store_methods
+            #persister_methods
+            #history_methods
+            #interceptors_methods
+            #stream_fns
+
             pub fn context(&self) -> &#context_path {
                 &self.context
             }
@@ -740,35 +1648,90 @@ pub(super) fn event_driven_state_machine(
             }
 
             pub fn into_parts(self) -> (#state_enum_ident, #context_path) {
-                let Self { context, state } = self;
+                let Self { context, state, .. } = self;
                 let state = state.expect("state is missing");
                 (state, context)
             }
 
             pub #asyncness fn handle_event<Event: Into<#event_enum_ident> + #event_trait_path>(&mut self, event: Event) -> &mut Self {
-                let mut event = event.into();
-                let mut state = self.state.take().expect("state is missing");
+                _ = self.dispatch(event.into(), #no_reply_arg)#async_postfix;
+                self
+            }
 
-                if !#state_enum_ident::should_exit(&state, &self.context, &event)#should_exit_postfix {
-                    self.state = ::core::option::Option::Some(state);
-                    return self;
-                }
+            pub #asyncness fn handle_event_outcome<Event: Into<#event_enum_ident> + #event_trait_path>(&mut self, event: Event) -> #outcome_ident {
+                self.dispatch(event.into(), #no_reply_arg)#async_postfix
+            }
 
-                #state_trait_path::on_exit(&mut state, &mut self.context)#on_exit_postfix;
-                #event_trait_path::pre_transition(&mut event, &mut self.context)#pre_transition_postfix;
+            #handle_request_fn
+
+            #asyncness fn dispatch(&mut self, event: #event_enum_ident, #reply_param) -> #outcome_ident {
+                let state = self.state.take().expect("state is missing");
+                #intercept_before
+                #collect_in_dispatch
+                #metrics_capture
+                #recording_capture
+                #event_store_capture
+                #history_capture
+
+                #run_pipeline
+                #persister_roll_back
+                #observe
+                #metrics_record
+                #recording_record
+                #event_store_append
+                #history_record
+                #execute_effects
+                #intercept_after
 
-                let mut state: #state_enum_ident = match (state, &mut event) {
-                    #(#handle_event_match_arms)*
-                    #unhandled_event
-                };
+                self.state = ::core::option::Option::Some(state);
+                outcome
+            }
+
+            #asyncness fn run_pipeline(
+                mut state: #state_enum_ident,
+                context: &mut #context_path,
+                mut event: #event_enum_ident,
+                #clock_param
+                #reply_param
+                #effects_param
+                #persister_param
+            ) -> (#state_enum_ident, #outcome_ident) {
+                let from = state.kind();
+                #pipeline
+            }
 
-                #event_trait_path::post_transition(&mut event, &mut self.context)#post_transition_postfix;
-                #state_trait_path::on_enter(&mut state, &mut self.context)#on_enter_postfix;
+            pub #asyncness fn step<Event: Into<#event_enum_ident> + #event_trait_path>(
+                state: #state_enum_ident,
+                mut context: #context_path,
+                event: Event,
+                #clock_param
+            ) -> (#state_enum_ident, #context_path, #outcome_ident #step_effects_output) {
+                #step_effects_init
+                let (state, outcome) = Self::run_pipeline(state, &mut context, event.into(), #clock_arg #no_reply_arg #dispatch_effects_arg #persister_no_arg)#async_postfix;
+                (state, context, outcome #step_effects_value)
+            }
 
-                self.state = ::core::option::Option::Some(state);
-                self
+            #transition_fn
+
+            #asyncness fn transition_block(
+                state: #state_enum_ident,
+                event: &mut #event_enum_ident,
+                context: &mut #context_path,
+                #clock_param
+                #reply_param
+                #effects_param
+            ) -> #state_enum_ident {
+                match (state, event) {
+                    #(#handle_event_match_arms)*
+                    #unhandled_event
+                }
             }
         }
+
+        #machine_traits
+        #actor
+        #service
+        #shared
     };
 
     expanded.into()
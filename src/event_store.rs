@@ -0,0 +1,144 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Ident, Path, Type, token::Async};
+
+pub struct EventStoreInput {
+    /// Brings the rest of the machine up to date after it
+    /// was rebuilt, given a `state` variable, if needed.
+    pub after_rebuild: Option<TokenStream>,
+    pub asyncness: Option<Async>,
+    pub context_path: Path,
+    pub event_enum_ident: Ident,
+    pub outcome_ident: Ident,
+    /// Runs the whole pipeline of the machine with `event`,
+    /// without effects, replies or bookkeeping, given
+    /// `state` and `event` variables, and evaluates to the
+    /// next state and the outcome.
+    pub replay: TokenStream,
+    pub state_enum_ident: Ident,
+    pub store_ty: Type,
+}
+
+pub struct EventStore {
+    /// Appends the event if it was accepted, given an
+    /// `outcome` variable.
+    pub append: TokenStream,
+    /// Clones the handled event while a store is attached,
+    /// given an `event` variable, before it is moved.
+    pub capture: TokenStream,
+    /// The machine's fields holding the store, and the
+    /// last error it returned.
+    pub fields: TokenStream,
+    /// Initializes `fields`.
+    pub init: TokenStream,
+    /// Methods to add to the machine.
+    pub methods: TokenStream,
+}
+
+/// Generates how the machine appends the events it accepts
+/// to an event store, and how it is rebuilt from one by
+/// handling the stored events again, hooks included.
+///
+/// Appending clones the events, so the event enum must
+/// implement `Clone`.
+pub fn event_store(input: EventStoreInput) -> EventStore {
+    let EventStoreInput {
+        asyncness,
+        store_ty,
+        state_enum_ident,
+        event_enum_ident,
+        outcome_ident,
+        context_path,
+        replay,
+        after_rebuild,
+    } = input;
+
+    let after_rebuild = after_rebuild.map(|after_rebuild| {
+        quote! {
+            let state = self.state.as_ref().expect("state is missing");
+            #after_rebuild
+        }
+    });
+    let store_trait = quote! {
+        ::machine_factory_runtime::EventStore<#event_enum_ident, #state_enum_ident, #context_path>
+    };
+    let error_ty =
+        quote!(<#store_ty as #store_trait>::Error);
+    let rebuild_error_ty = quote! {
+        ::machine_factory_runtime::RebuildError<#error_ty, #event_enum_ident, #outcome_ident>
+    };
+
+    EventStore {
+        fields: quote! {
+            event_store: ::core::option::Option<#store_ty>,
+            event_store_error: ::core::option::Option<#error_ty>,
+        },
+        init: quote! {
+            event_store: ::core::option::Option::None,
+            event_store_error: ::core::option::Option::None,
+        },
+        methods: quote! {
+            pub #asyncness fn rebuild(
+                mut self,
+                event_store: #store_ty,
+            ) -> ::core::result::Result<Self, #rebuild_error_ty> {
+                let stored = <#store_ty as #store_trait>::load(&event_store)
+                    .map_err(::machine_factory_runtime::RebuildError::Store)?;
+
+                if let ::core::option::Option::Some((state, context)) = stored.snapshot {
+                    self.state = ::core::option::Option::Some(state);
+                    self.context = context;
+                }
+
+                for (index, event) in stored.events.into_iter().enumerate() {
+                    let state = self.state.take().expect("state is missing");
+                    let (state, outcome) = {
+                        let event = ::core::clone::Clone::clone(&event);
+                        #replay
+                    };
+                    self.state = ::core::option::Option::Some(state);
+
+                    if !::core::matches!(outcome, #outcome_ident::Transitioned { .. }) {
+                        return ::core::result::Result::Err(::machine_factory_runtime::RebuildError::Diverged {
+                            index,
+                            event,
+                            outcome,
+                        });
+                    }
+                }
+
+                #after_rebuild
+                self.event_store = ::core::option::Option::Some(event_store);
+                ::core::result::Result::Ok(self)
+            }
+
+            pub fn event_store(&self) -> ::core::option::Option<&#store_ty> {
+                self.event_store.as_ref()
+            }
+
+            pub fn save_snapshot(&mut self) -> ::core::result::Result<(), #error_ty> {
+                match self.event_store.as_mut() {
+                    ::core::option::Option::Some(event_store) => {
+                        let state = self.state.as_ref().expect("state is missing");
+                        <#store_ty as #store_trait>::save_snapshot(event_store, state, &self.context)
+                    }
+                    ::core::option::Option::None => ::core::result::Result::Ok(()),
+                }
+            }
+
+            pub fn take_event_store_error(&mut self) -> ::core::option::Option<#error_ty> {
+                self.event_store_error.take()
+            }
+        },
+        capture: quote! {
+            let stored_event = self.event_store.is_some().then(|| ::core::clone::Clone::clone(&event));
+        },
+        append: quote! {
+            if let (#outcome_ident::Transitioned { .. }, ::core::option::Option::Some(event_store), ::core::option::Option::Some(event)) = (&outcome, self.event_store.as_mut(), stored_event) {
+                if let ::core::result::Result::Err(error) = <#store_ty as #store_trait>::append(event_store, &event) {
+                    self.event_store_error = ::core::option::Option::Some(error);
+                }
+            }
+        },
+    }
+}
@@ -1,169 +1,60 @@
-use syn::{
-    FnArg, ItemTrait, Path, ReturnType, Token, TraitItem,
-    TraitItemFn, Type,
+use crate::hook_args::{
+    HookArgs, HookSignature, ensure_hook_signature,
+    ensure_no_return, find_hook,
 };
+use quote::format_ident;
+use syn::{ItemTrait, Path, Token, TraitItem};
 
 pub fn ensure_event_trait(
     asyncness: Option<Token![async]>,
     trait_: &mut ItemTrait,
     context_path: &Path,
+    hook_args: HookArgs<'_>,
 ) -> syn::Result<()> {
-    ensure_pre_transition_fn(
+    ensure_transition_fn(
         asyncness,
         trait_,
+        "pre_transition",
         context_path,
+        hook_args,
     )?;
-    ensure_post_transition_fn(
+    ensure_transition_fn(
         asyncness,
         trait_,
+        "post_transition",
         context_path,
+        hook_args,
     )?;
     Ok(())
 }
 
-fn ensure_pre_transition_fn(
+fn ensure_transition_fn(
     asyncness: Option<Token![async]>,
     trait_: &mut ItemTrait,
+    name: &str,
     context_path: &Path,
+    hook_args: HookArgs<'_>,
 ) -> syn::Result<()> {
-    #[allow(clippy::wildcard_enum_match_arm)]
-    let func =
-        trait_.items.iter().find_map(|item| match item {
-            TraitItem::Fn(f)
-                if f.sig.ident == "pre_transition" =>
-            {
-                Some(f)
-            }
-            _ => None,
-        });
-
-    if let Some(func) = func {
-        check_transition_fn(func, context_path)?;
-    } else {
-        let pre_transition = syn::parse_quote! {
-            #asyncness fn pre_transition(&mut self, context: &mut #context_path) {}
-        };
-
-        trait_.items.push(TraitItem::Fn(pre_transition));
-    }
-
-    Ok(())
-}
-
-fn ensure_post_transition_fn(
-    asyncness: Option<Token![async]>,
-    trait_: &mut ItemTrait,
-    context_path: &Path,
-) -> syn::Result<()> {
-    #[allow(clippy::wildcard_enum_match_arm)]
-    let func =
-        trait_.items.iter().find_map(|item| match item {
-            TraitItem::Fn(f)
-                if f.sig.ident == "post_transition" =>
-            {
-                Some(f)
-            }
-            _ => None,
-        });
-
-    if let Some(func) = func {
-        check_transition_fn(func, context_path)?;
-    } else {
-        let post_transition = syn::parse_quote! {
-            #asyncness fn post_transition(&mut self, context: &mut #context_path) {}
-        };
-
-        trait_.items.push(TraitItem::Fn(post_transition));
-    }
-
-    Ok(())
-}
-
-fn check_transition_fn(
-    func: &TraitItemFn,
-    context_path: &Path,
-) -> syn::Result<()> {
-    const FIRST_ARG_ERROR: &str =
-        "must accept `&mut self` as the first argument";
-    const SECOND_ARG_ERROR: &str = "must accept `&mut {Context}` as the second argument";
-
-    let mut inputs = func.sig.inputs.iter();
-
-    let first_input = inputs.next();
-
-    let Some(first_input) = first_input else {
-        return Err(syn::Error::new_spanned(
-            func,
-            FIRST_ARG_ERROR,
-        ));
-    };
-
-    let FnArg::Receiver(first_input) = first_input else {
-        return Err(syn::Error::new_spanned(
+    if let Some(func) = find_hook(trait_, name) {
+        ensure_hook_signature(
             func,
-            FIRST_ARG_ERROR,
-        ));
-    };
-
-    if first_input.mutability.is_none()
-        || first_input.reference.is_none()
-    {
-        return Err(syn::Error::new_spanned(
-            first_input,
-            FIRST_ARG_ERROR,
-        ));
+            context_path,
+            HookSignature {
+                mutable: true,
+                event_enum: None,
+            },
+            hook_args,
+        )?;
+
+        return ensure_no_return(func);
     }
 
-    let second_input = inputs.next();
-
-    let Some(second_input) = second_input else {
-        return Err(syn::Error::new_spanned(
-            second_input,
-            SECOND_ARG_ERROR,
-        ));
-    };
-
-    let FnArg::Typed(second_input) = second_input else {
-        return Err(syn::Error::new_spanned(
-            second_input,
-            SECOND_ARG_ERROR,
-        ));
-    };
-
-    #[allow(clippy::wildcard_enum_match_arm)]
-    let second_input_ty = match &*second_input.ty {
-        Type::Reference(r) if r.mutability.is_some() => {
-            r.elem.as_ref()
-        }
-        _ => {
-            return Err(syn::Error::new_spanned(
-                second_input,
-                SECOND_ARG_ERROR,
-            ));
-        }
+    let ident = format_ident!("{name}");
+    let func = syn::parse_quote! {
+        #asyncness fn #ident(&mut self, context: &mut #context_path) {}
     };
 
-    if !matches!(second_input_ty, Type::Path(p) if p.path.eq(context_path))
-    {
-        return Err(syn::Error::new_spanned(
-            second_input,
-            SECOND_ARG_ERROR,
-        ));
-    }
-
-    if inputs.next().is_some() {
-        return Err(syn::Error::new_spanned(
-            func,
-            "must accept exactly two arguments",
-        ));
-    }
-
-    if !matches!(func.sig.output, ReturnType::Default) {
-        return Err(syn::Error::new_spanned(
-            func,
-            "must not have a return type",
-        ));
-    }
+    trait_.items.push(TraitItem::Fn(func));
 
     Ok(())
 }
@@ -0,0 +1,79 @@
+use quote::quote;
+use syn::{Ident, Visibility};
+
+pub struct HandlingInput {
+    pub event_enum_ident: Ident,
+    pub event_kind_ident: Ident,
+    pub has_fallback: bool,
+    pub ident: Ident,
+    pub state_kind_ident: Ident,
+    /// The events with a transition from each state.
+    pub transitions: Vec<(Ident, Vec<Ident>)>,
+    pub visibility: Option<Visibility>,
+}
+
+pub struct Handling {
+    /// The `{Name}Handling` enum, spliced next to the
+    /// machine.
+    pub enum_item: proc_macro2::TokenStream,
+    /// The methods, spliced in an `impl` block of the
+    /// machine.
+    pub methods: proc_macro2::TokenStream,
+}
+
+/// Generates the machine's methods reporting which events
+/// its current state handles, and the enum they return.
+pub fn handling(input: HandlingInput) -> Handling {
+    let HandlingInput {
+        visibility,
+        ident,
+        state_kind_ident,
+        event_kind_ident,
+        event_enum_ident,
+        transitions,
+        has_fallback,
+    } = input;
+
+    let arms = transitions.iter().map(|(state_ident, event_idents)| {
+        quote! {
+            #state_kind_ident::#state_ident => &[#(#event_kind_ident::#event_idents),*],
+        }
+    });
+
+    let fallback = if has_fallback {
+        quote!(#ident::Fallback)
+    } else {
+        quote!(#ident::Unhandled)
+    };
+
+    let enum_item = quote! {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #visibility enum #ident {
+            Transition,
+            Fallback,
+            Unhandled,
+        }
+    };
+
+    let methods = quote! {
+        pub fn allowed_events(&self) -> &'static [#event_kind_ident] {
+            match self.state().kind() {
+                #(#arms)*
+            }
+        }
+
+        pub fn can_handle(&self, event: &#event_enum_ident) -> #ident {
+            self.can_handle_kind(event.kind())
+        }
+
+        pub fn can_handle_kind(&self, event: #event_kind_ident) -> #ident {
+            if self.allowed_events().contains(&event) {
+                #ident::Transition
+            } else {
+                #fallback
+            }
+        }
+    };
+
+    Handling { enum_item, methods }
+}
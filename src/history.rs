@@ -0,0 +1,155 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{token::Async, Ident, LitInt, Path};
+
+pub struct HistoryInput {
+    pub asyncness: Option<Async>,
+    /// Binds `clock` to the machine's clock, if it has one.
+    pub bind_clock: Option<TokenStream>,
+    /// Publishes, times and saves the restored state, given
+    /// `outcome` and `state` variables, like after a
+    /// transition.
+    pub bookkeeping: TokenStream,
+    pub capacity: LitInt,
+    pub context_path: Path,
+    pub event_enum_ident: Ident,
+    /// Calls `on_enter`, given `state`, `context`, `clock`
+    /// and `event` variables.
+    pub on_enter: TokenStream,
+    /// Calls `on_exit`, given `state`, `context`, `clock`
+    /// and `event` variables.
+    pub on_exit: TokenStream,
+    pub outcome_ident: Ident,
+    /// Returns `false` from undo and redo when they're
+    /// refused, e.g. while recording a trace, which can't
+    /// replay them.
+    pub refused: Option<TokenStream>,
+    pub state_enum_ident: Ident,
+}
+
+pub struct History {
+    /// Clones the state, context and event before the
+    /// pipeline, given `state` and `event` variables.
+    pub capture: TokenStream,
+    /// The machine's field holding the history.
+    pub field: TokenStream,
+    /// Initializes `field`.
+    pub init: TokenStream,
+    /// Methods to add to the machine.
+    pub methods: TokenStream,
+    /// Records the clones if the event caused a
+    /// transition, given an `outcome` variable.
+    pub record: TokenStream,
+}
+
+/// Generates how the machine records its transitions, and
+/// undoes and redoes them.
+///
+/// The history clones the state, the context and the
+/// event, so they must all implement `Clone`.
+pub fn history(input: HistoryInput) -> History {
+    let HistoryInput {
+        asyncness,
+        capacity,
+        state_enum_ident,
+        event_enum_ident,
+        outcome_ident,
+        context_path,
+        bind_clock,
+        on_exit,
+        on_enter,
+        bookkeeping,
+        refused,
+    } = input;
+
+    let async_postfix = asyncness.map(|_| quote!(.await));
+    let history_ty = quote! {
+        ::machine_factory_runtime::History<#state_enum_ident, #context_path, #event_enum_ident>
+    };
+    let entry_ty = quote! {
+        ::machine_factory_runtime::HistoryEntry<#state_enum_ident, #context_path, #event_enum_ident>
+    };
+
+    // `undo` and `redo` only differ by the `History` method
+    // they call
+    let step = |method: &Ident| {
+        quote! {
+            pub #asyncness fn #method(&mut self) -> bool {
+                #refused
+
+                let state = self.state.take().expect("state is missing");
+                let context = ::core::clone::Clone::clone(&self.context);
+
+                match self.history.#method(::core::clone::Clone::clone(&state), context) {
+                    ::core::result::Result::Ok(entry) => {
+                        self.restore_history_entry(state, entry)#async_postfix;
+                        true
+                    }
+                    ::core::result::Result::Err(_) => {
+                        self.state = ::core::option::Option::Some(state);
+                        false
+                    }
+                }
+            }
+        }
+    };
+    let undo = step(&Ident::new("undo", capacity.span()));
+    let redo = step(&Ident::new("redo", capacity.span()));
+
+    History {
+        field: quote! {
+            history: #history_ty,
+        },
+        init: quote! {
+            history: ::machine_factory_runtime::History::new(#capacity),
+        },
+        methods: quote! {
+            pub fn with_undo_policy(mut self, policy: ::machine_factory_runtime::UndoPolicy) -> Self {
+                self.history.set_policy(policy);
+                self
+            }
+
+            pub fn history(&self) -> &#history_ty {
+                &self.history
+            }
+
+            #undo
+
+            #redo
+
+            #asyncness fn restore_history_entry(&mut self, mut state: #state_enum_ident, entry: #entry_ty) {
+                let ::machine_factory_runtime::HistoryEntry { state: restored, context, event } = entry;
+                let from = state.kind();
+                self.context = context;
+
+                if self.history.policy() == ::machine_factory_runtime::UndoPolicy::RunHooks {
+                    let context = &mut self.context;
+                    #bind_clock
+
+                    #on_exit
+                    state = restored;
+                    #on_enter
+                } else {
+                    state = restored;
+                }
+
+                let outcome = #outcome_ident::Transitioned { from, to: state.kind() };
+                #bookkeeping
+
+                self.state = ::core::option::Option::Some(state);
+            }
+        },
+        capture: quote! {
+            let history_entry = ::machine_factory_runtime::HistoryEntry {
+                state: ::core::clone::Clone::clone(&state),
+                context: ::core::clone::Clone::clone(&self.context),
+                event: ::core::clone::Clone::clone(&event),
+            };
+        },
+        record: quote! {
+            if let #outcome_ident::Transitioned { .. } = outcome {
+                self.history.record(history_entry);
+            }
+        },
+    }
+}
@@ -0,0 +1,286 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    FnArg, Ident, ItemTrait, Path, ReturnType, Signature,
+    TraitItem, TraitItemFn, Type,
+};
+
+/// Arguments that a lifecycle hook may optionally accept
+/// after its required arguments, each by reference.
+#[derive(Clone, Copy)]
+pub struct HookArgs<'a> {
+    pub clock: Option<&'a Type>,
+    /// The event being handled.
+    pub event: Option<&'a Type>,
+    /// The state the hook doesn't already receive: the
+    /// current state before the transition block, and the
+    /// next state after it.
+    pub state: Option<&'a Type>,
+}
+
+/// The values passed for the optional arguments of a hook.
+pub struct HookValues {
+    pub clock: TokenStream,
+    pub event: TokenStream,
+    pub state: TokenStream,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum HookArg {
+    Clock,
+    Event,
+    State,
+}
+
+impl<'a> HookArgs<'a> {
+    fn args(
+        self,
+    ) -> impl Iterator<Item = (HookArg, &'static str, &'a Type)>
+    {
+        [
+            (HookArg::Clock, "Clock", self.clock),
+            (HookArg::Event, "EventEnum", self.event),
+            (HookArg::State, "StateEnum", self.state),
+        ]
+        .into_iter()
+        .filter_map(|(arg, name, ty)| {
+            ty.map(|ty| (arg, name, ty))
+        })
+    }
+
+    fn find(self, ty: &Type) -> Option<HookArg> {
+        self.args()
+            .find(|(_, _, arg_ty)| *arg_ty == ty)
+            .map(|(arg, _, _)| arg)
+    }
+}
+
+/// The required arguments of a lifecycle hook.
+#[derive(Clone, Copy)]
+pub struct HookSignature<'a> {
+    /// Whether the hook accepts `&mut self` and
+    /// `&mut {Context}` rather than `&self` and
+    /// `&{Context}`.
+    pub mutable: bool,
+    /// The event enum, if the hook accepts `&{EventEnum}`
+    /// as its third argument.
+    pub event_enum: Option<&'a Ident>,
+}
+
+/// Returns the hook named `name` if `trait_` declares it.
+pub fn find_hook<'t>(
+    trait_: &'t ItemTrait,
+    name: &str,
+) -> Option<&'t TraitItemFn> {
+    trait_.items.iter().find_map(|item| {
+        let TraitItem::Fn(func) = item else {
+            return None;
+        };
+
+        (func.sig.ident == name).then_some(func)
+    })
+}
+
+/// Ensures that `func` accepts the required arguments of
+/// `signature`, followed by optional hook arguments.
+pub fn ensure_hook_signature(
+    func: &TraitItemFn,
+    context_path: &Path,
+    signature: HookSignature<'_>,
+    hook_args: HookArgs<'_>,
+) -> syn::Result<()> {
+    let (first_arg_error, second_arg_error) = if signature
+        .mutable
+    {
+        (
+            "must accept `&mut self` as the first argument",
+            "must accept `&mut {Context}` as the second argument",
+        )
+    } else {
+        (
+            "must accept `&self` as the first argument",
+            "must accept `&{Context}` as the second argument",
+        )
+    };
+
+    let mut inputs = func.sig.inputs.iter();
+
+    let Some(FnArg::Receiver(receiver)) = inputs.next()
+    else {
+        return Err(syn::Error::new_spanned(
+            func,
+            first_arg_error,
+        ));
+    };
+
+    if receiver.reference.is_none()
+        || receiver.mutability.is_some()
+            != signature.mutable
+    {
+        return Err(syn::Error::new_spanned(
+            receiver,
+            first_arg_error,
+        ));
+    }
+
+    ensure_ref_arg(
+        func,
+        inputs.next(),
+        signature.mutable,
+        |path| path == context_path,
+        second_arg_error,
+    )?;
+
+    let error = if let Some(event_enum) =
+        signature.event_enum
+    {
+        ensure_ref_arg(
+            func,
+            inputs.next(),
+            false,
+            |path| path.is_ident(event_enum),
+            "must accept `&{EventEnum}` as the third argument",
+        )?;
+
+        "must not have more than three arguments"
+    } else {
+        "must not have more than two arguments"
+    };
+
+    ensure_hook_args(func, inputs, hook_args, error)
+}
+
+/// Ensures that `func` has no return type.
+pub fn ensure_no_return(
+    func: &TraitItemFn,
+) -> syn::Result<()> {
+    if matches!(func.sig.output, ReturnType::Default) {
+        Ok(())
+    } else {
+        Err(syn::Error::new_spanned(
+            func,
+            "must not have a return type",
+        ))
+    }
+}
+
+/// Ensures that `input` is a reference, `mutable` or not,
+/// to a type whose path is accepted by `is_expected`.
+fn ensure_ref_arg(
+    func: &TraitItemFn,
+    input: Option<&FnArg>,
+    mutable: bool,
+    is_expected: impl FnOnce(&Path) -> bool,
+    error: &str,
+) -> syn::Result<()> {
+    let Some(input) = input else {
+        return Err(syn::Error::new_spanned(func, error));
+    };
+
+    let FnArg::Typed(typed) = input else {
+        return Err(syn::Error::new_spanned(input, error));
+    };
+
+    let Type::Reference(reference) = &*typed.ty else {
+        return Err(syn::Error::new_spanned(input, error));
+    };
+
+    let Type::Path(path) = &*reference.elem else {
+        return Err(syn::Error::new_spanned(input, error));
+    };
+
+    if reference.mutability.is_some() != mutable
+        || !is_expected(&path.path)
+    {
+        return Err(syn::Error::new_spanned(input, error));
+    }
+
+    Ok(())
+}
+
+/// Ensures that every argument of `func` after the
+/// required ones is one of the optional hook arguments,
+/// each accepted at most once.
+fn ensure_hook_args<'a, Inputs>(
+    func: &TraitItemFn,
+    inputs: Inputs,
+    hook_args: HookArgs<'_>,
+    error: &str,
+) -> syn::Result<()>
+where
+    Inputs: Iterator<Item = &'a FnArg>,
+{
+    let mut seen = Vec::new();
+
+    for input in inputs {
+        match hook_arg_ty(input)
+            .and_then(|ty| hook_args.find(ty))
+        {
+            Some(arg) if !seen.contains(&arg) => {
+                seen.push(arg);
+            }
+            _ => {
+                let optional = hook_args
+                    .args()
+                    .map(|(_, name, _)| {
+                        format!("`&{{{name}}}`")
+                    })
+                    .collect::<Vec<_>>();
+
+                let error = if optional.is_empty() {
+                    error.to_owned()
+                } else {
+                    format!(
+                        "{error}, other than an optional {}",
+                        optional.join(", ")
+                    )
+                };
+
+                return Err(syn::Error::new_spanned(
+                    func, error,
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Builds the values passed for the optional arguments of
+/// a hook, given the number of required arguments
+/// (including the receiver).
+pub fn hook_arg_values(
+    sig: &Signature,
+    required: usize,
+    hook_args: HookArgs<'_>,
+    values: &HookValues,
+) -> Vec<TokenStream> {
+    sig.inputs
+        .iter()
+        .skip(required)
+        .filter_map(|input| {
+            let value = match hook_args
+                .find(hook_arg_ty(input)?)?
+            {
+                HookArg::Clock => &values.clock,
+                HookArg::Event => &values.event,
+                HookArg::State => &values.state,
+            };
+
+            Some(quote!(#value))
+        })
+        .collect()
+}
+
+/// Returns `T` if `input` is typed as `&T`.
+fn hook_arg_ty(input: &FnArg) -> Option<&Type> {
+    let FnArg::Typed(input) = input else {
+        return None;
+    };
+
+    let Type::Reference(r) = &*input.ty else {
+        return None;
+    };
+
+    r.mutability.is_none().then_some(r.elem.as_ref())
+}
@@ -0,0 +1,41 @@
+use quote::quote;
+use syn::{token::Async, Ident, Path, Visibility};
+
+pub struct HooksInput {
+    pub asyncness: Option<Async>,
+    pub context_path: Path,
+    pub event_enum_ident: Ident,
+    pub ident: Ident,
+    pub state_kind_ident: Ident,
+    pub visibility: Option<Visibility>,
+}
+
+/// Generates the trait of the machine-level hooks, which
+/// are called for every transition of the machine.
+pub fn hooks(input: HooksInput) -> proc_macro2::TokenStream {
+    let HooksInput {
+        visibility,
+        asyncness,
+        ident,
+        state_kind_ident,
+        event_enum_ident,
+        context_path,
+    } = input;
+
+    let async_trait_attr = asyncness
+        .is_some()
+        .then(|| quote!(#[::async_trait::async_trait]));
+
+    quote! {
+        #async_trait_attr
+        #visibility trait #ident {
+            #asyncness fn on_transition(
+                _from: &#state_kind_ident,
+                _to: &#state_kind_ident,
+                _event: &#event_enum_ident,
+                _context: &mut #context_path,
+            ) {
+            }
+        }
+    }
+}
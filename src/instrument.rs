@@ -0,0 +1,87 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{token::Async, Ident};
+
+pub struct InstrumentInput {
+    pub asyncness: Option<Async>,
+    pub enabled: bool,
+    pub machine_ident: Ident,
+}
+
+/// Generates the `tracing` span and events of a machine
+/// with `tracing: true`. When disabled, nothing is
+/// generated.
+pub struct Instrument {
+    input: InstrumentInput,
+}
+
+impl Instrument {
+    /// Emits an event before calling `hook`.
+    pub fn hook(&self, hook: &str) -> Option<TokenStream> {
+        self.input.enabled.then(|| {
+            quote!(::tracing::trace!(hook = #hook, "calling hook");)
+        })
+    }
+
+    pub const fn new(input: InstrumentInput) -> Self {
+        Self { input }
+    }
+
+    /// Emits an event when the guard rejects the event,
+    /// with the rejection `reason`, if any.
+    pub fn rejected(
+        &self,
+        has_reason: bool,
+    ) -> Option<TokenStream> {
+        self.input.enabled.then(|| {
+            if has_reason {
+                quote!(::tracing::debug!(reason = ?reason, "event rejected");)
+            } else {
+                quote!(::tracing::debug!("event rejected");)
+            }
+        })
+    }
+
+    /// Emits an event with the resulting state, given
+    /// `from` and `to` state kinds.
+    pub fn transitioned(&self) -> Option<TokenStream> {
+        self.input.enabled.then(|| {
+            quote!(::tracing::info!(from = %from, to = %to, "transitioned");)
+        })
+    }
+
+    /// Runs `body` in a span with the machine's name, and
+    /// the kinds of the current state (`from`) and `event`.
+    pub fn wrap(&self, body: &TokenStream) -> TokenStream {
+        let InstrumentInput { enabled, asyncness, machine_ident } =
+            &self.input;
+
+        if !enabled {
+            return body.clone();
+        }
+
+        let machine = machine_ident.to_string();
+
+        let span = quote! {
+            ::tracing::info_span!(
+                "handle_event",
+                machine = #machine,
+                state = %from,
+                event = %event.kind(),
+            )
+        };
+
+        if asyncness.is_some() {
+            quote! {
+                let span = #span;
+                ::tracing::Instrument::instrument(async move { #body }, span).await
+            }
+        } else {
+            quote! {
+                let span = #span;
+                let _entered = span.enter();
+                #body
+            }
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::Ident;
+
+pub struct InterceptorsInput {
+    pub outcome_ident: Ident,
+}
+
+pub struct Interceptors {
+    /// Calls the interceptors after the event is handled,
+    /// given `state` and `outcome` variables.
+    pub after: TokenStream,
+    /// Calls the interceptors before the event is handled,
+    /// given `state` and `event` variables, and returns a
+    /// `Vetoed` outcome if one of them vetoes it.
+    pub before: TokenStream,
+    /// The machine's field holding the interceptors.
+    pub field: TokenStream,
+    /// Initializes `field`.
+    pub init: TokenStream,
+    /// Methods to add to the machine.
+    pub methods: TokenStream,
+}
+
+/// Generates how the machine holds its interceptors, and
+/// calls them around the pipeline.
+pub fn interceptors(input: InterceptorsInput) -> Interceptors {
+    let InterceptorsInput { outcome_ident } = input;
+
+    Interceptors {
+        field: quote! {
+            interceptors: ::machine_factory_runtime::Interceptors<Self>,
+        },
+        init: quote! {
+            interceptors: ::core::default::Default::default(),
+        },
+        methods: quote! {
+            pub fn layer<Layer>(mut self, interceptor: Layer) -> Self
+            where
+                Layer: ::machine_factory_runtime::Interceptor<Self> + ::core::clone::Clone + ::core::marker::Send + 'static,
+            {
+                self.interceptors.push(interceptor);
+                self
+            }
+
+            pub fn interceptors(&self) -> &::machine_factory_runtime::Interceptors<Self> {
+                &self.interceptors
+            }
+        },
+        before: quote! {
+            if self.interceptors.before(&state, &self.context, &event) == ::machine_factory_runtime::Verdict::Veto {
+                let outcome = #outcome_ident::Vetoed { state: state.kind() };
+                self.interceptors.after(&state, &self.context, &outcome);
+                self.state = ::core::option::Option::Some(state);
+                return outcome;
+            }
+        },
+        after: quote! {
+            self.interceptors.after(&state, &self.context, &outcome);
+        },
+    }
+}
@@ -0,0 +1,96 @@
+use quote::{format_ident, quote};
+use syn::{Ident, Visibility};
+
+pub struct IntrospectionInput {
+    pub events: Vec<String>,
+    pub machine_ident: Ident,
+    pub states: Vec<String>,
+    pub transitions: Vec<TransitionDescription>,
+    pub visibility: Option<Visibility>,
+}
+
+pub struct TransitionDescription {
+    pub event: String,
+    pub from: String,
+    pub line: usize,
+    /// The target state, if it's known statically.
+    pub target: Option<String>,
+}
+
+pub struct Introspection {
+    /// The associated constants, spliced in an `impl` block
+    /// of the machine.
+    pub constants: proc_macro2::TokenStream,
+    /// The `{Name}TransitionInfo` and `{Name}TransitionTarget`
+    /// types, spliced next to the machine.
+    pub types: proc_macro2::TokenStream,
+}
+
+/// Generates the `STATES`, `EVENTS` and `TRANSITIONS`
+/// associated constants describing a machine, and the
+/// types of the `TRANSITIONS` table.
+pub fn introspection(input: IntrospectionInput) -> Introspection {
+    let IntrospectionInput {
+        visibility,
+        machine_ident,
+        states,
+        events,
+        transitions,
+    } = input;
+
+    let info_ident =
+        format_ident!("{}TransitionInfo", machine_ident);
+    let target_ident =
+        format_ident!("{}TransitionTarget", machine_ident);
+
+    let transitions = transitions.into_iter().map(
+        |TransitionDescription { from, event, target, line }| {
+            let target = target.map_or_else(
+                || quote!(#target_ident::Dynamic),
+                |target| quote!(#target_ident::State(#target)),
+            );
+
+            quote! {
+                #info_ident {
+                    from: #from,
+                    event: #event,
+                    target: #target,
+                    line: #line,
+                }
+            }
+        },
+    );
+
+    let types = quote! {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        #visibility struct #info_ident {
+            pub from: &'static str,
+            pub event: &'static str,
+            pub target: #target_ident,
+            pub line: usize,
+        }
+
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        #visibility enum #target_ident {
+            State(&'static str),
+            Dynamic,
+        }
+
+        impl ::core::fmt::Display for #target_ident {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                match *self {
+                    Self::State(name) => f.write_str(name),
+                    Self::Dynamic => f.write_str("dynamic"),
+                }
+            }
+        }
+    };
+
+    let constants = quote! {
+        pub const STATES: &'static [&'static str] = &[#(#states),*];
+        pub const EVENTS: &'static [&'static str] = &[#(#events),*];
+        pub const TRANSITIONS: &'static [#info_ident] = &[#(#transitions),*];
+    };
+
+    Introspection { constants, types }
+}
@@ -0,0 +1,161 @@
+use quote::{format_ident, quote};
+use syn::{Ident, ItemTrait, TraitItem, Visibility};
+
+pub struct KindInput {
+    pub enum_ident: Ident,
+    pub ident: Ident,
+    /// Whether to implement `machine_factory_runtime::Kind`.
+    pub runtime: bool,
+    /// Whether to derive `serde`'s traits.
+    pub serializable: bool,
+    pub variants: Vec<Ident>,
+    pub visibility: Option<Visibility>,
+}
+
+/// Generates a fieldless enum with a variant for each
+/// variant of `enum_ident`, named after the variants, and
+/// `kind` and `name` methods to get them. Parsing a kind
+/// from an unknown name returns a generated
+/// `Parse{Kind}Error`.
+pub fn kind(input: KindInput) -> proc_macro2::TokenStream {
+    let KindInput {
+        visibility,
+        enum_ident,
+        ident,
+        variants,
+        serializable,
+        runtime,
+    } = input;
+
+    let error_ident = format_ident!("Parse{}Error", ident);
+    let error_kind = ident.to_string();
+
+    let indices = (0..variants.len()).collect::<Vec<_>>();
+
+    let derive_serde = serializable.then(|| {
+        quote!(#[derive(::serde::Serialize, ::serde::Deserialize)])
+    });
+
+    let names = variants
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+
+    let runtime_kind = runtime.then(|| {
+        quote! {
+            impl ::machine_factory_runtime::Kind for #ident {
+                fn name(self) -> &'static str {
+                    Self::name(self)
+                }
+
+                fn index(self) -> usize {
+                    match self {
+                        #(Self::#variants => #indices,)*
+                    }
+                }
+
+                fn from_index(index: usize) -> ::core::option::Option<Self> {
+                    match index {
+                        #(#indices => ::core::option::Option::Some(Self::#variants),)*
+                        _ => ::core::option::Option::None,
+                    }
+                }
+            }
+        }
+    });
+
+    quote! {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #derive_serde
+        #visibility enum #ident {
+            #(#variants),*
+        }
+
+        #[allow(clippy::same_name_method)]
+        impl #ident {
+            pub const fn name(self) -> &'static str {
+                match self {
+                    #(Self::#variants => #names,)*
+                }
+            }
+        }
+
+        #runtime_kind
+
+        impl ::core::fmt::Display for #ident {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                f.write_str(self.name())
+            }
+        }
+
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        #visibility struct #error_ident {
+            name: ::std::string::String,
+        }
+
+        impl #error_ident {
+            pub fn name(&self) -> &str {
+                &self.name
+            }
+        }
+
+        impl ::core::fmt::Display for #error_ident {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                write!(f, "unknown {} `{}`", #error_kind, self.name)
+            }
+        }
+
+        impl ::std::error::Error for #error_ident {}
+
+        impl ::core::str::FromStr for #ident {
+            type Err = #error_ident;
+
+            fn from_str(name: &str) -> ::core::result::Result<Self, Self::Err> {
+                match name {
+                    #(#names => ::core::result::Result::Ok(Self::#variants),)*
+                    _ => ::core::result::Result::Err(#error_ident { name: name.into() }),
+                }
+            }
+        }
+
+        impl #enum_ident {
+            pub fn kind(&self) -> #ident {
+                match self {
+                    #(Self::#variants(_) => #ident::#variants,)*
+                }
+            }
+
+            pub fn name(&self) -> &'static str {
+                self.kind().name()
+            }
+        }
+    }
+}
+
+/// Rejects the methods of a trait that would be shadowed by
+/// the `kind` and `name` methods generated on `enum_ident`,
+/// which implements the trait.
+pub fn ensure_no_kind_methods(
+    item_trait: &ItemTrait,
+    enum_ident: &Ident,
+) -> syn::Result<()> {
+    item_trait
+        .items
+        .iter()
+        .filter_map(|item| {
+            if let TraitItem::Fn(f) = item {
+                Some(&f.sig.ident)
+            } else {
+                None
+            }
+        })
+        .find(|ident| *ident == "kind" || *ident == "name")
+        .map_or(Ok(()), |ident| {
+            Err(syn::Error::new(
+                ident.span(),
+                format!(
+                    "`{ident}` would be shadowed by the method generated on `{enum_ident}`"
+                ),
+            ))
+        })
+}
@@ -2,12 +2,34 @@
 
 use proc_macro::TokenStream;
 
+mod actor;
 mod deterministic_state_machine;
+mod effects;
 mod event_driven_state_machine;
 mod event_enum;
+mod event_store;
 mod event_trait;
+mod handling;
+mod history;
+mod hook_args;
+mod hooks;
+mod instrument;
+mod interceptor;
+mod introspection;
+mod kind;
+mod machine_traits;
+mod metrics;
+mod observer;
+mod outcome;
+mod persister;
+mod recording;
+mod request;
+mod service;
+mod shared;
 mod state_enum;
 mod state_trait;
+mod stream;
+mod transaction;
 
 /// Build an event driven finite state machine.
 ///
@@ -27,11 +49,429 @@ mod state_trait;
 ///     // - `mut state`: the current state
 ///     // - `&mut context`: the context
 ///     // - `&mut event`: the event
+///     // - `&clock`: the clock (only if a `clock` is specified)
 /// }
 /// Event::post_transition(&mut self, &mut context)
 /// NewState::on_enter(&mut self, &mut context)
 /// ```
 ///
+/// `handle_event` returns the machine, so calls can be
+/// chained. `handle_event_outcome` handles the event in the
+/// same way, but returns a generated `{Identifier}Outcome`
+/// enum: `Rejected { state }` if `should_exit` returned
+/// `false` (in which case only `on_rejected` is called,
+/// see below), and `Transitioned { from, to }` otherwise, with
+/// the kinds of the states.
+///
+/// `should_exit` is the guard phase: it only receives
+/// shared references, so deciding whether to handle an
+/// event has no side effects. When it rejects an event,
+/// the state's
+/// `on_rejected(&mut self, &mut context, &event)` hook is
+/// called instead of the other functions, which is where
+/// rejections can update the context (e.g., counters).
+///
+/// `should_exit` may return a `Result<(), Reason>` instead
+/// of a `bool`, for any `Reason` type implementing `Debug`,
+/// `Clone`, `PartialEq` and `Eq`. An `Err(reason)` rejects
+/// the event, and the reason is returned in the outcome:
+/// `Rejected { state, reason }`.
+///
+/// Besides the clock (see below), `on_enter` and `on_exit`
+/// may declare an additional `&EventEnum` argument to
+/// receive the event being handled, and `pre_transition`
+/// and `post_transition` an additional `&StateEnum`
+/// argument to receive the current state (before the
+/// transition block) or the next state (after it):
+///
+/// ```ignore
+/// fn on_enter(&mut self, context: &mut Context, event: &MyEvent);
+/// fn pre_transition(&mut self, context: &mut Context, from: &MyState);
+/// ```
+///
+/// With `hooks: Type`, the machine also calls
+/// `<Type as {Identifier}Hooks>::on_transition(&from, &to,
+/// &event, &mut context)` after `on_enter`, with the kinds of
+/// the states at both ends of the transition. The
+/// `{Identifier}Hooks` trait is generated, with an empty
+/// default implementation, and is `async` for `async`
+/// machines.
+///
+/// The associated function `step(state, context, event)`
+/// runs the same functions in the same order, over values
+/// instead of a machine, and returns the next state, the
+/// context and the outcome (followed by the produced
+/// effects, if the machine has `effects`). When the
+/// machine has a `clock`, it's passed as a last argument.
+///
+/// Fieldless `{StateEnum}Kind` and `{EventEnum}Kind` enums
+/// are also generated, with a variant for each state and
+/// event, and the state and event enums get a `kind`
+/// method that returns them. Kinds are `Copy` and `Hash`,
+/// and are named after their variant: `name()` and
+/// `Display` return the name (e.g., `"Red"`), which
+/// `FromStr` parses back (unknown names return a generated
+/// `Parse{Kind}Error`). The state and event enums also
+/// have a `name` method, so the state and event traits
+/// can't declare `kind` or `name` methods, which would be
+/// shadowed by them. When a label that relies on the
+/// runtime crate is set, kinds also implement
+/// `machine_factory_runtime::Kind`.
+///
+/// # Clock
+/// A `clock` can optionally be specified, which must be a
+/// type implementing `machine_factory_runtime::Clock`
+/// (e.g., `machine_factory_runtime::SharedClock`). The
+/// machine holds the clock, which is created with
+/// `Default::default()` by `new`, and can be replaced
+/// with `with_clock`. The clock is available to
+/// transition blocks, and any of the hooks above can
+/// accept it by declaring an additional `&Clock` argument
+/// in the trait definition, e.g.:
+///
+/// ```ignore
+/// fn on_enter(&mut self, context: &mut Context, clock: &SharedClock);
+/// ```
+///
+/// This allows all of the time a machine observes to be
+/// controlled in tests with a
+/// `machine_factory_runtime::ManualClock`.
+///
+/// # Tracing
+/// Setting `tracing: true` handles each event in a
+/// `handle_event` span of the `tracing` crate (which must
+/// be a dependency), with the machine's name and the kinds
+/// of the current state and of the event as fields. Within
+/// the span, events are emitted when the event is rejected
+/// (with the reason, if any), before each hook, and with
+/// the kinds of both states once the event is handled.
+/// Without the flag, no tracing code is generated.
+///
+/// # Metrics
+/// Setting `metrics: true` makes the machine record every
+/// transition in a `machine_factory_runtime::TransitionMetrics`,
+/// returned by `metrics()`. It counts the transitions per
+/// `(from, event, to)` kinds, and keeps a histogram of how
+/// long the machine stayed in each state, measured with
+/// the machine's clock if it has one. With the `metrics`
+/// feature of `machine-factory-runtime`, both are also
+/// exported through the `metrics` crate, as the
+/// `state_machine_transitions_total` counter and the
+/// `state_machine_dwell_seconds` histogram, labeled with
+/// the machine's name and the kinds' names.
+///
+/// # Recording
+/// Setting `recording: true` lets the machine record the
+/// events it handles, along with their outcomes, in a
+/// `machine_factory_runtime::Trace`:
+///
+/// - `start_recording()`: starts a new trace.
+/// - `trace()`: returns the trace being recorded, if any.
+/// - `stop_recording()`: stops recording, and returns the
+///   trace.
+/// - `replay(trace)`: handles the events of `trace` with
+///   the machine, which should be configured (e.g., with a
+///   clock or a guard) and in the same state as the
+///   recorded one was. Returns the machine, or a
+///   `machine_factory_runtime::Divergence` for the first
+///   event with a different outcome than the recorded one.
+///
+/// The event enum must implement `Clone`. The kind enums
+/// and the outcome derive `serde`'s traits (so `serde` must
+/// be a dependency, and the rejection reason, if any, must
+/// implement them as well), and with the `serde` feature of
+/// `machine-factory-runtime`, so does the trace if the
+/// event enum does.
+///
+/// # Event sourcing
+/// An `event_store` can optionally be specified, which
+/// must be a type implementing
+/// `machine_factory_runtime::EventStore<EventEnum, StateEnum, Context>`
+/// (e.g., `machine_factory_runtime::InMemoryEventStore`, or
+/// `machine_factory_runtime::FileEventStore` with the
+/// `serde` feature). A machine created with `new` has no
+/// store; the following methods attach one:
+///
+/// - `rebuild(event_store)`: restores the machine, which
+///   should be configured (e.g., with a clock) and in its
+///   initial state, from the latest snapshot in the store
+///   (if any), and handles the events stored after it
+///   again. Each event goes through the whole pipeline,
+///   hooks included, so that the context ends up as it
+///   was, but without effects, persistence, observers or
+///   history. If a stored event is rejected, `rebuild`
+///   returns a
+///   `machine_factory_runtime::RebuildError::Diverged`
+///   instead of silently skipping it. The store is then
+///   attached to the machine, which appends every event it
+///   accepts (i.e., that isn't rejected) to it.
+/// - `save_snapshot()`: stores the current state and
+///   context, so that the events before it don't need to be
+///   handled again by `rebuild`.
+/// - `event_store()`: returns the attached store.
+/// - `take_event_store_error()`: returns the error of the
+///   last failed append, if any, since `handle_event` can't
+///   return it.
+///
+/// The event enum must implement `Clone`.
+///
+/// # Persistence
+/// A `persister` can optionally be specified, which must
+/// be a type implementing
+/// `machine_factory_runtime::Persister<StateEnum, Context>`
+/// (e.g., `machine_factory_runtime::SqlitePersister` with
+/// the `sqlite` feature), in which case the machine must
+/// be `transactional`. A machine given a persister and an
+/// id with `with_persister(persister, id)` saves its state
+/// and context after every transition, once `on_enter`
+/// returns and before the machine-level `hooks` are
+/// called. If the save fails, the hooks aren't called, the
+/// state and the context are restored to what they were
+/// before the event, its effects are discarded, and
+/// `handle_event_outcome`
+/// returns a generated `NotPersisted { state }` outcome,
+/// which isn't observed, recorded, stored or undoable. The
+/// following methods are also generated:
+///
+/// - `id()`: returns the machine's id, if it has one.
+/// - `persist()`: saves the machine now, e.g. before its
+///   first event.
+/// - `load(&persister, id)`: loads the machine `id`, if it
+///   was saved.
+/// - `load_all(&persister)`: loads every saved machine,
+///   e.g. on startup.
+/// - `take_persister_error()`: returns the error of the
+///   last failed save, if any, including saves after
+///   `undo` or `redo`, which aren't rolled back.
+///
+/// # Transactions
+/// Setting `transactional: true` makes handling an event
+/// all-or-nothing: the state and the context are cloned
+/// before the event is handled, and if any stage panics
+/// (e.g., `post_transition` or `on_enter`, after `on_exit`
+/// and `pre_transition` changed the context), both are
+/// restored before the panic is resumed. A caller catching
+/// the panic is left with the machine as it was before the
+/// event. The state enum and the context must implement
+/// `Clone`, and `futures` must be a dependency of `async`
+/// machines.
+///
+/// # History
+/// Setting `history: N` makes the machine keep the state
+/// and context it had before each of its last `N`
+/// transitions, in a `machine_factory_runtime::History`:
+///
+/// - `undo()`: restores the state and context before the
+///   last transition, and returns whether there was one.
+/// - `redo()`: restores the state and context after the
+///   last undone transition, and returns whether there was
+///   one. Handling an event that causes a transition
+///   forgets the undone transitions.
+/// - `with_undo_policy(policy)`: sets whether undoing and
+///   redoing runs the state hooks. With
+///   `machine_factory_runtime::UndoPolicy::RunHooks`, the
+///   context is restored, and then `on_exit` is called on
+///   the current state and `on_enter` on the restored one,
+///   with the event of the undone (or redone) transition.
+///   By default, they are only restored.
+/// - `history()`: returns the history.
+///
+/// Undoing and redoing move the machine like a transition:
+/// observers are notified, the persister saves the restored
+/// state and context, and `metrics` times the state that
+/// was left (without counting a transition). Since they
+/// don't handle events, `history` can't be combined with
+/// an `event_store`, and `undo` and `redo` return `false`
+/// while a trace is being recorded. The state enum, the
+/// context and the event enum must implement `Clone`.
+///
+/// # Interceptors
+/// Setting `interceptors: true` generates a `layer` method,
+/// which adds a `machine_factory_runtime::Interceptor`
+/// around `handle_event` when the machine is built, for
+/// concerns such as authorization, rate limiting, auditing
+/// or deduplication. Like `tower` layers, the first
+/// interceptor added is the outermost:
+///
+/// - `before(state, context, event)` is called in the
+///   order the interceptors were added, before
+///   `should_exit`. If it returns `Verdict::Veto`, the
+///   event is dropped, and `handle_event_outcome` returns
+///   a generated `Vetoed { state }` outcome.
+/// - `after(state, context, outcome)` is called in the
+///   reverse order, on the interceptors that let the event
+///   through.
+///
+/// Vetoed events aren't observed, recorded, stored or
+/// persisted. Clones of a machine get clones of its
+/// interceptors, and the interceptors must implement
+/// `Clone` and `Send`.
+///
+/// # Registries
+/// Setting `registry: true` (or any other label relying on
+/// the runtime crate) implements
+/// `machine_factory_runtime::StateMachine` (and
+/// `machine_factory_runtime::HandleEvent` if the machine
+/// isn't `async`), so that many instances can be kept in a
+/// `machine_factory_runtime::MachineRegistry`, keyed by
+/// id. The registry routes events to machines by id,
+/// creates machines with a factory on their first event,
+/// evicts the machines a predicate marks as finished, and
+/// can save every machine with a
+/// `machine_factory_runtime::Persister`. Restoring them
+/// creates each machine with the factory, and then replaces
+/// its state and context with the saved ones.
+///
+/// # Actor
+/// Setting `actor: true` on an `async` machine generates a
+/// `spawn` method, which moves the machine into a `tokio`
+/// task and returns a cloneable `{Identifier}Handle` with
+/// the following methods. `spawn_with_capacity` takes the
+/// size of the mailbox as a `NonZeroUsize` (`spawn` uses
+/// `machine_factory_runtime::DEFAULT_MAILBOX_CAPACITY`):
+///
+/// - `send(event)`: queues an event without waiting for it
+///   to be handled.
+/// - `ask(event)`: waits for the event to be handled, and
+///   returns the outcome.
+/// - `state_snapshot()`: returns a clone of the current
+///   state (only available if the state enum implements
+///   `Clone`).
+/// - `shutdown()`: stops accepting events, handles the
+///   events that were already sent, and returns the
+///   machine.
+///
+/// All of them return `machine_factory_runtime::ActorStopped`
+/// if the task is no longer running.
+///
+/// # Services
+/// Setting `service: true` on an `async` machine generates
+/// an `into_service` method, which moves the machine into a
+/// cloneable `{Identifier}Service`, for use with `tower`
+/// middleware such as timeouts and concurrency limits. The
+/// service shares the machine between requests, behind a
+/// `tokio` mutex, and implements `tower::Service` for every
+/// event, with the outcome as its response. `lock()` gives
+/// access to the machine itself.
+///
+/// If the machine has requests, `requests()` returns an
+/// `{Identifier}RequestService` sharing the same machine,
+/// which implements `tower::Service` for every request,
+/// with the reply as its response.
+///
+/// # Observing
+/// Setting `observable: true` lets other code react to
+/// state changes without polling `state()`. After every
+/// transition, a generated `{Identifier}Transition` (with
+/// the `from` and `to` state kinds) is published:
+///
+/// - `async` machines publish through `tokio` channels:
+///   `subscribe()` returns a `watch::Receiver` of the
+///   current state kind, and `subscribe_transitions()`
+///   returns a `broadcast::Receiver` of transitions
+///   (requires the `tokio` feature of
+///   `machine_factory_runtime`).
+/// - Other machines call the listeners added with
+///   `add_listener(listener)`.
+///
+/// Clones of a machine start without subscribers.
+///
+/// # Sharing
+/// Setting `shared: true` on a machine that isn't `async`
+/// generates an `into_shared` method, which wraps the
+/// machine in a cloneable `{Identifier}Shared` handle
+/// (`machine_factory_runtime::Shared`) for use from many
+/// threads:
+///
+/// - `handle_event(event)`: handles events one at a time,
+///   behind a lock, and returns the outcome.
+/// - `state_kind()`: returns the kind of the current state
+///   from an atomic, without waiting for the lock.
+/// - `subscribe()`: returns a `std::sync::mpsc::Receiver`
+///   of the kind of each state the machine moves into.
+/// - `with(f)`: calls `f` with the machine, e.g. to read
+///   its context.
+///
+/// If handling an event panics, later calls keep using the
+/// machine only if it's `transactional: true` (which puts
+/// the state and context back); otherwise, they panic too.
+///
+/// # Streams
+/// Setting `stream: true` generates a
+/// `run_stream(events, until)` method, which feeds each
+/// item of a `futures::Stream` of events into
+/// `handle_event_outcome`, and returns a stream of the
+/// outcomes.
+/// The returned stream ends when the input stream ends, or
+/// when `until` returns `true` for the current state
+/// (which is checked before each event is pulled from the
+/// input stream).
+///
+/// # Introspection
+/// The machine gets `STATES` and `EVENTS` associated
+/// constants, listing the names of its states and events in
+/// declaration order, and a `TRANSITIONS` constant of
+/// generated `{Identifier}TransitionInfo`s, one for each
+/// transition in the state blocks. A transition's target is
+/// a generated `{Identifier}TransitionTarget`:
+/// `State(name)` for default transitions (`Event -> State`),
+/// and `Dynamic` for transition blocks. Each transition
+/// also has the line where it's declared.
+///
+/// # Queries
+/// `allowed_events()` returns the kinds of the events with
+/// a transition from the current state, and
+/// `can_handle(&event)` (or `can_handle_kind(kind)`)
+/// returns a generated `{Identifier}Handling`: whether
+/// the current state has a transition for the event
+/// (`Transition`), the event would fall through to the
+/// unhandled-event block (`Fallback`), or neither
+/// (`Unhandled`). No hooks are run, so `should_exit` may
+/// still reject an event with a transition.
+///
+/// # Requests
+/// An entry of `events` can declare the type of its reply
+/// (e.g., `events: [GetColor -> Color]`), which makes the
+/// event a request. Requests implement the generated
+/// `{Identifier}Request` trait, whose `Output` is the type
+/// of their reply (a separate trait, since associated
+/// types can't have a default for the other events), and
+/// can be sent with `handle_request(request)`, which
+/// handles the request like any other event and returns
+/// the reply.
+///
+/// Transition blocks for a request have a `reply` variable
+/// of type `&mut Option<Output>` to answer it. The
+/// unhandled-event block answers every request, so its
+/// `reply` variable is an `&mut Option<{Identifier}Reply>`,
+/// an enum with a variant for the reply of each request
+/// (reply types must implement `Debug`).
+///
+/// `handle_request` returns `None` if the request wasn't
+/// answered: when `should_exit` rejects it, when its block
+/// doesn't set `reply`, or when the unhandled-event block
+/// answers with the variant of another request.
+///
+/// # Effects
+/// With `effects: Type`, transition blocks (including the
+/// unhandled-event block) return the next state along with
+/// a `Vec` of effects, e.g. `(Green {}, vec![Effect::Log])`,
+/// instead of performing side effects themselves. Default
+/// transitions return no effects.
+///
+/// The effects are run by an executor, which implements
+/// the generated `{Identifier}Executor` trait and is set
+/// with `with_executor(executor)`. The executor runs each
+/// effect with the machine's context, in order, after the
+/// transition. Until an executor is set, effects are kept
+/// and can be taken with `take_effects()`.
+///
+/// The associated function
+/// `transition(state, context, event)` runs only the
+/// transition block, and returns the next state and its
+/// effects, so transitions can be tested without running
+/// any effects or hooks.
+///
 /// # Syntax
 /// ```text
 /// event_driven_state_machine! {
@@ -47,8 +487,25 @@ mod state_trait;
 ///             [ _ { DefaultTransitionBlock } ]
 ///         RightBracket,
 ///         events: LeftBracket
-///            [ Path [, Path]* ]
+///            [ EventDeclaration [, EventDeclaration]* ]
 ///         RightBracket,
+///       [ clock: Type, ]
+///       [ effects: Type, ]
+///       [ hooks: Type, ]
+///       [ event_store: Type, ]
+///       [ persister: Type, ]
+///       [ history: Integer, ]
+///       [ tracing: Bool, ]
+///       [ metrics: Bool, ]
+///       [ recording: Bool, ]
+///       [ transactional: Bool, ]
+///       [ interceptors: Bool, ]
+///       [ actor: Bool, ]
+///       [ observable: Bool, ]
+///       [ registry: Bool, ]
+///       [ service: Bool, ]
+///       [ shared: Bool, ]
+///       [ stream: Bool, ]
 ///     }
 /// }
 ///
@@ -56,6 +513,9 @@ mod state_trait;
 /// Visibility = a valid Rust visibility modifier (e.g., `pub`, `pub(crate)`, etc.)
 /// Identifier = a valid Rust identifier (e.g., `MyStateMachine`)
 /// Path = a valid Rust path (e.g., `crate::MyContext` or `MyContext`)
+/// Type = a valid Rust type (e.g., `machine_factory_runtime::SharedClock`)
+/// Bool = `true` or `false`
+/// Integer = a valid Rust integer literal (e.g., `10`)
 /// Trait = a valid Rust trait definition (e.g., `pub trait MyTrait { ... }`)
 /// LeftBracket = [
 /// RightBracket = ]
@@ -63,6 +523,7 @@ mod state_trait;
 /// DefaultTransition = Path -> Path
 /// TransitionBlock = Path { ... } (where `...` is a block of Rust code that returns a state)
 /// DefaultTransitionBlock = 1 or more Rust statements that return a state
+/// EventDeclaration = Path [ -> Type ]
 /// ```
 ///
 /// # Example
@@ -344,6 +805,21 @@ pub fn event_driven_state_machine(
 /// Since the state machine is deterministic, transitions
 /// are enforced at compile time.
 ///
+/// # Introspection
+/// The machine gets `STATES` and `EVENTS` associated
+/// constants, listing the names of its states and
+/// transition functions, and a `TRANSITIONS` constant of
+/// generated `{Identifier}TransitionInfo`s. Only functions
+/// that take `self` by value and return the machine are
+/// transitions; other functions (e.g., queries taking
+/// `&self`) aren't listed. The target of a transition is
+/// the state in the function's return type (e.g., `Pending`
+/// for `Result<MyMachine<Pending>, Error>`), or
+/// `{Identifier}TransitionTarget::Dynamic` if it has none.
+/// Since the machine is generic over its state, the
+/// constants are accessed through any state (e.g.,
+/// `MyMachine::<Start>::TRANSITIONS`).
+///
 /// # Syntax
 /// ```text
 /// deterministic_state_machine! {
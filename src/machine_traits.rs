@@ -0,0 +1,82 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{token::Async, Ident, Path};
+
+pub struct MachineTraitsInput {
+    /// Brings the rest of the machine up to date after its
+    /// state and context were replaced, given a `state`
+    /// variable.
+    pub after_restore: TokenStream,
+    pub asyncness: Option<Async>,
+    pub context_path: Path,
+    pub event_enum_ident: Ident,
+    pub ident: Ident,
+    pub outcome_ident: Ident,
+    pub state_enum_ident: Ident,
+    pub state_kind_ident: Ident,
+    pub transactional: bool,
+}
+
+/// Implements the runtime's machine traits, so that the
+/// machine can be used by generic code such as
+/// `MachineRegistry`.
+///
+/// `HandleEvent` is only implemented for machines that
+/// aren't `async`.
+pub fn machine_traits(
+    input: MachineTraitsInput,
+) -> TokenStream {
+    let MachineTraitsInput {
+        asyncness,
+        ident,
+        state_enum_ident,
+        event_enum_ident,
+        outcome_ident,
+        state_kind_ident,
+        context_path,
+        transactional,
+        after_restore,
+    } = input;
+
+    let handle_event = asyncness.is_none().then(|| {
+        quote! {
+            impl ::machine_factory_runtime::HandleEvent for #ident {
+                fn handle_event_outcome(&mut self, event: #event_enum_ident) -> #outcome_ident {
+                    Self::handle_event_outcome(self, event)
+                }
+            }
+        }
+    });
+
+    quote! {
+        impl ::machine_factory_runtime::StateMachine for #ident {
+            type State = #state_enum_ident;
+            type Context = #context_path;
+            type Event = #event_enum_ident;
+            type Outcome = #outcome_ident;
+            type StateKind = #state_kind_ident;
+
+            const TRANSACTIONAL: bool = #transactional;
+
+            fn restore_parts(&mut self, state: #state_enum_ident, context: #context_path) {
+                #after_restore
+                self.state = ::core::option::Option::Some(state);
+                self.context = context;
+            }
+
+            fn state(&self) -> &#state_enum_ident {
+                Self::state(self)
+            }
+
+            fn context(&self) -> &#context_path {
+                Self::context(self)
+            }
+
+            fn state_kind(&self) -> #state_kind_ident {
+                Self::state(self).kind()
+            }
+        }
+
+        #handle_event
+    }
+}
@@ -0,0 +1,94 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Ident, Type};
+
+pub struct MetricsInput {
+    pub clock: Option<Type>,
+    pub event_kind_ident: Ident,
+    pub machine_ident: Ident,
+    pub outcome_ident: Ident,
+    pub state_kind_ident: Ident,
+}
+
+pub struct Metrics {
+    /// Remembers the kind of the handled event, given an
+    /// `event` variable, before it is moved.
+    pub capture: TokenStream,
+    /// The machine's field holding the metrics.
+    pub field: TokenStream,
+    /// Initializes `field`.
+    pub init: TokenStream,
+    /// Methods to add to the machine.
+    pub methods: TokenStream,
+    /// Records the transition, given an `outcome`
+    /// variable.
+    pub record: TokenStream,
+    /// Records the time spent in the state left by an undo
+    /// or redo, given an `outcome` variable.
+    pub record_restore: TokenStream,
+    /// Restarts timing the current state, after the state
+    /// was replaced.
+    pub reenter: TokenStream,
+    /// Restarts timing the current state, after the clock
+    /// was replaced.
+    pub restart: Option<TokenStream>,
+}
+
+/// Generates how the machine counts its transitions and
+/// times its states.
+///
+/// Time is read from the machine's clock, if it has one.
+pub fn metrics(input: MetricsInput) -> Metrics {
+    let MetricsInput {
+        machine_ident,
+        state_kind_ident,
+        event_kind_ident,
+        outcome_ident,
+        clock,
+    } = input;
+
+    let machine_name = machine_ident.to_string();
+    let now = clock.as_ref().map_or_else(
+        || quote!(::std::time::Instant::now()),
+        |_| {
+            quote!(::machine_factory_runtime::Clock::now(
+                &self.clock
+            ))
+        },
+    );
+
+    Metrics {
+        field: quote! {
+            metrics: ::machine_factory_runtime::TransitionMetrics<#state_kind_ident, #event_kind_ident>,
+        },
+        init: quote! {
+            metrics: ::machine_factory_runtime::TransitionMetrics::new(#machine_name, ::std::time::Instant::now()),
+        },
+        methods: quote! {
+            pub fn metrics(&self) -> &::machine_factory_runtime::TransitionMetrics<#state_kind_ident, #event_kind_ident> {
+                &self.metrics
+            }
+        },
+        capture: quote! {
+            let event_kind = event.kind();
+        },
+        record: quote! {
+            if let #outcome_ident::Transitioned { from, to } = outcome {
+                self.metrics.record(from, event_kind, to, #now);
+            }
+        },
+        record_restore: quote! {
+            if let #outcome_ident::Transitioned { from, .. } = outcome {
+                self.metrics.record_restore(from, #now);
+            }
+        },
+        reenter: quote! {
+            self.metrics.restart(#now);
+        },
+        restart: clock.map(|_| {
+            quote! {
+                self.metrics.restart(::machine_factory_runtime::Clock::now(&self.clock));
+            }
+        }),
+    }
+}
@@ -0,0 +1,103 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{token::Async, Ident, Visibility};
+
+pub struct ObserverInput {
+    pub asyncness: Option<Async>,
+    pub ident: Ident,
+    pub state_kind_ident: Ident,
+    pub visibility: Option<Visibility>,
+}
+
+pub struct Observer {
+    /// The machine's field holding the subscribers.
+    pub field: TokenStream,
+    /// Initializes `field`, given a `state` variable.
+    pub init: TokenStream,
+    /// Items to add next to the machine.
+    pub items: TokenStream,
+    /// Methods to add to the machine.
+    pub methods: TokenStream,
+    /// Notifies subscribers, given `from` and `to` state
+    /// kinds.
+    pub notify: TokenStream,
+    /// Publishes the current state kind without a
+    /// transition, given a `state` variable, after the state
+    /// was replaced.
+    pub reset: Option<TokenStream>,
+}
+
+/// Generates the transition record published to
+/// subscribers, and how the machine holds and notifies
+/// them.
+///
+/// Async machines publish through `tokio` channels, while
+/// other machines call synchronous listeners.
+pub fn observer(input: ObserverInput) -> Observer {
+    let ObserverInput {
+        visibility,
+        asyncness,
+        ident,
+        state_kind_ident,
+    } = input;
+
+    let items = quote! {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        #visibility struct #ident {
+            pub from: #state_kind_ident,
+            pub to: #state_kind_ident,
+        }
+    };
+
+    let transition = quote!(#ident { from, to });
+
+    if asyncness.is_some() {
+        Observer {
+            items,
+            field: quote! {
+                observers: ::machine_factory_runtime::StatePublisher<#state_kind_ident, #ident>,
+            },
+            init: quote! {
+                observers: ::machine_factory_runtime::StatePublisher::new(state.kind()),
+            },
+            methods: quote! {
+                pub fn subscribe(&self) -> ::tokio::sync::watch::Receiver<#state_kind_ident> {
+                    self.observers.subscribe()
+                }
+
+                pub fn subscribe_transitions(&self) -> ::tokio::sync::broadcast::Receiver<#ident> {
+                    self.observers.subscribe_transitions()
+                }
+            },
+            notify: quote! {
+                self.observers.publish(to, #transition);
+            },
+            reset: Some(quote! {
+                self.observers.reset(state.kind());
+            }),
+        }
+    } else {
+        Observer {
+            items,
+            field: quote! {
+                observers: ::machine_factory_runtime::Listeners<#ident>,
+            },
+            init: quote! {
+                observers: ::core::default::Default::default(),
+            },
+            methods: quote! {
+                pub fn add_listener<Listener>(&mut self, listener: Listener)
+                where
+                    Listener: Fn(&#ident) + Send + Sync + 'static,
+                {
+                    self.observers.add(listener);
+                }
+            },
+            notify: quote! {
+                self.observers.notify(&#transition);
+            },
+            // Listeners are only called on transitions.
+            reset: None,
+        }
+    }
+}
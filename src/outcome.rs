@@ -0,0 +1,75 @@
+use quote::quote;
+use syn::{Ident, Type, Visibility};
+
+pub struct OutcomeInput {
+    pub ident: Ident,
+    /// Whether transitions can be rolled back because the
+    /// persister failed to save them.
+    pub persisted: bool,
+    /// The reason events are rejected with, if any.
+    pub rejection_reason: Option<Type>,
+    /// Whether to derive `serde`'s traits.
+    pub serializable: bool,
+    pub state_kind_ident: Ident,
+    /// Whether interceptors can veto events.
+    pub vetoable: bool,
+    pub visibility: Option<Visibility>,
+}
+
+pub fn outcome(
+    input: OutcomeInput,
+) -> proc_macro2::TokenStream {
+    let OutcomeInput {
+        visibility,
+        ident,
+        state_kind_ident,
+        rejection_reason,
+        serializable,
+        vetoable,
+        persisted,
+    } = input;
+
+    // The reason may not be `Copy`
+    let (derive_copy, reason_field) = rejection_reason
+        .map_or_else(
+            || (Some(quote!(Copy,)), None),
+            |reason| (None, Some(quote!(reason: #reason,))),
+        );
+
+    let derive_serde = serializable.then(|| {
+        quote!(#[derive(::serde::Serialize, ::serde::Deserialize)])
+    });
+
+    let vetoed = vetoable.then(|| {
+        quote! {
+            Vetoed {
+                state: #state_kind_ident,
+            },
+        }
+    });
+
+    let not_persisted = persisted.then(|| {
+        quote! {
+            NotPersisted {
+                state: #state_kind_ident,
+            },
+        }
+    });
+
+    quote! {
+        #[derive(Debug, Clone, #derive_copy PartialEq, Eq)]
+        #derive_serde
+        #visibility enum #ident {
+            Transitioned {
+                from: #state_kind_ident,
+                to: #state_kind_ident,
+            },
+            Rejected {
+                state: #state_kind_ident,
+                #reason_field
+            },
+            #vetoed
+            #not_persisted
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Ident, Path, Type};
+
+pub struct PersisterInput {
+    pub context_path: Path,
+    /// Discards the effects of a rolled back transition.
+    pub discard_effects: Option<TokenStream>,
+    pub outcome_ident: Ident,
+    pub persister_ty: Type,
+    pub state_enum_ident: Ident,
+}
+
+pub struct Persister {
+    /// The arguments of `run_pipeline` for `param`, in
+    /// `dispatch`.
+    pub arg: TokenStream,
+    /// The machine's fields holding the persister and the
+    /// machine's id, and the last error it returned.
+    pub fields: TokenStream,
+    /// Initializes `fields`.
+    pub init: TokenStream,
+    /// Methods to add to the machine.
+    pub methods: TokenStream,
+    /// The arguments of `run_pipeline` for `param`, when
+    /// nothing is saved.
+    pub no_arg: TokenStream,
+    /// The parameters of `run_pipeline` giving it the
+    /// persister, if the machine has one, and where to put
+    /// the error of a failed save.
+    pub param: TokenStream,
+    /// Rolls the transition back if the save failed, given
+    /// `outcome`, `state` and `snapshot` variables.
+    pub roll_back: TokenStream,
+    /// Saves the machine after a transition in the
+    /// pipeline, given `state`, `context` and `from`
+    /// variables, returning `NotPersisted` if the save
+    /// fails.
+    pub save: TokenStream,
+    /// Saves the machine after undoing or redoing a
+    /// transition, given `outcome` and `state` variables.
+    pub save_restored: TokenStream,
+}
+
+/// Generates how the machine saves itself with its
+/// persister after every transition, and how machines are
+/// loaded from one.
+pub fn persister(input: PersisterInput) -> Persister {
+    let PersisterInput {
+        persister_ty,
+        state_enum_ident,
+        outcome_ident,
+        context_path,
+        discard_effects,
+    } = input;
+
+    let persister_trait = quote! {
+        ::machine_factory_runtime::Persister<#state_enum_ident, #context_path>
+    };
+    let error_ty =
+        quote!(<#persister_ty as #persister_trait>::Error);
+
+    Persister {
+        fields: quote! {
+            persister: ::core::option::Option<(#persister_ty, ::std::string::String)>,
+            persister_error: ::core::option::Option<#error_ty>,
+        },
+        init: quote! {
+            persister: ::core::option::Option::None,
+            persister_error: ::core::option::Option::None,
+        },
+        methods: quote! {
+            pub fn with_persister<Id: Into<::std::string::String>>(mut self, persister: #persister_ty, id: Id) -> Self {
+                self.persister = ::core::option::Option::Some((persister, id.into()));
+                self
+            }
+
+            pub fn id(&self) -> ::core::option::Option<&str> {
+                self.persister.as_ref().map(|(_, id)| id.as_str())
+            }
+
+            pub fn persist(&mut self) -> ::core::result::Result<(), #error_ty> {
+                match self.persister.as_mut() {
+                    ::core::option::Option::Some((persister, id)) => {
+                        let state = self.state.as_ref().expect("state is missing");
+                        <#persister_ty as #persister_trait>::save(persister, id, state, &self.context)
+                    }
+                    ::core::option::Option::None => ::core::result::Result::Ok(()),
+                }
+            }
+
+            pub fn load(persister: &#persister_ty, id: &str) -> ::core::result::Result<::core::option::Option<Self>, #error_ty> {
+                let loaded = <#persister_ty as #persister_trait>::load(persister, id)?;
+
+                ::core::result::Result::Ok(loaded.map(|(state, context)| {
+                    Self::new(state, context).with_persister(::core::clone::Clone::clone(persister), id)
+                }))
+            }
+
+            pub fn load_all(persister: &#persister_ty) -> ::core::result::Result<::std::vec::Vec<Self>, #error_ty> {
+                let loaded = <#persister_ty as #persister_trait>::load_all(persister)?;
+
+                ::core::result::Result::Ok(
+                    loaded
+                        .into_iter()
+                        .map(|(id, state, context)| {
+                            Self::new(state, context).with_persister(::core::clone::Clone::clone(persister), id)
+                        })
+                        .collect(),
+                )
+            }
+
+            pub fn take_persister_error(&mut self) -> ::core::option::Option<#error_ty> {
+                self.persister_error.take()
+            }
+        },
+        param: quote! {
+            persister: ::core::option::Option<&mut (#persister_ty, ::std::string::String)>,
+            persister_error: &mut ::core::option::Option<#error_ty>,
+        },
+        arg: quote! {
+            self.persister.as_mut(),
+            &mut self.persister_error,
+        },
+        no_arg: quote! {
+            ::core::option::Option::None,
+            &mut ::core::option::Option::None,
+        },
+        save: quote! {
+            if let ::core::option::Option::Some((persister, id)) = persister {
+                if let ::core::result::Result::Err(error) = <#persister_ty as #persister_trait>::save(persister, id, &state, context) {
+                    *persister_error = ::core::option::Option::Some(error);
+                    return (state, #outcome_ident::NotPersisted { state: from });
+                }
+            }
+        },
+        roll_back: quote! {
+            let (state, outcome) = if let #outcome_ident::NotPersisted { .. } = outcome {
+                #discard_effects
+                let (state, context) = snapshot;
+                self.context = context;
+                (state, outcome)
+            } else {
+                (state, outcome)
+            };
+        },
+        save_restored: quote! {
+            if let (#outcome_ident::Transitioned { .. }, ::core::option::Option::Some((persister, id))) = (&outcome, self.persister.as_mut()) {
+                if let ::core::result::Result::Err(error) = <#persister_ty as #persister_trait>::save(persister, id, &state, &self.context) {
+                    self.persister_error = ::core::option::Option::Some(error);
+                }
+            }
+        },
+    }
+}
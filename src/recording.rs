@@ -0,0 +1,97 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Ident, token::Async};
+
+pub struct RecordingInput {
+    pub asyncness: Option<Async>,
+    pub event_enum_ident: Ident,
+    /// Passed as the reply argument of `dispatch`, if it
+    /// takes one.
+    pub no_reply_arg: Option<TokenStream>,
+    pub outcome_ident: Ident,
+}
+
+pub struct Recording {
+    /// Clones the handled event while recording, given an
+    /// `event` variable, before it is moved.
+    pub capture: TokenStream,
+    /// The machine's field holding the trace, while
+    /// recording.
+    pub field: TokenStream,
+    /// Initializes `field`.
+    pub init: TokenStream,
+    /// Methods to add to the machine.
+    pub methods: TokenStream,
+    /// Records the event, given an `outcome` variable.
+    pub record: TokenStream,
+}
+
+/// Generates how the machine records a trace of the events
+/// it handles, and replays one.
+///
+/// Recording clones the events, so the event enum must
+/// implement `Clone`.
+pub fn recording(input: RecordingInput) -> Recording {
+    let RecordingInput {
+        asyncness,
+        event_enum_ident,
+        outcome_ident,
+        no_reply_arg,
+    } = input;
+
+    let async_postfix = asyncness.map(|_| quote!(.await));
+    let trace_ty = quote! {
+        ::machine_factory_runtime::Trace<#event_enum_ident, #outcome_ident>
+    };
+
+    Recording {
+        field: quote! {
+            trace: ::core::option::Option<#trace_ty>,
+        },
+        init: quote! {
+            trace: ::core::option::Option::None,
+        },
+        methods: quote! {
+            pub fn start_recording(&mut self) {
+                self.trace = ::core::option::Option::Some(::machine_factory_runtime::Trace::new());
+            }
+
+            pub fn trace(&self) -> ::core::option::Option<&#trace_ty> {
+                self.trace.as_ref()
+            }
+
+            pub fn stop_recording(&mut self) -> ::core::option::Option<#trace_ty> {
+                self.trace.take()
+            }
+
+            pub #asyncness fn replay(
+                mut self,
+                trace: &#trace_ty,
+            ) -> ::core::result::Result<Self, ::machine_factory_runtime::Divergence<#event_enum_ident, #outcome_ident>> {
+                for (index, entry) in trace.entries().iter().enumerate() {
+                    let event = ::core::clone::Clone::clone(&entry.event);
+                    let actual = self.dispatch(event, #no_reply_arg)#async_postfix;
+
+                    if actual != entry.outcome {
+                        return ::core::result::Result::Err(::machine_factory_runtime::Divergence {
+                            index,
+                            event: ::core::clone::Clone::clone(&entry.event),
+                            expected: ::core::clone::Clone::clone(&entry.outcome),
+                            actual,
+                        });
+                    }
+                }
+
+                ::core::result::Result::Ok(self)
+            }
+        },
+        capture: quote! {
+            let recorded_event = self.trace.is_some().then(|| ::core::clone::Clone::clone(&event));
+        },
+        record: quote! {
+            if let (::core::option::Option::Some(trace), ::core::option::Option::Some(event)) = (self.trace.as_mut(), recorded_event) {
+                trace.push(event, ::core::clone::Clone::clone(&outcome));
+            }
+        },
+    }
+}
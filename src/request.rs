@@ -0,0 +1,69 @@
+use quote::quote;
+use syn::{Ident, Path, Type, Visibility};
+
+pub struct RequestInput {
+    pub event_enum_ident: Ident,
+    pub reply_ident: Ident,
+    pub requests: Vec<Request>,
+    pub trait_ident: Ident,
+    pub visibility: Option<Visibility>,
+}
+
+pub struct Request {
+    pub event_ident: Ident,
+    pub event_path: Path,
+    pub output: Type,
+}
+
+/// Generates the trait implemented by events that are
+/// requests, and the enum of their replies.
+pub fn request(
+    input: RequestInput,
+) -> proc_macro2::TokenStream {
+    let RequestInput {
+        visibility,
+        trait_ident,
+        reply_ident,
+        event_enum_ident,
+        requests,
+    } = input;
+
+    let variants = requests.iter().map(
+        |Request { event_ident, output, .. }| {
+            quote!(#event_ident(#output))
+        },
+    );
+
+    let impls = requests.iter().map(
+        |Request { event_path, event_ident, output }| {
+            quote! {
+                impl #trait_ident for #event_path {
+                    type Output = #output;
+
+                    fn from_reply(reply: #reply_ident) -> ::core::option::Option<Self::Output> {
+                        match reply {
+                            #reply_ident::#event_ident(output) => ::core::option::Option::Some(output),
+                            #[allow(unreachable_patterns)]
+                            _ => ::core::option::Option::None,
+                        }
+                    }
+                }
+            }
+        },
+    );
+
+    quote! {
+        #[derive(Debug)]
+        #visibility enum #reply_ident {
+            #(#variants),*
+        }
+
+        #visibility trait #trait_ident: Into<#event_enum_ident> {
+            type Output;
+
+            fn from_reply(reply: #reply_ident) -> ::core::option::Option<Self::Output>;
+        }
+
+        #(#impls)*
+    }
+}
@@ -0,0 +1,167 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Ident, Visibility};
+
+pub struct ServiceInput {
+    pub event_enum_ident: Ident,
+    pub event_trait_ident: Ident,
+    pub machine_ident: Ident,
+    pub outcome_ident: Ident,
+    /// The trait implemented by requests, if the machine
+    /// has any.
+    pub request_trait_ident: Option<Ident>,
+    pub visibility: Option<Visibility>,
+}
+
+/// Generates a `{Machine}Service`, which shares an `async`
+/// machine between `tower` requests, and implements
+/// `tower::Service` for its events.
+///
+/// If the machine has requests, a `{Machine}RequestService`
+/// is generated as well, whose responses are the replies.
+pub fn service(input: ServiceInput) -> TokenStream {
+    let ServiceInput {
+        visibility,
+        machine_ident,
+        event_enum_ident,
+        event_trait_ident,
+        outcome_ident,
+        request_trait_ident,
+    } = input;
+
+    let service_ident = format_ident!("{}Service", machine_ident);
+    let service_struct =
+        shared_machine(visibility.as_ref(), &service_ident, &machine_ident);
+    let outcome_future = response_future(&quote!(#outcome_ident));
+    let poll_ready = poll_ready();
+
+    let request_service = request_trait_ident.map(|request_trait_ident| {
+        let request_service_ident =
+            format_ident!("{}RequestService", machine_ident);
+        let request_service_struct = shared_machine(
+            visibility.as_ref(),
+            &request_service_ident,
+            &machine_ident,
+        );
+        let reply_future = response_future(
+            &quote!(::core::option::Option<Request::Output>),
+        );
+
+        quote! {
+            #request_service_struct
+
+            impl #service_ident {
+                pub fn requests(&self) -> #request_service_ident {
+                    #request_service_ident {
+                        machine: ::std::sync::Arc::clone(&self.machine),
+                    }
+                }
+            }
+
+            impl<Request> ::tower::Service<Request> for #request_service_ident
+            where
+                Request: #request_trait_ident + #event_trait_ident + ::core::marker::Send + 'static,
+                Request::Output: ::core::marker::Send + 'static,
+            {
+                type Response = ::core::option::Option<Request::Output>;
+                type Error = ::core::convert::Infallible;
+                type Future = #reply_future;
+
+                #poll_ready
+
+                fn call(&mut self, request: Request) -> Self::Future {
+                    let machine = ::std::sync::Arc::clone(&self.machine);
+
+                    ::std::boxed::Box::pin(async move {
+                        ::core::result::Result::Ok(machine.lock().await.handle_request(request).await)
+                    })
+                }
+            }
+        }
+    });
+
+    quote! {
+        #service_struct
+
+        impl #machine_ident {
+            pub fn into_service(self) -> #service_ident {
+                #service_ident {
+                    machine: ::std::sync::Arc::new(::tokio::sync::Mutex::new(self)),
+                }
+            }
+        }
+
+        impl #service_ident {
+            pub async fn lock(&self) -> ::tokio::sync::MutexGuard<'_, #machine_ident> {
+                self.machine.lock().await
+            }
+        }
+
+        impl<Event> ::tower::Service<Event> for #service_ident
+        where
+            Event: Into<#event_enum_ident> + #event_trait_ident + ::core::marker::Send + 'static,
+        {
+            type Response = #outcome_ident;
+            type Error = ::core::convert::Infallible;
+            type Future = #outcome_future;
+
+            #poll_ready
+
+            fn call(&mut self, event: Event) -> Self::Future {
+                let machine = ::std::sync::Arc::clone(&self.machine);
+
+                ::std::boxed::Box::pin(async move {
+                    ::core::result::Result::Ok(machine.lock().await.handle_event_outcome(event).await)
+                })
+            }
+        }
+
+        #request_service
+    }
+}
+
+/// A cloneable struct holding a shared machine, which is
+/// `Debug` even if the machine isn't.
+fn shared_machine(
+    visibility: Option<&Visibility>,
+    ident: &Ident,
+    machine_ident: &Ident,
+) -> TokenStream {
+    let name = ident.to_string();
+
+    quote! {
+        #[derive(Clone)]
+        #visibility struct #ident {
+            machine: ::std::sync::Arc<::tokio::sync::Mutex<#machine_ident>>,
+        }
+
+        impl ::core::fmt::Debug for #ident {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                f.debug_struct(#name).finish_non_exhaustive()
+            }
+        }
+    }
+}
+
+fn response_future(response: &TokenStream) -> TokenStream {
+    quote! {
+        ::core::pin::Pin<::std::boxed::Box<
+            dyn ::core::future::Future<
+                Output = ::core::result::Result<#response, ::core::convert::Infallible>
+            > + ::core::marker::Send
+        >>
+    }
+}
+
+/// Services are always ready: events wait for the machine
+/// in the returned future.
+fn poll_ready() -> TokenStream {
+    quote! {
+        fn poll_ready(
+            &mut self,
+            _context: &mut ::core::task::Context<'_>,
+        ) -> ::core::task::Poll<::core::result::Result<(), ::core::convert::Infallible>> {
+            ::core::task::Poll::Ready(::core::result::Result::Ok(()))
+        }
+    }
+}
@@ -0,0 +1,28 @@
+use quote::{format_ident, quote};
+use syn::{Ident, Visibility};
+
+pub struct SharedInput {
+    pub machine_ident: Ident,
+    pub visibility: Option<Visibility>,
+}
+
+/// Generates a `{Machine}Shared` alias of the runtime's
+/// `Shared` handle, and an `into_shared` method.
+pub fn shared(
+    input: SharedInput,
+) -> proc_macro2::TokenStream {
+    let SharedInput { visibility, machine_ident } = input;
+
+    let shared_ident =
+        format_ident!("{}Shared", machine_ident);
+
+    quote! {
+        #visibility type #shared_ident = ::machine_factory_runtime::Shared<#machine_ident>;
+
+        impl #machine_ident {
+            pub fn into_shared(self) -> #shared_ident {
+                ::machine_factory_runtime::Shared::new(self)
+            }
+        }
+    }
+}
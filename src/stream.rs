@@ -0,0 +1,49 @@
+use quote::quote;
+use syn::{token::Async, Ident};
+
+pub struct StreamInput {
+    pub asyncness: Option<Async>,
+    pub event_enum_ident: Ident,
+    pub outcome_ident: Ident,
+    pub state_enum_ident: Ident,
+}
+
+/// Generates a `run_stream` method, which feeds a stream
+/// of events into the machine, and yields their outcomes.
+pub fn stream(input: StreamInput) -> proc_macro2::TokenStream {
+    let StreamInput {
+        asyncness,
+        state_enum_ident,
+        event_enum_ident,
+        outcome_ident,
+    } = input;
+
+    let async_postfix = asyncness.map(|_| quote!(.await));
+
+    quote! {
+        pub fn run_stream<'machine, Events, Until>(
+            &'machine mut self,
+            events: Events,
+            until: Until,
+        ) -> impl ::futures::Stream<Item = #outcome_ident> + 'machine
+        where
+            Events: ::futures::Stream + 'machine,
+            Events::Item: Into<#event_enum_ident>,
+            Until: FnMut(&#state_enum_ident) -> bool + 'machine,
+        {
+            ::futures::stream::unfold(
+                (self, ::std::boxed::Box::pin(events), until),
+                |(machine, mut events, mut until)| async move {
+                    if until(machine.state()) {
+                        return ::core::option::Option::None;
+                    }
+
+                    let event: #event_enum_ident = ::futures::StreamExt::next(&mut events).await?.into();
+                    let outcome = machine.handle_event_outcome(event)#async_postfix;
+
+                    ::core::option::Option::Some((outcome, (machine, events, until)))
+                },
+            )
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::token::Async;
+
+pub struct TransactionInput {
+    pub asyncness: Option<Async>,
+    /// Whether the machine is transactional.
+    pub enabled: bool,
+    /// Runs the pipeline, evaluating to the new state and
+    /// the outcome, given a `state` variable.
+    pub run: TokenStream,
+}
+
+/// Generates how `dispatch` runs the pipeline, binding
+/// `state` and `outcome`.
+///
+/// In transactional machines, the state and the context
+/// are cloned beforehand, and restored if any stage of the
+/// pipeline panics, before the panic is resumed.
+pub fn transaction(input: TransactionInput) -> TokenStream {
+    let TransactionInput { asyncness, enabled, run } = input;
+
+    if !enabled {
+        return quote! {
+            let (state, outcome) = #run;
+        };
+    }
+
+    let caught = if asyncness.is_some() {
+        quote! {
+            ::futures::FutureExt::catch_unwind(::std::panic::AssertUnwindSafe(async { #run })).await
+        }
+    } else {
+        quote! {
+            ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(|| #run))
+        }
+    };
+
+    quote! {
+        let snapshot = (::core::clone::Clone::clone(&state), ::core::clone::Clone::clone(&self.context));
+
+        let (state, outcome) = match #caught {
+            ::core::result::Result::Ok(handled) => handled,
+            ::core::result::Result::Err(panic) => {
+                let (state, context) = snapshot;
+                self.state = ::core::option::Option::Some(state);
+                self.context = context;
+                ::std::panic::resume_unwind(panic)
+            }
+        };
+    }
+}
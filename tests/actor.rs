@@ -0,0 +1,123 @@
+//! Machines spawned as actors with `actor: true`.
+
+#![allow(clippy::missing_trait_methods)]
+#![allow(clippy::tests_outside_test_module)]
+
+use core::num::NonZeroUsize;
+use machine_factory::event_driven_state_machine;
+use machine_factory_runtime::ActorStopped;
+
+#[derive(Debug, Default, Clone)]
+struct Lamp {
+    switches: u32,
+}
+
+#[derive(Debug, Default, Clone)]
+struct Off;
+impl LampState for Off {}
+
+#[derive(Debug, Default, Clone)]
+struct On;
+impl LampState for On {}
+
+#[derive(Debug, Clone)]
+struct Switch;
+
+impl LampEvent for Switch {}
+
+event_driven_state_machine!(
+    async LampMachine {
+        context: Lamp,
+        actor: true,
+        state_enum: #[derive(Debug, Clone)] LampMachineState,
+        state_trait: trait LampState {
+            async fn on_enter(&mut self, context: &mut Lamp) {
+                context.switches = context.switches.saturating_add(1);
+            }
+        },
+        event_enum: LampMachineEvent,
+        event_trait: trait LampEvent: Send {},
+        states: [
+            Off {
+                Switch -> On,
+            },
+            On {
+                Switch -> Off,
+            },
+        ],
+        events: [],
+    }
+);
+
+fn lamp() -> LampMachine {
+    LampMachine::new(Off, Lamp::default())
+}
+
+#[tokio::test]
+async fn ask_returns_the_outcome_of_each_event() {
+    let lamp = lamp().spawn();
+
+    let outcome = lamp.ask(Switch).await;
+    assert_eq!(
+        outcome,
+        Ok(LampMachineOutcome::Transitioned {
+            from: LampMachineStateKind::Off,
+            to: LampMachineStateKind::On,
+        })
+    );
+
+    let outcome = lamp.ask(Switch).await;
+    assert_eq!(
+        outcome,
+        Ok(LampMachineOutcome::Transitioned {
+            from: LampMachineStateKind::On,
+            to: LampMachineStateKind::Off,
+        })
+    );
+}
+
+#[tokio::test]
+async fn shutdown_handles_the_events_already_sent() {
+    let lamp =
+        lamp().spawn_with_capacity(NonZeroUsize::MIN);
+
+    for _ in 0..3_u32 {
+        lamp.send(Switch).await.unwrap();
+    }
+
+    let lamp = lamp.shutdown().await.unwrap();
+    assert_eq!(lamp.context().switches, 3);
+    assert!(matches!(
+        lamp.state(),
+        LampMachineState::On(_)
+    ));
+}
+
+#[tokio::test]
+async fn state_snapshot_clones_the_current_state() {
+    let lamp = lamp().spawn();
+    lamp.send(Switch).await.unwrap();
+
+    let state = lamp.state_snapshot().await.unwrap();
+    assert!(matches!(state, LampMachineState::On(_)));
+}
+
+#[tokio::test]
+async fn handles_fail_once_the_actor_stopped() {
+    let lamp = lamp().spawn();
+    let other = lamp.clone();
+    _ = lamp.shutdown().await.unwrap();
+
+    assert_eq!(other.send(Switch).await, Err(ActorStopped));
+    assert_eq!(other.ask(Switch).await, Err(ActorStopped));
+    assert_eq!(
+        other.state_snapshot().await.err(),
+        Some(ActorStopped)
+    );
+}
+
+#[test]
+fn actor_requires_an_async_machine() {
+    trybuild::TestCases::new()
+        .compile_fail("tests/ui/actor_not_async.rs");
+}
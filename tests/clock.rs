@@ -0,0 +1,127 @@
+//! Machines with a `clock`.
+
+#![allow(clippy::missing_trait_methods)]
+#![allow(clippy::tests_outside_test_module)]
+
+use core::time::Duration;
+use machine_factory::event_driven_state_machine;
+use machine_factory_runtime::{
+    Clock as _, ManualClock, SharedClock,
+};
+use std::time::Instant;
+
+#[derive(Debug, Default, Clone)]
+struct Stopwatch {
+    elapsed: Duration,
+    started_at: Option<Instant>,
+}
+
+#[derive(Debug, Default, Clone)]
+struct Stopped;
+impl StopwatchState for Stopped {}
+
+#[derive(Debug, Default, Clone)]
+struct Running;
+
+impl StopwatchState for Running {
+    fn on_enter(
+        &mut self,
+        context: &mut Stopwatch,
+        clock: &SharedClock,
+    ) {
+        context.started_at = Some(clock.now());
+    }
+
+    fn on_exit(
+        &mut self,
+        context: &mut Stopwatch,
+        clock: &SharedClock,
+    ) {
+        if let Some(started_at) = context.started_at.take()
+        {
+            context.elapsed =
+                context.elapsed.saturating_add(
+                    clock.now().saturating_duration_since(
+                        started_at,
+                    ),
+                );
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Toggle;
+impl StopwatchEvent for Toggle {}
+
+event_driven_state_machine!(
+    StopwatchMachine {
+        context: Stopwatch,
+        clock: SharedClock,
+        state_enum: #[derive(Debug, Clone)] StopwatchMachineState,
+        state_trait: trait StopwatchState {
+            fn on_enter(&mut self, _context: &mut Stopwatch, _clock: &SharedClock) {}
+
+            fn on_exit(&mut self, _context: &mut Stopwatch, _clock: &SharedClock) {}
+        },
+        event_enum: StopwatchMachineEvent,
+        event_trait: trait StopwatchEvent {},
+        states: [
+            Running {
+                Toggle -> Stopped,
+            },
+            Stopped {
+                Toggle -> Running,
+            },
+        ],
+        events: [],
+    }
+);
+
+fn stopwatch(clock: &ManualClock) -> StopwatchMachine {
+    StopwatchMachine::new(Stopped, Stopwatch::default())
+        .with_clock(SharedClock::new(clock.clone()))
+}
+
+#[test]
+fn hooks_measure_time_with_the_machine_clock() {
+    let clock = ManualClock::new();
+    let mut stopwatch = stopwatch(&clock);
+
+    _ = stopwatch.handle_event(Toggle);
+    clock.advance(Duration::from_secs(3));
+    _ = stopwatch.handle_event(Toggle);
+
+    assert_eq!(
+        stopwatch.context().elapsed,
+        Duration::from_secs(3)
+    );
+}
+
+#[test]
+fn time_stands_still_until_the_clock_advances() {
+    let clock = ManualClock::new();
+    let mut stopwatch = stopwatch(&clock);
+
+    _ = stopwatch.handle_event(Toggle).handle_event(Toggle);
+
+    assert_eq!(stopwatch.context().elapsed, Duration::ZERO);
+}
+
+#[test]
+fn step_takes_the_clock_as_an_argument() {
+    let clock = ManualClock::new();
+    let shared = SharedClock::new(clock.clone());
+
+    let (state, context, _) = StopwatchMachine::step(
+        Stopped.into(),
+        Stopwatch::default(),
+        Toggle,
+        &shared,
+    );
+
+    assert!(matches!(
+        state,
+        StopwatchMachineState::Running(_)
+    ));
+    assert_eq!(context.started_at, Some(clock.now()));
+}
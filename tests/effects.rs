@@ -0,0 +1,148 @@
+//! Machines with `effects`, whose transitions return the
+//! side effects to run.
+
+#![allow(clippy::missing_trait_methods)]
+#![allow(clippy::tests_outside_test_module)]
+
+use machine_factory::event_driven_state_machine;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Effect {
+    Beep,
+    Log(&'static str),
+}
+
+#[derive(Debug, Default, Clone)]
+struct Kettle {
+    entered: u32,
+    executed: Vec<Effect>,
+}
+
+#[derive(Debug, Default, Clone)]
+struct Cold;
+impl KettleState for Cold {}
+
+#[derive(Debug, Default, Clone)]
+struct Hot;
+
+impl KettleState for Hot {
+    fn on_enter(&mut self, context: &mut Kettle) {
+        context.entered = context.entered.saturating_add(1);
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Cool;
+impl KettleEventTrait for Cool {}
+
+#[derive(Debug, Clone)]
+struct Heat;
+impl KettleEventTrait for Heat {}
+
+event_driven_state_machine!(
+    KettleMachine {
+        context: Kettle,
+        effects: Effect,
+        state_enum: #[derive(Debug, Clone)] KettleMachineState,
+        state_trait: trait KettleState {},
+        event_enum: KettleEvent,
+        event_trait: trait KettleEventTrait {},
+        states: [
+            Cold {
+                Heat {
+                    (KettleMachineState::from(Hot), vec![Effect::Log("heating"), Effect::Beep])
+                },
+            },
+            Hot {
+                Cool -> Cold,
+            },
+            _ {
+                (state, Vec::new())
+            },
+        ],
+        events: [],
+    }
+);
+
+struct Recorder;
+
+impl KettleMachineExecutor for Recorder {
+    fn execute(
+        &mut self,
+        effect: Effect,
+        context: &mut Kettle,
+    ) {
+        context.executed.push(effect);
+    }
+}
+
+fn kettle() -> KettleMachine {
+    KettleMachine::new(Cold, Kettle::default())
+}
+
+#[test]
+fn effects_are_kept_until_an_executor_is_set() {
+    let mut kettle = kettle();
+    _ = kettle.handle_event(Heat);
+
+    assert_eq!(
+        kettle.take_effects(),
+        [Effect::Log("heating"), Effect::Beep]
+    );
+    assert!(kettle.take_effects().is_empty());
+}
+
+#[test]
+fn the_executor_runs_effects_in_order() {
+    let mut kettle = kettle().with_executor(Recorder);
+    _ = kettle.handle_event(Heat);
+
+    assert_eq!(
+        kettle.context().executed,
+        [Effect::Log("heating"), Effect::Beep]
+    );
+    assert!(kettle.take_effects().is_empty());
+}
+
+#[test]
+fn default_transitions_have_no_effects() {
+    let mut kettle =
+        KettleMachine::new(Hot, Kettle::default());
+    _ = kettle.handle_event(Cool);
+
+    assert!(kettle.take_effects().is_empty());
+}
+
+#[test]
+fn transition_runs_only_the_transition_block() {
+    let mut context = Kettle::default();
+
+    let (state, effects) = KettleMachine::transition(
+        Cold.into(),
+        &mut context,
+        Heat,
+    );
+
+    assert!(matches!(state, KettleMachineState::Hot(_)));
+    assert_eq!(
+        effects,
+        [Effect::Log("heating"), Effect::Beep]
+    );
+    assert_eq!(context.entered, 0);
+}
+
+#[test]
+fn step_returns_the_effects() {
+    let (state, context, _, effects) = KettleMachine::step(
+        Cold.into(),
+        Kettle::default(),
+        Heat,
+    );
+
+    assert!(matches!(state, KettleMachineState::Hot(_)));
+    assert_eq!(context.entered, 1);
+    assert_eq!(
+        effects,
+        [Effect::Log("heating"), Effect::Beep]
+    );
+}
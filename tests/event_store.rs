@@ -0,0 +1,396 @@
+//! Machines with an `event_store`.
+
+#![allow(clippy::missing_trait_methods)]
+#![allow(clippy::tests_outside_test_module)]
+
+use core::time::Duration;
+use machine_factory::event_driven_state_machine;
+use machine_factory_runtime::{
+    Clock as _, EventStore as _, FileEventStore,
+    InMemoryEventStore, ManualClock, RebuildError,
+};
+use serde::{Deserialize, Serialize};
+use std::{env, fs, path::PathBuf, process, time::Instant};
+
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+)]
+struct Account {
+    balance: u32,
+    notified: u32,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+struct Active;
+
+impl AccountState for Active {
+    fn on_enter(&mut self, context: &mut Account) {
+        context.notified =
+            context.notified.saturating_add(1);
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+struct Frozen;
+
+impl AccountState for Frozen {
+    fn should_exit(
+        &self,
+        _context: &Account,
+        event: &AccountEvent,
+    ) -> bool {
+        matches!(event, AccountEvent::Thaw(_))
+    }
+}
+
+#[derive(
+    Debug, Clone, PartialEq, Eq, Serialize, Deserialize,
+)]
+struct Deposit(u32);
+impl AccountEventTrait for Deposit {}
+
+#[derive(
+    Debug, Clone, PartialEq, Eq, Serialize, Deserialize,
+)]
+struct Freeze;
+impl AccountEventTrait for Freeze {}
+
+#[derive(
+    Debug, Clone, PartialEq, Eq, Serialize, Deserialize,
+)]
+struct Thaw;
+impl AccountEventTrait for Thaw {}
+
+type MemoryStore = InMemoryEventStore<
+    AccountEvent,
+    AccountMachineState,
+    Account,
+>;
+
+event_driven_state_machine!(
+    AccountMachine {
+        context: Account,
+        event_store: MemoryStore,
+        state_enum: #[derive(Debug, Clone)] AccountMachineState,
+        state_trait: trait AccountState {},
+        event_enum: #[derive(Clone, Debug, PartialEq, Eq)] AccountEvent,
+        event_trait: trait AccountEventTrait {},
+        states: [
+            Active {
+                Deposit {
+                    context.balance = context.balance.saturating_add(event.0);
+                    state
+                },
+                Freeze -> Frozen,
+            },
+            Frozen {
+                Thaw -> Active,
+            },
+            _ {
+                state
+            },
+        ],
+        events: [],
+    }
+);
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+struct Empty;
+impl JarState for Empty {}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+struct Full;
+impl JarState for Full {}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Fill;
+impl JarEventTrait for Fill {}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Drain;
+impl JarEventTrait for Drain {}
+
+type FileStore =
+    FileEventStore<JarEvent, JarMachineState, Account>;
+
+event_driven_state_machine!(
+    JarMachine {
+        context: Account,
+        event_store: FileStore,
+        state_enum: #[derive(Debug, Clone, Serialize, Deserialize)] JarMachineState,
+        state_trait: trait JarState {},
+        event_enum: #[derive(Clone, Debug, Serialize, Deserialize)] JarEvent,
+        event_trait: trait JarEventTrait {},
+        states: [
+            Empty {
+                Fill {
+                    context.balance = context.balance.saturating_add(1);
+                    JarMachineState::from(Full)
+                },
+            },
+            Full {
+                Drain -> Empty,
+            },
+            _ {
+                state
+            },
+        ],
+        events: [],
+    }
+);
+
+#[derive(Debug, Default, Clone)]
+struct Kettle {
+    boiled_at: Option<Instant>,
+}
+
+#[derive(Debug, Default, Clone)]
+struct Cold;
+impl KettleState for Cold {}
+
+#[derive(Debug, Default, Clone)]
+struct Boiling;
+impl KettleState for Boiling {}
+
+#[derive(Debug, Clone)]
+struct Boil;
+impl KettleEventTrait for Boil {}
+
+type KettleStore = InMemoryEventStore<
+    KettleEvent,
+    KettleMachineState,
+    Kettle,
+>;
+
+event_driven_state_machine!(
+    KettleMachine {
+        context: Kettle,
+        clock: ManualClock,
+        event_store: KettleStore,
+        state_enum: #[derive(Debug, Clone)] KettleMachineState,
+        state_trait: trait KettleState {},
+        event_enum: #[derive(Clone, Debug)] KettleEvent,
+        event_trait: trait KettleEventTrait {},
+        states: [
+            Cold {
+                Boil {
+                    context.boiled_at = Some(clock.now());
+                    KettleMachineState::from(Boiling)
+                },
+            },
+            Boiling {
+                Boil -> Boiling,
+            },
+        ],
+        events: [],
+    }
+);
+
+/// A directory removed when dropped.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        Self(env::temp_dir().join(format!(
+            "machine-factory-{name}-{}",
+            process::id()
+        )))
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        drop(fs::remove_dir_all(&self.0));
+    }
+}
+
+fn account() -> AccountMachine {
+    AccountMachine::new(Active, Account::default())
+}
+
+fn rebuild(store: MemoryStore) -> Option<AccountMachine> {
+    account().rebuild(store).ok()
+}
+
+fn rebuild_jar(store: FileStore) -> Option<JarMachine> {
+    JarMachine::new(Empty, Account::default())
+        .rebuild(store)
+        .ok()
+}
+
+#[test]
+fn accepted_events_are_appended() {
+    let mut account =
+        rebuild(MemoryStore::new()).unwrap_or_else(account);
+    _ = account
+        .handle_event(Deposit(5))
+        .handle_event(Freeze)
+        .handle_event(Deposit(1));
+
+    assert_eq!(
+        account
+            .event_store()
+            .map(InMemoryEventStore::events),
+        Some(&[Deposit(5).into(), Freeze.into()][..])
+    );
+    assert!(account.take_event_store_error().is_none());
+}
+
+#[test]
+fn machines_without_a_store_append_nothing() {
+    let mut account = account();
+    _ = account.handle_event(Deposit(5));
+
+    assert!(account.event_store().is_none());
+}
+
+#[test]
+fn rebuilding_runs_the_hooks_like_handling() {
+    let mut live =
+        rebuild(MemoryStore::new()).unwrap_or_else(account);
+    _ = live
+        .handle_event(Deposit(2))
+        .handle_event(Freeze)
+        .handle_event(Thaw)
+        .handle_event(Deposit(3));
+
+    let rebuilt =
+        live.event_store().cloned().and_then(rebuild);
+
+    assert_eq!(
+        rebuilt.as_ref().map(AccountMachine::context),
+        Some(&Account { balance: 5, notified: 3 })
+    );
+    assert_eq!(
+        rebuilt.as_ref().map(AccountMachine::context),
+        Some(live.context())
+    );
+    assert!(rebuilt.is_some_and(|account| {
+        matches!(
+            account.state(),
+            AccountMachineState::Active(_)
+        )
+    }));
+}
+
+#[test]
+fn rebuilding_uses_the_configured_clock() {
+    let clock = ManualClock::new();
+    clock.advance(Duration::from_secs(45));
+    let mut store = KettleStore::new();
+    _ = store.append(&Boil.into());
+
+    let kettle =
+        KettleMachine::new(Cold, Kettle::default())
+            .with_clock(clock.clone())
+            .rebuild(store)
+            .ok();
+
+    assert_eq!(
+        kettle
+            .and_then(|kettle| kettle.context().boiled_at),
+        Some(clock.now())
+    );
+}
+
+#[test]
+fn rebuilding_starts_from_the_latest_snapshot() {
+    let mut store = MemoryStore::new();
+    _ = store.append(&Deposit(2).into());
+    _ = store.save_snapshot(
+        &Frozen.into(),
+        &Account { balance: 10, notified: 0 },
+    );
+    _ = store.append(&Thaw.into());
+
+    let account = rebuild(store);
+
+    assert_eq!(
+        account.map(|account| account.context().balance),
+        Some(10)
+    );
+}
+
+#[test]
+fn rebuilding_reports_rejected_events() {
+    let mut store = MemoryStore::new();
+    _ = store.append(&Freeze.into());
+    _ = store.append(&Deposit(1).into());
+
+    let error = account().rebuild(store).err();
+
+    assert_eq!(
+        error,
+        Some(RebuildError::Diverged {
+            index: 1,
+            event: Deposit(1).into(),
+            outcome: AccountMachineOutcome::Rejected {
+                state: AccountMachineStateKind::Frozen,
+            },
+        })
+    );
+}
+
+#[test]
+fn file_stores_survive_reopening() {
+    let directory = TempDir::new("event-store");
+
+    let store = FileStore::open(&directory.0).ok();
+    let mut jar = store.and_then(rebuild_jar);
+    if let Some(jar) = jar.as_mut() {
+        _ = jar
+            .handle_event(Fill)
+            .handle_event(Drain)
+            .handle_event(Drain)
+            .handle_event(Fill);
+        assert!(jar.take_event_store_error().is_none());
+    }
+
+    let jar = FileStore::open(&directory.0)
+        .ok()
+        .and_then(rebuild_jar);
+
+    assert_eq!(
+        jar.as_ref().map(|jar| jar.context().balance),
+        Some(2)
+    );
+    assert!(jar.is_some_and(|jar| {
+        matches!(jar.state(), JarMachineState::Full(_))
+    }));
+}
+
+#[test]
+fn file_stores_keep_snapshots() {
+    let directory = TempDir::new("snapshot");
+
+    if let Some(mut jar) = FileStore::open(&directory.0)
+        .ok()
+        .and_then(rebuild_jar)
+    {
+        _ = jar.handle_event(Fill).handle_event(Drain);
+        assert_eq!(jar.save_snapshot().ok(), Some(()));
+        _ = jar.handle_event(Fill);
+    }
+
+    let stored = FileStore::open(&directory.0)
+        .and_then(|store| store.load())
+        .ok();
+
+    assert!(stored.is_some_and(|stored| {
+        stored.events.len() == 1
+            && stored.snapshot.is_some_and(
+                |(state, context)| {
+                    matches!(
+                        state,
+                        JarMachineState::Empty(_)
+                    ) && context.balance == 1
+                },
+            )
+    }));
+}
@@ -0,0 +1,163 @@
+//! The `allowed_events` and `can_handle` queries.
+
+#![allow(clippy::missing_trait_methods)]
+#![allow(clippy::tests_outside_test_module)]
+
+use machine_factory::event_driven_state_machine;
+
+#[derive(Debug, Default, Clone)]
+struct Player;
+
+#[derive(Debug, Default, Clone)]
+struct Paused;
+impl PlayerState for Paused {}
+
+#[derive(Debug, Default, Clone)]
+struct Playing;
+
+impl PlayerState for Playing {
+    fn should_exit(
+        &self,
+        _context: &Player,
+        _event: &PlayerEvent,
+    ) -> bool {
+        false
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Pause;
+impl PlayerEventTrait for Pause {}
+
+#[derive(Debug, Clone)]
+struct Play;
+impl PlayerEventTrait for Play {}
+
+#[derive(Debug, Clone)]
+struct Stop;
+impl PlayerEventTrait for Stop {}
+
+event_driven_state_machine!(
+    PlayerMachine {
+        context: Player,
+        state_enum: #[derive(Debug, Clone)] PlayerMachineState,
+        state_trait: trait PlayerState {},
+        event_enum: PlayerEvent,
+        event_trait: trait PlayerEventTrait {},
+        states: [
+            Paused {
+                Play -> Playing,
+                Stop -> Paused,
+            },
+            Playing {
+                Pause -> Paused,
+            },
+            _ {
+                state
+            },
+        ],
+        events: [],
+    }
+);
+
+#[derive(Debug, Default, Clone)]
+struct Off;
+impl LampState for Off {}
+
+#[derive(Debug, Default, Clone)]
+struct On;
+impl LampState for On {}
+
+#[derive(Debug, Clone)]
+struct Toggle;
+impl LampEventTrait for Toggle {}
+
+#[derive(Debug, Clone)]
+struct Flicker;
+impl LampEventTrait for Flicker {}
+
+// Without an unhandled-event block
+event_driven_state_machine!(
+    LampMachine {
+        context: Player,
+        state_enum: #[derive(Debug, Clone)] LampMachineState,
+        state_trait: trait LampState {},
+        event_enum: LampEvent,
+        event_trait: trait LampEventTrait {},
+        states: [
+            Off {
+                Toggle -> On,
+                Flicker -> Off,
+            },
+            On {
+                Toggle -> Off,
+                Flicker -> On,
+            },
+        ],
+        events: [],
+    }
+);
+
+#[test]
+fn allowed_events_follow_the_current_state() {
+    let mut player = PlayerMachine::new(Paused, Player);
+
+    assert_eq!(
+        player.allowed_events(),
+        [PlayerEventKind::Play, PlayerEventKind::Stop]
+    );
+
+    _ = player.handle_event(Play);
+
+    assert_eq!(
+        player.allowed_events(),
+        [PlayerEventKind::Pause]
+    );
+}
+
+#[test]
+fn events_without_a_transition_fall_back() {
+    let player = PlayerMachine::new(Paused, Player);
+
+    assert_eq!(
+        player.can_handle(&Play.into()),
+        PlayerMachineHandling::Transition
+    );
+    assert_eq!(
+        player.can_handle_kind(PlayerEventKind::Pause),
+        PlayerMachineHandling::Fallback
+    );
+}
+
+#[test]
+fn machines_without_a_fallback_never_fall_back() {
+    let mut lamp = LampMachine::new(Off, Player);
+
+    assert_eq!(
+        lamp.can_handle(&Toggle.into()),
+        LampMachineHandling::Transition
+    );
+
+    _ = lamp.handle_event(Toggle);
+
+    assert_eq!(
+        lamp.can_handle_kind(LampEventKind::Flicker),
+        LampMachineHandling::Transition
+    );
+}
+
+#[test]
+fn queries_ignore_should_exit() {
+    let mut player = PlayerMachine::new(Playing, Player);
+
+    assert_eq!(
+        player.can_handle_kind(PlayerEventKind::Pause),
+        PlayerMachineHandling::Transition
+    );
+    assert_eq!(
+        player.handle_event_outcome(Pause),
+        PlayerMachineOutcome::Rejected {
+            state: PlayerMachineStateKind::Playing,
+        }
+    );
+}
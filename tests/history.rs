@@ -0,0 +1,234 @@
+//! Machines with a `history`, whose transitions can be
+//! undone and redone.
+
+#![allow(clippy::missing_trait_methods)]
+#![allow(clippy::tests_outside_test_module)]
+
+extern crate alloc;
+
+use alloc::sync::Arc;
+use machine_factory::event_driven_state_machine;
+use machine_factory_runtime::UndoPolicy;
+use std::sync::{Mutex, PoisonError};
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct Editor {
+    hooks: Vec<String>,
+    words: u32,
+}
+
+#[derive(Debug, Default, Clone)]
+struct Editing;
+
+impl EditorState for Editing {
+    fn on_enter(
+        &mut self,
+        context: &mut Editor,
+        event: &EditorEvent,
+    ) {
+        context.hooks.push(format!(
+            "enter Editing on {}",
+            event.name()
+        ));
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+struct Saved;
+
+impl EditorState for Saved {
+    fn on_exit(
+        &mut self,
+        context: &mut Editor,
+        event: &EditorEvent,
+    ) {
+        context.hooks.push(format!(
+            "exit Saved on {}",
+            event.name()
+        ));
+    }
+
+    fn should_exit(
+        &self,
+        _context: &Editor,
+        event: &EditorEvent,
+    ) -> bool {
+        !matches!(event, EditorEvent::Save(_))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Type;
+impl EditorEventTrait for Type {}
+
+#[derive(Debug, Clone)]
+struct Save;
+impl EditorEventTrait for Save {}
+
+event_driven_state_machine!(
+    EditorMachine {
+        context: Editor,
+        history: 2,
+        observable: true,
+        state_enum: #[derive(Debug, Clone)] EditorMachineState,
+        state_trait: trait EditorState {
+            fn on_enter(&mut self, _context: &mut Editor, _event: &EditorEvent) {}
+            fn on_exit(&mut self, _context: &mut Editor, _event: &EditorEvent) {}
+        },
+        event_enum: #[derive(Clone)] EditorEvent,
+        event_trait: trait EditorEventTrait {},
+        states: [
+            Editing {
+                Save -> Saved,
+                Type {
+                    context.words = context.words.saturating_add(1);
+                    state
+                },
+            },
+            Saved {
+                Type {
+                    context.words = context.words.saturating_add(1);
+                    EditorMachineState::from(Editing)
+                },
+            },
+            _ {
+                state
+            },
+        ],
+        events: [],
+    }
+);
+
+fn editor() -> EditorMachine {
+    EditorMachine::new(Editing, Editor::default())
+}
+
+#[test]
+fn transitions_are_undone_and_redone() {
+    let mut editor = editor();
+    _ = editor.handle_event(Type).handle_event(Save);
+
+    assert!(editor.undo());
+    assert_eq!(
+        editor.state().kind(),
+        EditorMachineStateKind::Editing
+    );
+    assert_eq!(editor.context().words, 1);
+
+    assert!(editor.redo());
+    assert_eq!(
+        editor.state().kind(),
+        EditorMachineStateKind::Saved
+    );
+    assert_eq!(editor.context().words, 1);
+    assert!(!editor.redo());
+}
+
+#[test]
+fn only_the_last_transitions_are_kept() {
+    let mut editor = editor();
+    _ = editor
+        .handle_event(Type)
+        .handle_event(Type)
+        .handle_event(Type);
+
+    assert_eq!(editor.history().capacity(), 2);
+    assert!(editor.undo());
+    assert!(editor.undo());
+    assert!(!editor.undo());
+    assert_eq!(editor.context().words, 1);
+}
+
+#[test]
+fn rejected_events_are_not_recorded() {
+    let mut editor = editor();
+    _ = editor.handle_event(Save).handle_event(Save);
+
+    assert!(editor.undo());
+    assert!(!editor.undo());
+}
+
+#[test]
+fn new_transitions_forget_the_undone_ones() {
+    let mut editor = editor();
+    _ = editor.handle_event(Type).handle_event(Save);
+
+    assert!(editor.undo());
+    _ = editor.handle_event(Type);
+
+    assert!(!editor.history().can_redo());
+    assert!(!editor.redo());
+    assert_eq!(editor.context().words, 2);
+}
+
+#[test]
+fn undoing_restores_without_hooks_by_default() {
+    let mut editor = editor();
+    _ = editor.handle_event(Save);
+
+    assert!(editor.undo());
+    assert!(editor.context().hooks.is_empty());
+}
+
+#[test]
+fn undoing_can_run_the_hooks() {
+    let mut editor =
+        EditorMachine::new(Saved, Editor::default())
+            .with_undo_policy(UndoPolicy::RunHooks);
+    _ = editor.handle_event(Type);
+
+    // The context is restored before the hooks are called
+    assert!(editor.undo());
+    assert!(editor.context().hooks.is_empty());
+    assert!(editor.redo());
+    assert_eq!(
+        editor.context().hooks,
+        [
+            "exit Saved on Type",
+            "enter Editing on Type",
+            "exit Saved on Type",
+            "enter Editing on Type",
+        ]
+    );
+}
+
+#[test]
+fn undoing_notifies_the_listeners() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let mut editor = editor();
+    let listened = Arc::clone(&seen);
+    editor.add_listener(
+        move |transition: &EditorMachineTransition| {
+            listened
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push((transition.from, transition.to));
+        },
+    );
+
+    _ = editor.handle_event(Save);
+    assert!(editor.undo());
+
+    assert_eq!(
+        *seen
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner),
+        [
+            (
+                EditorMachineStateKind::Editing,
+                EditorMachineStateKind::Saved
+            ),
+            (
+                EditorMachineStateKind::Saved,
+                EditorMachineStateKind::Editing
+            ),
+        ]
+    );
+}
+
+#[test]
+fn history_cannot_be_combined_with_an_event_store() {
+    trybuild::TestCases::new().compile_fail(
+        "tests/ui/history_with_event_store.rs",
+    );
+}
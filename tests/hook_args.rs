@@ -0,0 +1,139 @@
+//! Hooks declaring the optional event and state arguments.
+
+#![allow(clippy::missing_trait_methods)]
+#![allow(clippy::tests_outside_test_module)]
+
+use machine_factory::event_driven_state_machine;
+
+#[derive(Debug, Default, Clone)]
+struct Elevator {
+    log: Vec<String>,
+}
+
+#[derive(Debug, Default, Clone)]
+struct Ground;
+
+impl ElevatorState for Ground {
+    fn on_enter(
+        &mut self,
+        context: &mut Elevator,
+        event: &ElevatorEvent,
+    ) {
+        context.log.push(format!(
+            "enter Ground on {}",
+            event.name()
+        ));
+    }
+
+    fn on_exit(
+        &mut self,
+        context: &mut Elevator,
+        event: &ElevatorEvent,
+    ) {
+        context.log.push(format!(
+            "exit Ground on {}",
+            event.name()
+        ));
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+struct Top;
+impl ElevatorState for Top {}
+
+#[derive(Debug, Clone)]
+struct Down;
+
+impl ElevatorEventTrait for Down {
+    fn post_transition(
+        &mut self,
+        context: &mut Elevator,
+        to: &ElevatorMachineState,
+    ) {
+        context.log.push(format!("Down to {}", to.name()));
+    }
+
+    fn pre_transition(
+        &mut self,
+        context: &mut Elevator,
+        from: &ElevatorMachineState,
+    ) {
+        context
+            .log
+            .push(format!("Down from {}", from.name()));
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Up;
+impl ElevatorEventTrait for Up {}
+
+event_driven_state_machine!(
+    ElevatorMachine {
+        context: Elevator,
+        state_enum: #[derive(Debug, Clone)] ElevatorMachineState,
+        state_trait: trait ElevatorState {
+            fn on_enter(&mut self, _context: &mut Elevator, _event: &ElevatorEvent) {}
+            fn on_exit(&mut self, _context: &mut Elevator, _event: &ElevatorEvent) {}
+        },
+        event_enum: ElevatorEvent,
+        event_trait: trait ElevatorEventTrait {
+            fn pre_transition(&mut self, _context: &mut Elevator, _from: &ElevatorMachineState) {}
+            fn post_transition(&mut self, _context: &mut Elevator, _to: &ElevatorMachineState) {}
+        },
+        states: [
+            Ground {
+                Up -> Top,
+            },
+            Top {
+                Down -> Ground,
+            },
+            _ {
+                state
+            },
+        ],
+        events: [],
+    }
+);
+
+#[test]
+fn hooks_receive_the_event_and_the_states() {
+    let mut elevator =
+        ElevatorMachine::new(Ground, Elevator::default());
+    _ = elevator.handle_event(Up).handle_event(Down);
+
+    assert_eq!(
+        elevator.context().log,
+        [
+            "exit Ground on Up",
+            "Down from Top",
+            "Down to Ground",
+            "enter Ground on Down",
+        ]
+    );
+}
+
+#[test]
+fn step_passes_the_same_arguments() {
+    let (_, context, _) = ElevatorMachine::step(
+        Top.into(),
+        Elevator::default(),
+        Down,
+    );
+
+    assert_eq!(
+        context.log,
+        [
+            "Down from Top",
+            "Down to Ground",
+            "enter Ground on Down",
+        ]
+    );
+}
+
+#[test]
+fn other_arguments_are_rejected() {
+    trybuild::TestCases::new().compile_fail(
+        "tests/ui/on_enter_extra_argument.rs",
+    );
+}
@@ -0,0 +1,152 @@
+//! Machines with `hooks`, called for every transition.
+
+#![allow(clippy::missing_trait_methods)]
+#![allow(clippy::tests_outside_test_module)]
+
+use machine_factory::event_driven_state_machine;
+
+#[derive(Debug, Default, Clone)]
+struct Audit {
+    log: Vec<String>,
+}
+
+#[derive(Debug, Default, Clone)]
+struct Draft;
+impl DocumentState for Draft {}
+
+#[derive(Debug, Default, Clone)]
+struct Review;
+
+impl DocumentState for Review {
+    fn on_enter(&mut self, context: &mut Audit) {
+        context.log.push("enter Review".to_owned());
+    }
+
+    fn should_exit(
+        &self,
+        _context: &Audit,
+        event: &DocumentEvent,
+    ) -> bool {
+        !matches!(event, DocumentEvent::Submit(_))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Reject;
+impl DocumentEventTrait for Reject {}
+
+#[derive(Debug, Clone)]
+struct Submit;
+impl DocumentEventTrait for Submit {}
+
+event_driven_state_machine!(
+    DocumentMachine {
+        context: Audit,
+        hooks: Auditor,
+        state_enum: #[derive(Debug, Clone)] DocumentMachineState,
+        state_trait: trait DocumentState {},
+        event_enum: DocumentEvent,
+        event_trait: trait DocumentEventTrait {},
+        states: [
+            Draft {
+                Submit -> Review,
+            },
+            Review {
+                Reject -> Draft,
+            },
+            _ {
+                state
+            },
+        ],
+        events: [],
+    }
+);
+
+struct Auditor;
+
+impl DocumentMachineHooks for Auditor {
+    fn on_transition(
+        from: &DocumentMachineStateKind,
+        to: &DocumentMachineStateKind,
+        event: &DocumentEvent,
+        context: &mut Audit,
+    ) {
+        context.log.push(format!(
+            "{from} -> {to} on {}",
+            event.name()
+        ));
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+struct Idle;
+impl AsyncDocumentState for Idle {}
+
+#[derive(Debug, Clone)]
+struct Touch;
+impl AsyncDocumentEventTrait for Touch {}
+
+event_driven_state_machine!(
+    async AsyncDocumentMachine {
+        context: Audit,
+        hooks: AsyncAuditor,
+        state_enum: #[derive(Debug, Clone)] AsyncDocumentMachineState,
+        state_trait: trait AsyncDocumentState {},
+        event_enum: AsyncDocumentEvent,
+        event_trait: trait AsyncDocumentEventTrait: Send {},
+        states: [
+            Idle {
+                Touch -> Idle,
+            },
+        ],
+        events: [],
+    }
+);
+
+struct AsyncAuditor;
+
+#[async_trait::async_trait]
+impl AsyncDocumentMachineHooks for AsyncAuditor {
+    async fn on_transition(
+        from: &AsyncDocumentMachineStateKind,
+        to: &AsyncDocumentMachineStateKind,
+        _event: &AsyncDocumentEvent,
+        context: &mut Audit,
+    ) {
+        context.log.push(format!("{from} -> {to}"));
+    }
+}
+
+#[test]
+fn hooks_see_both_ends_of_every_transition() {
+    let mut document =
+        DocumentMachine::new(Draft, Audit::default());
+    _ = document.handle_event(Submit).handle_event(Reject);
+
+    assert_eq!(
+        document.context().log,
+        [
+            "enter Review",
+            "Draft -> Review on Submit",
+            "Review -> Draft on Reject",
+        ]
+    );
+}
+
+#[test]
+fn hooks_are_not_called_for_rejections() {
+    let mut document =
+        DocumentMachine::new(Review, Audit::default());
+    _ = document.handle_event(Submit);
+
+    assert!(document.context().log.is_empty());
+}
+
+#[tokio::test]
+async fn async_machines_await_their_hooks() {
+    let mut document =
+        AsyncDocumentMachine::new(Idle, Audit::default());
+    _ = document.handle_event(Touch).await;
+
+    assert_eq!(document.context().log, ["Idle -> Idle"]);
+}
@@ -0,0 +1,230 @@
+//! Machines with `interceptors: true`, whose events go
+//! through layers before being handled.
+
+#![allow(clippy::missing_trait_methods)]
+#![allow(clippy::tests_outside_test_module)]
+
+extern crate alloc;
+
+use alloc::sync::Arc;
+use machine_factory::event_driven_state_machine;
+use machine_factory_runtime::{Interceptor, Verdict};
+use std::sync::{Mutex, PoisonError};
+
+#[derive(Debug, Default, Clone)]
+struct Bell {
+    rings: u32,
+}
+
+#[derive(Debug, Default, Clone)]
+struct Quiet;
+impl BellState for Quiet {}
+
+#[derive(Debug, Default, Clone)]
+struct Ringing;
+impl BellState for Ringing {}
+
+#[derive(Debug, Clone)]
+struct Ring;
+
+impl BellEventTrait for Ring {
+    fn pre_transition(&mut self, context: &mut Bell) {
+        context.rings = context.rings.saturating_add(1);
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Hush;
+impl BellEventTrait for Hush {}
+
+event_driven_state_machine!(
+    #[derive(Clone)]
+    BellMachine {
+        context: Bell,
+        interceptors: true,
+        state_enum: #[derive(Debug, Clone)] BellMachineState,
+        state_trait: trait BellState {},
+        event_enum: BellEvent,
+        event_trait: trait BellEventTrait {},
+        states: [
+            Quiet {
+                Ring -> Ringing,
+            },
+            Ringing {
+                Hush -> Quiet,
+            },
+            _ {
+                state
+            },
+        ],
+        events: [],
+    }
+);
+
+type Log = Arc<Mutex<Vec<String>>>;
+
+/// Logs its calls, and vetoes the events named `vetoed`.
+#[derive(Clone)]
+struct Layer {
+    log: Log,
+    name: &'static str,
+    vetoed: &'static str,
+}
+
+impl Layer {
+    fn push(&self, line: String) {
+        self.log
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(line);
+    }
+}
+
+impl Interceptor<BellMachine> for Layer {
+    fn after(
+        &mut self,
+        _state: &BellMachineState,
+        _context: &Bell,
+        outcome: &BellMachineOutcome,
+    ) {
+        self.push(format!(
+            "{} after {outcome:?}",
+            self.name
+        ));
+    }
+
+    fn before(
+        &mut self,
+        _state: &BellMachineState,
+        _context: &Bell,
+        event: &BellEvent,
+    ) -> Verdict {
+        self.push(format!(
+            "{} before {}",
+            self.name,
+            event.name()
+        ));
+
+        if event.name() == self.vetoed {
+            Verdict::Veto
+        } else {
+            Verdict::Proceed
+        }
+    }
+}
+
+fn layer(log: &Log, name: &'static str) -> Layer {
+    vetoing(log, name, "")
+}
+
+fn vetoing(
+    log: &Log,
+    name: &'static str,
+    vetoed: &'static str,
+) -> Layer {
+    Layer { log: Arc::clone(log), name, vetoed }
+}
+
+fn lines(log: &Log) -> Vec<String> {
+    log.lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
+#[test]
+fn the_first_layer_added_is_the_outermost() {
+    let log = Log::default();
+    let mut bell = BellMachine::new(Quiet, Bell::default())
+        .layer(layer(&log, "outer"))
+        .layer(layer(&log, "inner"));
+
+    _ = bell.handle_event(Ring);
+
+    assert_eq!(bell.interceptors().len(), 2);
+    assert_eq!(
+        lines(&log),
+        [
+            "outer before Ring",
+            "inner before Ring",
+            "inner after Transitioned { from: Quiet, to: Ringing }",
+            "outer after Transitioned { from: Quiet, to: Ringing }",
+        ]
+    );
+}
+
+#[test]
+fn vetoed_events_are_not_handled() {
+    let log = Log::default();
+    let mut bell = BellMachine::new(Quiet, Bell::default())
+        .layer(vetoing(&log, "guard", "Ring"));
+
+    assert_eq!(
+        bell.handle_event_outcome(Ring),
+        BellMachineOutcome::Vetoed {
+            state: BellMachineStateKind::Quiet,
+        }
+    );
+    assert_eq!(
+        bell.state().kind(),
+        BellMachineStateKind::Quiet
+    );
+    assert_eq!(bell.context().rings, 0);
+}
+
+#[test]
+fn vetoes_skip_the_inner_layers() {
+    let log = Log::default();
+    let mut bell = BellMachine::new(Quiet, Bell::default())
+        .layer(layer(&log, "outer"))
+        .layer(vetoing(&log, "guard", "Ring"))
+        .layer(layer(&log, "inner"));
+
+    _ = bell.handle_event(Ring);
+
+    assert_eq!(
+        lines(&log),
+        [
+            "outer before Ring",
+            "guard before Ring",
+            "outer after Vetoed { state: Quiet }",
+        ]
+    );
+}
+
+#[test]
+fn other_events_go_through_a_vetoing_layer() {
+    let log = Log::default();
+    let mut bell =
+        BellMachine::new(Ringing, Bell::default())
+            .layer(vetoing(&log, "guard", "Ring"));
+
+    assert_eq!(
+        bell.handle_event_outcome(Hush),
+        BellMachineOutcome::Transitioned {
+            from: BellMachineStateKind::Ringing,
+            to: BellMachineStateKind::Quiet,
+        }
+    );
+}
+
+#[test]
+fn clones_get_clones_of_the_layers() {
+    let log = Log::default();
+    let bell = BellMachine::new(Quiet, Bell::default())
+        .layer(layer(&log, "outer"));
+    let mut clone = bell.clone();
+
+    _ = clone.handle_event(Ring);
+
+    assert_eq!(
+        bell.state().kind(),
+        BellMachineStateKind::Quiet
+    );
+    assert_eq!(
+        lines(&log),
+        [
+            "outer before Ring",
+            "outer after Transitioned { from: Quiet, to: Ringing }",
+        ]
+    );
+}
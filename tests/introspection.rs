@@ -0,0 +1,196 @@
+//! The `STATES`, `EVENTS` and `TRANSITIONS` constants
+//! describing a machine.
+
+#![allow(clippy::missing_trait_methods)]
+#![allow(clippy::tests_outside_test_module)]
+
+use machine_factory::{
+    deterministic_state_machine, event_driven_state_machine,
+};
+
+#[derive(Debug, Default, Clone)]
+struct Door {
+    locked: bool,
+}
+
+#[derive(Debug, Default, Clone)]
+struct Closed;
+impl DoorState for Closed {}
+
+#[derive(Debug, Default, Clone)]
+struct Open;
+impl DoorState for Open {}
+
+#[derive(Debug, Clone)]
+struct Push;
+impl DoorEventTrait for Push {}
+
+#[derive(Debug, Clone)]
+struct Pull;
+impl DoorEventTrait for Pull {}
+
+event_driven_state_machine!(
+    DoorMachine {
+        context: Door,
+        state_enum: #[derive(Debug, Clone)] DoorMachineState,
+        state_trait: trait DoorState {},
+        event_enum: DoorEvent,
+        event_trait: trait DoorEventTrait {},
+        states: [
+            Closed {
+                Push {
+                    if context.locked {
+                        state.into()
+                    } else {
+                        DoorMachineState::from(Open)
+                    }
+                },
+            },
+            Open {
+                Pull -> Closed,
+            },
+            _ {
+                state
+            },
+        ],
+        events: [],
+    }
+);
+
+struct Draft;
+impl DocumentState for Draft {}
+
+struct Published;
+impl DocumentState for Published {}
+
+deterministic_state_machine!(
+    Document {
+        context: String,
+        state_trait: trait DocumentState {},
+        states: [
+            Draft {
+                fn publish(self) -> Document<Published> {
+                    Document { context: self.context, state: Published }
+                }
+
+                fn title(&self) -> &str {
+                    &self.context
+                }
+            },
+            Published {
+                fn retract(self) -> Result<Document<Draft>, String> {
+                    if self.context.is_empty() {
+                        return Err("untitled".to_owned());
+                    }
+
+                    Ok(Document { context: self.context, state: Draft })
+                }
+            },
+        ]
+    }
+);
+
+#[test]
+fn states_and_events_are_listed_in_order() {
+    assert_eq!(DoorMachine::STATES, ["Closed", "Open"]);
+    assert_eq!(DoorMachine::EVENTS, ["Push", "Pull"]);
+}
+
+#[test]
+fn transitions_describe_their_target() {
+    assert_eq!(
+        DoorMachine::TRANSITIONS
+            .iter()
+            .map(|transition| {
+                (
+                    transition.from,
+                    transition.event,
+                    transition.target,
+                )
+            })
+            .collect::<Vec<_>>(),
+        [
+            (
+                "Closed",
+                "Push",
+                DoorMachineTransitionTarget::Dynamic
+            ),
+            (
+                "Open",
+                "Pull",
+                DoorMachineTransitionTarget::State(
+                    "Closed"
+                )
+            ),
+        ]
+    );
+    assert_eq!(
+        DoorMachineTransitionTarget::Dynamic.to_string(),
+        "dynamic"
+    );
+    assert_eq!(
+        DoorMachineTransitionTarget::State("Open")
+            .to_string(),
+        "Open"
+    );
+}
+
+#[test]
+fn transitions_have_the_line_they_are_declared_on() {
+    assert!(matches!(
+        DoorMachine::TRANSITIONS,
+        [push, pull] if pull.line.checked_sub(push.line) == Some(9)
+    ));
+}
+
+#[test]
+fn the_machine_still_handles_events() {
+    let mut door =
+        DoorMachine::new(Closed, Door { locked: true });
+    _ = door.handle_event(Push).handle_event(Pull);
+
+    assert!(matches!(
+        door.state(),
+        DoorMachineState::Closed(_)
+    ));
+}
+
+#[test]
+fn deterministic_machines_list_only_transitions() {
+    assert_eq!(
+        Document::<Draft>::STATES,
+        ["Draft", "Published"]
+    );
+    assert_eq!(
+        Document::<Draft>::EVENTS,
+        ["publish", "retract"]
+    );
+    assert_eq!(
+        Document::<Draft>::TRANSITIONS
+            .iter()
+            .map(|transition| transition.target)
+            .collect::<Vec<_>>(),
+        [
+            DocumentTransitionTarget::State("Published"),
+            DocumentTransitionTarget::State("Draft"),
+        ]
+    );
+}
+
+#[test]
+fn deterministic_machines_still_transition() {
+    let draft = Document::new(Draft, "Notes".to_owned());
+    assert_eq!(draft.title(), "Notes");
+
+    let draft = draft.publish().retract();
+    assert_eq!(
+        draft.map(|draft| draft.context).ok(),
+        Some("Notes".to_owned())
+    );
+
+    let untitled = Document::new(Draft, String::new());
+    assert_eq!(
+        untitled.publish().retract().err(),
+        Some("untitled".to_owned())
+    );
+}
@@ -0,0 +1,140 @@
+//! The `Kind` enums generated for states and events.
+
+#![allow(clippy::missing_trait_methods)]
+#![allow(clippy::tests_outside_test_module)]
+
+use machine_factory::event_driven_state_machine;
+use machine_factory_runtime::Kind as _;
+use std::collections::HashSet;
+
+#[derive(Debug, Default, Clone)]
+struct Pump;
+
+#[derive(Debug, Default, Clone)]
+struct Filling;
+impl PumpState for Filling {}
+
+#[derive(Debug, Default, Clone)]
+struct Idle;
+impl PumpState for Idle {}
+
+#[derive(Debug, Clone)]
+struct Start;
+impl PumpEventTrait for Start {}
+
+#[derive(Debug, Clone)]
+struct Stop;
+impl PumpEventTrait for Stop {}
+
+event_driven_state_machine!(
+    PumpMachine {
+        context: Pump,
+        // Relies on the runtime crate, so kinds implement `Kind`
+        metrics: true,
+        state_enum: #[derive(Debug, Clone)] PumpMachineState,
+        state_trait: trait PumpState {},
+        event_enum: PumpEvent,
+        event_trait: trait PumpEventTrait {},
+        states: [
+            Filling {
+                Stop -> Idle,
+            },
+            Idle {
+                Start -> Filling,
+            },
+            _ {
+                state
+            },
+        ],
+        events: [],
+    }
+);
+
+#[test]
+fn kinds_are_named_after_their_variants() {
+    let state = PumpMachineState::from(Idle);
+
+    assert_eq!(state.kind(), PumpMachineStateKind::Idle);
+    assert_eq!(state.name(), "Idle");
+    assert_eq!(
+        PumpEvent::from(Start).kind().name(),
+        "Start"
+    );
+    assert_eq!(PumpEventKind::Stop.to_string(), "Stop");
+}
+
+#[test]
+fn kinds_parse_their_names() {
+    assert_eq!(
+        "Filling".parse::<PumpMachineStateKind>(),
+        Ok(PumpMachineStateKind::Filling)
+    );
+    assert_eq!(
+        "Stop".parse::<PumpEventKind>(),
+        Ok(PumpEventKind::Stop)
+    );
+}
+
+#[test]
+fn unknown_names_are_reported() {
+    let error = "Draining"
+        .parse::<PumpMachineStateKind>()
+        .unwrap_err();
+
+    assert_eq!(error.name(), "Draining");
+    assert_eq!(
+        error.to_string(),
+        "unknown PumpMachineStateKind `Draining`"
+    );
+    assert_eq!(
+        "stop"
+            .parse::<PumpEventKind>()
+            .map_err(|error| error.to_string()),
+        Err("unknown PumpEventKind `stop`".to_owned())
+    );
+}
+
+#[test]
+fn kinds_are_hashable() {
+    let kinds = [
+        PumpMachineStateKind::Idle,
+        PumpMachineStateKind::Filling,
+        PumpMachineStateKind::Idle,
+    ]
+    .into_iter()
+    .collect::<HashSet<_>>();
+
+    assert_eq!(kinds.len(), 2);
+}
+
+#[test]
+fn kinds_implement_the_runtime_trait() {
+    assert_eq!(
+        machine_factory_runtime::Kind::index(
+            PumpMachineStateKind::Idle
+        ),
+        1
+    );
+    assert_eq!(
+        PumpMachineStateKind::from_index(0),
+        Some(PumpMachineStateKind::Filling)
+    );
+    assert_eq!(PumpEventKind::from_index(2), None);
+}
+
+#[test]
+fn machines_report_the_kind_of_their_state() {
+    let mut pump = PumpMachine::new(Idle, Pump);
+    _ = pump.handle_event(Start);
+
+    assert_eq!(
+        pump.state().kind(),
+        PumpMachineStateKind::Filling
+    );
+}
+
+#[test]
+fn traits_cannot_declare_methods_shadowed_by_kinds() {
+    trybuild::TestCases::new()
+        .compile_fail("tests/ui/state_trait_name.rs");
+}
@@ -0,0 +1,160 @@
+//! Machines with `metrics: true`.
+
+#![allow(clippy::missing_trait_methods)]
+#![allow(clippy::tests_outside_test_module)]
+
+use core::time::Duration;
+use machine_factory::event_driven_state_machine;
+use machine_factory_runtime::ManualClock;
+
+#[derive(Debug, Default, Clone)]
+struct Light;
+
+#[derive(Debug, Default, Clone)]
+struct Green;
+impl LightState for Green {}
+
+#[derive(Debug, Default, Clone)]
+struct Red;
+
+impl LightState for Red {
+    fn should_exit(
+        &self,
+        _context: &Light,
+        event: &LightEvent,
+    ) -> bool {
+        matches!(event, LightEvent::Next(_))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Hold;
+impl LightEventTrait for Hold {}
+
+#[derive(Debug, Clone)]
+struct Next;
+impl LightEventTrait for Next {}
+
+event_driven_state_machine!(
+    LightMachine {
+        context: Light,
+        clock: ManualClock,
+        metrics: true,
+        state_enum: #[derive(Debug, Clone)] LightMachineState,
+        state_trait: trait LightState {},
+        event_enum: LightEvent,
+        event_trait: trait LightEventTrait {},
+        states: [
+            Green {
+                Hold -> Green,
+                Next -> Red,
+            },
+            Red {
+                Hold -> Red,
+                Next -> Green,
+            },
+        ],
+        events: [],
+    }
+);
+
+fn light(clock: &ManualClock) -> LightMachine {
+    LightMachine::new(Red, Light).with_clock(clock.clone())
+}
+
+#[test]
+fn transitions_are_counted() {
+    let clock = ManualClock::new();
+    let mut light = light(&clock);
+    _ = light
+        .handle_event(Next)
+        .handle_event(Hold)
+        .handle_event(Next)
+        .handle_event(Next);
+
+    let metrics = light.metrics();
+
+    assert_eq!(metrics.machine(), "LightMachine");
+    assert_eq!(
+        metrics.count(
+            LightMachineStateKind::Red,
+            LightEventKind::Next,
+            LightMachineStateKind::Green,
+        ),
+        2
+    );
+    assert_eq!(
+        metrics.count(
+            LightMachineStateKind::Green,
+            LightEventKind::Hold,
+            LightMachineStateKind::Green,
+        ),
+        1
+    );
+    assert_eq!(metrics.transitions().count(), 3);
+}
+
+#[test]
+fn rejections_are_not_counted() {
+    let clock = ManualClock::new();
+    let mut light = light(&clock);
+    _ = light.handle_event(Hold);
+
+    assert_eq!(light.metrics().transitions().count(), 0);
+    assert!(
+        light
+            .metrics()
+            .dwell_time(LightMachineStateKind::Red)
+            .is_none()
+    );
+}
+
+#[test]
+fn dwell_times_are_measured_with_the_clock() {
+    let clock = ManualClock::new();
+    let mut light = light(&clock);
+
+    clock.advance(Duration::from_secs(3));
+    _ = light.handle_event(Next);
+    clock.advance(Duration::from_secs(5));
+    _ = light.handle_event(Next);
+    clock.advance(Duration::from_secs(1));
+    _ = light.handle_event(Next);
+
+    let red = light
+        .metrics()
+        .dwell_time(LightMachineStateKind::Red)
+        .cloned()
+        .unwrap_or_default();
+
+    assert_eq!(red.count(), 2);
+    assert_eq!(red.total(), Duration::from_secs(4));
+    assert_eq!(red.min(), Some(Duration::from_secs(1)));
+    assert_eq!(red.max(), Some(Duration::from_secs(3)));
+    assert_eq!(red.mean(), Some(Duration::from_secs(2)));
+    assert_eq!(
+        red.buckets()
+            .filter(|&(_, count)| count > 0)
+            .collect::<Vec<_>>(),
+        [
+            (Some(Duration::from_secs(1)), 1),
+            (Some(Duration::from_secs(10)), 1),
+        ]
+    );
+}
+
+#[test]
+fn the_current_stay_is_not_recorded() {
+    let clock = ManualClock::new();
+    let mut light = light(&clock);
+
+    _ = light.handle_event(Next);
+    clock.advance(Duration::from_mins(1));
+
+    assert!(
+        light
+            .metrics()
+            .dwell_time(LightMachineStateKind::Green)
+            .is_none()
+    );
+}
@@ -0,0 +1,174 @@
+//! Machines with `observable: true`.
+
+#![allow(clippy::missing_trait_methods)]
+#![allow(clippy::tests_outside_test_module)]
+
+extern crate alloc;
+
+use alloc::sync::Arc;
+use machine_factory::event_driven_state_machine;
+use std::sync::Mutex;
+
+#[derive(Debug, Default, Clone)]
+struct Door {
+    locked: bool,
+}
+
+#[derive(Debug, Default, Clone)]
+struct Closed;
+
+impl DoorState for Closed {
+    fn should_exit(
+        &self,
+        context: &Door,
+        _event: &DoorEvent,
+    ) -> bool {
+        !context.locked
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+struct Open;
+impl DoorState for Open {}
+
+#[derive(Debug, Clone)]
+struct Push;
+impl DoorEventTrait for Push {}
+
+event_driven_state_machine!(
+    DoorMachine {
+        context: Door,
+        observable: true,
+        state_enum: #[derive(Debug, Clone)] DoorMachineState,
+        state_trait: trait DoorState {},
+        event_enum: DoorEvent,
+        event_trait: trait DoorEventTrait {},
+        states: [
+            Closed {
+                Push -> Open,
+            },
+            Open {
+                Push -> Closed,
+            },
+        ],
+        events: [],
+    }
+);
+
+#[derive(Debug, Default, Clone)]
+struct Gate;
+
+#[derive(Debug, Default, Clone)]
+struct Down;
+impl GateState for Down {}
+
+#[derive(Debug, Default, Clone)]
+struct Up;
+impl GateState for Up {}
+
+#[derive(Debug, Clone)]
+struct Raise;
+impl GateEventTrait for Raise {}
+
+event_driven_state_machine!(
+    async GateMachine {
+        context: Gate,
+        observable: true,
+        state_enum: #[derive(Debug, Clone)] GateMachineState,
+        state_trait: trait GateState {},
+        event_enum: GateEvent,
+        event_trait: trait GateEventTrait: Send {},
+        states: [
+            Down {
+                Raise -> Up,
+            },
+            Up {
+                Raise -> Down,
+            },
+        ],
+        events: [],
+    }
+);
+
+#[test]
+fn listeners_are_called_on_every_transition() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let mut door =
+        DoorMachine::new(Closed, Door::default());
+    let listened = Arc::clone(&seen);
+    door.add_listener(
+        move |transition: &DoorMachineTransition| {
+            listened.lock().unwrap().push(*transition);
+        },
+    );
+
+    _ = door.handle_event(Push).handle_event(Push);
+
+    assert_eq!(
+        *seen.lock().unwrap(),
+        [
+            DoorMachineTransition {
+                from: DoorMachineStateKind::Closed,
+                to: DoorMachineStateKind::Open,
+            },
+            DoorMachineTransition {
+                from: DoorMachineStateKind::Open,
+                to: DoorMachineStateKind::Closed,
+            },
+        ]
+    );
+}
+
+#[test]
+fn listeners_are_not_called_on_rejected_events() {
+    let calls = Arc::new(Mutex::new(0_u32));
+    let mut door =
+        DoorMachine::new(Closed, Door { locked: true });
+    let counted = Arc::clone(&calls);
+    door.add_listener(move |_| {
+        *counted.lock().unwrap() += 1;
+    });
+
+    _ = door.handle_event(Push);
+
+    assert_eq!(*calls.lock().unwrap(), 0);
+}
+
+#[tokio::test]
+async fn subscribers_see_the_current_state_kind() {
+    let mut gate = GateMachine::new(Down, Gate);
+    let mut state = gate.subscribe();
+    assert_eq!(*state.borrow(), GateMachineStateKind::Down);
+
+    _ = gate.handle_event(Raise).await;
+
+    state.changed().await.unwrap();
+    assert_eq!(*state.borrow(), GateMachineStateKind::Up);
+}
+
+#[tokio::test]
+async fn subscribers_receive_every_transition() {
+    let mut gate = GateMachine::new(Down, Gate);
+    let mut transitions = gate.subscribe_transitions();
+
+    _ = gate
+        .handle_event(Raise)
+        .await
+        .handle_event(Raise)
+        .await;
+
+    assert_eq!(
+        transitions.recv().await.unwrap(),
+        GateMachineTransition {
+            from: GateMachineStateKind::Down,
+            to: GateMachineStateKind::Up,
+        }
+    );
+    assert_eq!(
+        transitions.recv().await.unwrap(),
+        GateMachineTransition {
+            from: GateMachineStateKind::Up,
+            to: GateMachineStateKind::Down,
+        }
+    );
+}
@@ -0,0 +1,142 @@
+//! The `on_rejected` hook, called instead of the others
+//! when `should_exit` rejects an event.
+
+#![allow(clippy::missing_trait_methods)]
+#![allow(clippy::tests_outside_test_module)]
+
+use machine_factory::event_driven_state_machine;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct Vault {
+    attempts: u32,
+    exits: u32,
+    rejected: Vec<&'static str>,
+}
+
+#[derive(Debug, Default, Clone)]
+struct Sealed;
+
+impl VaultState for Sealed {
+    fn on_exit(&mut self, context: &mut Vault) {
+        context.exits = context.exits.saturating_add(1);
+    }
+
+    fn on_rejected(
+        &mut self,
+        context: &mut Vault,
+        event: &VaultEvent,
+    ) {
+        context.rejected.push(event.name());
+    }
+
+    fn should_exit(
+        &self,
+        context: &Vault,
+        _event: &VaultEvent,
+    ) -> bool {
+        context.attempts >= 2
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+struct Unsealed;
+impl VaultState for Unsealed {}
+
+#[derive(Debug, Clone)]
+struct Unseal;
+
+impl VaultEventTrait for Unseal {
+    fn pre_transition(&mut self, context: &mut Vault) {
+        context.attempts =
+            context.attempts.saturating_add(1);
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Knock;
+impl VaultEventTrait for Knock {}
+
+event_driven_state_machine!(
+    VaultMachine {
+        context: Vault,
+        state_enum: #[derive(Debug, Clone)] VaultMachineState,
+        state_trait: trait VaultState {},
+        event_enum: VaultEvent,
+        event_trait: trait VaultEventTrait {},
+        states: [
+            Sealed {
+                Unseal -> Unsealed,
+            },
+            Unsealed {
+                Knock -> Unsealed,
+            },
+            _ {
+                context.attempts = context.attempts.saturating_add(1);
+                state
+            },
+        ],
+        events: [],
+    }
+);
+
+#[test]
+fn rejections_only_call_on_rejected() {
+    let mut vault =
+        VaultMachine::new(Sealed, Vault::default());
+    _ = vault.handle_event(Unseal).handle_event(Knock);
+
+    assert_eq!(
+        vault.context(),
+        &Vault {
+            attempts: 0,
+            exits: 0,
+            rejected: vec!["Unseal", "Knock"],
+        }
+    );
+}
+
+#[test]
+fn transitions_do_not_call_on_rejected() {
+    let mut vault = VaultMachine::new(
+        Sealed,
+        Vault { attempts: 2, ..Vault::default() },
+    );
+    _ = vault.handle_event(Unseal);
+
+    assert_eq!(
+        vault.context(),
+        &Vault {
+            attempts: 3,
+            exits: 1,
+            rejected: Vec::new(),
+        }
+    );
+    assert!(matches!(
+        vault.state(),
+        VaultMachineState::Unsealed(_)
+    ));
+}
+
+#[test]
+fn step_calls_on_rejected() {
+    let (_, context, outcome) = VaultMachine::step(
+        Sealed.into(),
+        Vault::default(),
+        Knock,
+    );
+
+    assert_eq!(context.rejected, ["Knock"]);
+    assert_eq!(
+        outcome,
+        VaultMachineOutcome::Rejected {
+            state: VaultMachineStateKind::Sealed,
+        }
+    );
+}
+
+#[test]
+fn guards_cannot_mutate_the_context() {
+    trybuild::TestCases::new().compile_fail(
+        "tests/ui/should_exit_mut_context.rs",
+    );
+}
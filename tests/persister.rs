@@ -0,0 +1,296 @@
+//! Machines with a `persister`.
+
+#![allow(clippy::missing_trait_methods)]
+#![allow(clippy::tests_outside_test_module)]
+
+extern crate alloc;
+
+use alloc::sync::Arc;
+use core::{
+    cell::Cell,
+    sync::atomic::{AtomicBool, Ordering},
+};
+use machine_factory::event_driven_state_machine;
+use machine_factory_runtime::{
+    Persister, SqlitePersister, SqlitePersisterError,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Effect {
+    Ship,
+}
+
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+)]
+struct Parcel {
+    scans: u32,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+struct Packed;
+impl ParcelState for Packed {}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+struct Shipped;
+impl ParcelState for Shipped {}
+
+#[derive(Debug, Clone)]
+struct Scan;
+
+impl ParcelEventTrait for Scan {
+    fn pre_transition(&mut self, context: &mut Parcel) {
+        context.scans = context.scans.saturating_add(1);
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Return;
+impl ParcelEventTrait for Return {}
+
+type Sqlite = SqlitePersister<ParcelMachineState, Parcel>;
+
+/// A persister whose saves fail while `failing` is set.
+#[derive(Clone)]
+struct Flaky {
+    failing: Arc<AtomicBool>,
+    sqlite: Sqlite,
+}
+
+impl Persister<ParcelMachineState, Parcel> for Flaky {
+    type Error = Option<SqlitePersisterError>;
+
+    fn load(
+        &self,
+        id: &str,
+    ) -> Result<
+        Option<(ParcelMachineState, Parcel)>,
+        Self::Error,
+    > {
+        self.sqlite.load(id).map_err(Some)
+    }
+
+    fn load_all(
+        &self,
+    ) -> Result<
+        Vec<(String, ParcelMachineState, Parcel)>,
+        Self::Error,
+    > {
+        self.sqlite.load_all().map_err(Some)
+    }
+
+    fn remove(
+        &mut self,
+        id: &str,
+    ) -> Result<(), Self::Error> {
+        self.sqlite.remove(id).map_err(Some)
+    }
+
+    fn save(
+        &mut self,
+        id: &str,
+        state: &ParcelMachineState,
+        context: &Parcel,
+    ) -> Result<(), Self::Error> {
+        if self.failing.load(Ordering::SeqCst) {
+            return Err(None);
+        }
+
+        self.sqlite.save(id, state, context).map_err(Some)
+    }
+}
+
+event_driven_state_machine!(
+    ParcelMachine {
+        context: Parcel,
+        effects: Effect,
+        hooks: Notifier,
+        persister: Flaky,
+        transactional: true,
+        state_enum: #[derive(Debug, Clone, Serialize, Deserialize)] ParcelMachineState,
+        state_trait: trait ParcelState {},
+        event_enum: ParcelEvent,
+        event_trait: trait ParcelEventTrait {},
+        states: [
+            Packed {
+                Scan {
+                    (ParcelMachineState::from(Shipped), vec![Effect::Ship])
+                },
+            },
+            Shipped {
+                Return -> Packed,
+            },
+            _ {
+                (state, Vec::new())
+            },
+        ],
+        events: [],
+    }
+);
+
+thread_local! {
+    /// The notices sent by `Notifier` on this thread.
+    static NOTICES: Cell<u32> = const { Cell::new(0) };
+}
+
+/// Sends a notice outside of the machine for every
+/// transition.
+struct Notifier;
+
+impl ParcelMachineHooks for Notifier {
+    fn on_transition(
+        _from: &ParcelMachineStateKind,
+        _to: &ParcelMachineStateKind,
+        _event: &ParcelEvent,
+        _context: &mut Parcel,
+    ) {
+        NOTICES.set(NOTICES.get().saturating_add(1));
+    }
+}
+
+fn flaky() -> Flaky {
+    Flaky {
+        failing: Arc::default(),
+        sqlite: Sqlite::open(":memory:", "parcel")
+            .expect("the database should open"),
+    }
+}
+
+fn parcel(persister: &Flaky, id: &str) -> ParcelMachine {
+    ParcelMachine::new(Packed, Parcel::default())
+        .with_persister(persister.clone(), id)
+}
+
+fn loaded(
+    persister: &Flaky,
+    id: &str,
+) -> Option<(ParcelMachineStateKind, Parcel)> {
+    ParcelMachine::load(persister, id).ok().flatten().map(
+        |parcel| {
+            (
+                parcel.state().kind(),
+                parcel.context().clone(),
+            )
+        },
+    )
+}
+
+#[test]
+fn transitions_are_saved() {
+    let persister = flaky();
+    let mut parcel = parcel(&persister, "a");
+    _ = parcel.handle_event(Scan);
+
+    assert_eq!(parcel.id(), Some("a"));
+    assert_eq!(
+        loaded(&persister, "a"),
+        Some((
+            ParcelMachineStateKind::Shipped,
+            Parcel { scans: 1 }
+        ))
+    );
+}
+
+#[test]
+fn machines_are_saved_on_demand() {
+    let persister = flaky();
+
+    assert_eq!(loaded(&persister, "a"), None);
+    assert_eq!(
+        parcel(&persister, "a").persist().ok(),
+        Some(())
+    );
+    assert_eq!(
+        loaded(&persister, "a"),
+        Some((
+            ParcelMachineStateKind::Packed,
+            Parcel::default()
+        ))
+    );
+}
+
+#[test]
+fn every_machine_is_loaded() {
+    let persister = flaky();
+    _ = parcel(&persister, "a").handle_event(Scan);
+    assert_eq!(
+        parcel(&persister, "b").persist().ok(),
+        Some(())
+    );
+
+    let mut ids = ParcelMachine::load_all(&persister)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|parcel| parcel.id().map(str::to_owned))
+        .collect::<Vec<_>>();
+    ids.sort();
+
+    assert_eq!(ids, ["a", "b"]);
+}
+
+#[test]
+fn failed_saves_roll_back_the_transition() {
+    let persister = flaky();
+    let mut parcel = parcel(&persister, "a");
+    persister.failing.store(true, Ordering::SeqCst);
+
+    assert_eq!(
+        parcel.handle_event_outcome(Scan),
+        ParcelMachineOutcome::NotPersisted {
+            state: ParcelMachineStateKind::Packed,
+        }
+    );
+    assert_eq!(
+        parcel.state().kind(),
+        ParcelMachineStateKind::Packed
+    );
+    assert_eq!(parcel.context(), &Parcel::default());
+    assert!(parcel.take_effects().is_empty());
+    assert!(parcel.take_persister_error().is_some());
+    assert!(parcel.take_persister_error().is_none());
+}
+
+#[test]
+fn hooks_only_run_for_saved_transitions() {
+    let persister = flaky();
+    let mut parcel = parcel(&persister, "a");
+    persister.failing.store(true, Ordering::SeqCst);
+    _ = parcel.handle_event(Scan);
+    assert_eq!(NOTICES.get(), 0);
+
+    persister.failing.store(false, Ordering::SeqCst);
+    _ = parcel.handle_event(Scan);
+    assert_eq!(NOTICES.get(), 1);
+}
+
+#[test]
+fn saves_succeed_again_after_a_failure() {
+    let persister = flaky();
+    let mut parcel = parcel(&persister, "a");
+    persister.failing.store(true, Ordering::SeqCst);
+    _ = parcel.handle_event(Scan);
+    persister.failing.store(false, Ordering::SeqCst);
+
+    assert_eq!(
+        parcel.handle_event_outcome(Scan),
+        ParcelMachineOutcome::Transitioned {
+            from: ParcelMachineStateKind::Packed,
+            to: ParcelMachineStateKind::Shipped,
+        }
+    );
+    assert_eq!(parcel.take_effects(), [Effect::Ship]);
+}
+
+#[test]
+fn persisters_require_a_transactional_machine() {
+    trybuild::TestCases::new().compile_fail(
+        "tests/ui/persister_not_transactional.rs",
+    );
+}
@@ -0,0 +1,182 @@
+//! Machines with `recording: true`.
+
+#![allow(clippy::missing_trait_methods)]
+#![allow(clippy::tests_outside_test_module)]
+
+use machine_factory::event_driven_state_machine;
+use machine_factory_runtime::{Divergence, Trace};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Clone)]
+struct Gate {
+    jammed: bool,
+}
+
+#[derive(Debug, Default, Clone)]
+struct Closed;
+
+impl GateState for Closed {
+    fn should_exit(
+        &self,
+        context: &Gate,
+        _event: &GateEvent,
+    ) -> bool {
+        !context.jammed
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+struct Opened;
+impl GateState for Opened {}
+
+#[derive(
+    Debug, Clone, PartialEq, Eq, Serialize, Deserialize,
+)]
+struct Open;
+impl GateEventTrait for Open {}
+
+#[derive(
+    Debug, Clone, PartialEq, Eq, Serialize, Deserialize,
+)]
+struct Shut;
+impl GateEventTrait for Shut {}
+
+event_driven_state_machine!(
+    GateMachine {
+        context: Gate,
+        recording: true,
+        state_enum: #[derive(Debug, Clone)] GateMachineState,
+        state_trait: trait GateState {},
+        event_enum: #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)] GateEvent,
+        event_trait: trait GateEventTrait {},
+        states: [
+            Closed {
+                Open -> Opened,
+            },
+            Opened {
+                Shut -> Closed,
+            },
+            _ {
+                state
+            },
+        ],
+        events: [],
+    }
+);
+
+type GateTrace = Trace<GateEvent, GateMachineOutcome>;
+
+fn recorded() -> GateTrace {
+    let mut gate =
+        GateMachine::new(Closed, Gate::default());
+    gate.start_recording();
+    _ = gate
+        .handle_event(Open)
+        .handle_event(Open)
+        .handle_event(Shut);
+
+    gate.stop_recording().unwrap_or_default()
+}
+
+#[test]
+fn events_are_recorded_with_their_outcome() {
+    let trace = recorded();
+
+    assert_eq!(
+        trace
+            .entries()
+            .iter()
+            .map(|entry| (
+                entry.event.clone(),
+                entry.outcome
+            ))
+            .collect::<Vec<_>>(),
+        [
+            (
+                GateEvent::from(Open),
+                GateMachineOutcome::Transitioned {
+                    from: GateMachineStateKind::Closed,
+                    to: GateMachineStateKind::Opened,
+                }
+            ),
+            (
+                Open.into(),
+                GateMachineOutcome::Transitioned {
+                    from: GateMachineStateKind::Opened,
+                    to: GateMachineStateKind::Opened,
+                }
+            ),
+            (
+                Shut.into(),
+                GateMachineOutcome::Transitioned {
+                    from: GateMachineStateKind::Opened,
+                    to: GateMachineStateKind::Closed,
+                }
+            ),
+        ]
+    );
+}
+
+#[test]
+fn nothing_is_recorded_until_started() {
+    let mut gate =
+        GateMachine::new(Closed, Gate::default());
+    _ = gate.handle_event(Open);
+
+    assert!(gate.trace().is_none());
+
+    gate.start_recording();
+
+    assert!(gate.trace().is_some_and(Trace::is_empty));
+    assert!(
+        gate.stop_recording()
+            .is_some_and(|trace| trace.is_empty())
+    );
+    assert!(gate.stop_recording().is_none());
+}
+
+#[test]
+fn replaying_reaches_the_same_state() {
+    let gate = GateMachine::new(Closed, Gate::default())
+        .replay(&recorded())
+        .ok();
+
+    assert!(gate.is_some_and(|gate| {
+        matches!(gate.state(), GateMachineState::Closed(_))
+    }));
+}
+
+#[test]
+fn replaying_reports_the_first_divergence() {
+    let divergence =
+        GateMachine::new(Closed, Gate { jammed: true })
+            .replay(&recorded())
+            .err();
+
+    assert_eq!(
+        divergence,
+        Some(Divergence {
+            index: 0,
+            event: Open.into(),
+            expected: GateMachineOutcome::Transitioned {
+                from: GateMachineStateKind::Closed,
+                to: GateMachineStateKind::Opened,
+            },
+            actual: GateMachineOutcome::Rejected {
+                state: GateMachineStateKind::Closed,
+            },
+        })
+    );
+}
+
+#[test]
+fn traces_are_serializable() {
+    let trace = recorded();
+
+    let json =
+        serde_json::to_string(&trace).unwrap_or_default();
+    let restored =
+        serde_json::from_str::<GateTrace>(&json).ok();
+
+    assert_eq!(restored, Some(trace));
+}
@@ -0,0 +1,221 @@
+//! Machines with `registry: true`, kept in a
+//! `MachineRegistry`.
+
+#![allow(clippy::missing_trait_methods)]
+#![allow(clippy::tests_outside_test_module)]
+
+extern crate alloc;
+
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use machine_factory::event_driven_state_machine;
+use machine_factory_runtime::{
+    MachineRegistry, Persister as _, RestoreError,
+    SqlitePersister,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+)]
+struct Ticket {
+    comments: u32,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+struct Open;
+impl TicketState for Open {}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+struct Closed;
+impl TicketState for Closed {}
+
+#[derive(Debug, Clone)]
+struct Comment;
+impl TicketEventTrait for Comment {}
+
+#[derive(Debug, Clone)]
+struct Close;
+impl TicketEventTrait for Close {}
+
+event_driven_state_machine!(
+    TicketMachine {
+        context: Ticket,
+        registry: true,
+        state_enum: #[derive(Debug, Clone, Serialize, Deserialize)] TicketMachineState,
+        state_trait: trait TicketState {},
+        event_enum: TicketEvent,
+        event_trait: trait TicketEventTrait {},
+        states: [
+            Open {
+                Close -> Closed,
+                Comment {
+                    context.comments = context.comments.saturating_add(1);
+                    state
+                },
+            },
+            Closed {
+                Comment -> Closed,
+            },
+            _ {
+                state
+            },
+        ],
+        events: [],
+    }
+);
+
+type Tickets = MachineRegistry<u32, TicketMachine>;
+
+type Sqlite = SqlitePersister<TicketMachineState, Ticket>;
+
+fn tickets() -> Tickets {
+    MachineRegistry::new(|_| {
+        TicketMachine::new(Open, Ticket::default())
+    })
+}
+
+fn evicting(tickets: Tickets) -> Tickets {
+    tickets.evicting(|ticket: &TicketMachine| {
+        ticket.state().kind()
+            == TicketMachineStateKind::Closed
+    })
+}
+
+/// A registry counting the machines its factory creates.
+fn counting(created: &Arc<AtomicUsize>) -> Tickets {
+    let counted = Arc::clone(created);
+
+    MachineRegistry::new(move |_| {
+        _ = counted.fetch_add(1, Ordering::SeqCst);
+        TicketMachine::new(Open, Ticket::default())
+    })
+}
+
+fn sqlite() -> Sqlite {
+    Sqlite::open(":memory:", "ticket")
+        .expect("the database should open")
+}
+
+#[test]
+fn events_are_routed_by_id() {
+    let mut tickets = tickets();
+    _ = tickets.handle_event(1, Comment);
+    _ = tickets.handle_event(1, Comment);
+    _ = tickets.handle_event(2, Comment);
+
+    assert_eq!(tickets.len(), 2);
+    assert_eq!(
+        tickets
+            .get(&1)
+            .map(|ticket| ticket.context().comments),
+        Some(2)
+    );
+    assert_eq!(
+        tickets
+            .get(&2)
+            .map(|ticket| ticket.context().comments),
+        Some(1)
+    );
+}
+
+#[test]
+fn machines_are_created_once_by_the_factory() {
+    let created = Arc::new(AtomicUsize::new(0));
+    let mut tickets = counting(&created);
+
+    assert_eq!(
+        tickets.handle_event(7, Comment),
+        TicketMachineOutcome::Transitioned {
+            from: TicketMachineStateKind::Open,
+            to: TicketMachineStateKind::Open,
+        }
+    );
+    _ = tickets.handle_event(7, Close);
+
+    assert_eq!(created.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn finished_machines_are_evicted() {
+    let mut tickets = evicting(tickets());
+    _ = tickets.handle_event(1, Comment);
+    _ = tickets.handle_event(2, Close);
+
+    assert!(tickets.get(&2).is_none());
+    assert_eq!(tickets.len(), 1);
+}
+
+#[test]
+fn finished_machines_are_evicted_on_demand() {
+    let mut tickets = evicting(tickets());
+    _ = tickets.insert(
+        1,
+        TicketMachine::new(Closed, Ticket::default()),
+    );
+    _ = tickets.insert(
+        2,
+        TicketMachine::new(Open, Ticket::default()),
+    );
+
+    let evicted = tickets
+        .evict_finished()
+        .into_iter()
+        .map(|(id, _)| id)
+        .collect::<Vec<_>>();
+
+    assert_eq!(evicted, [1]);
+    assert_eq!(tickets.len(), 1);
+}
+
+#[test]
+fn machines_are_saved_and_restored() {
+    let mut persister = sqlite();
+    let mut saved = tickets();
+    _ = saved.handle_event(1, Comment);
+    _ = saved.handle_event(2, Close);
+    assert_eq!(saved.save(&mut persister).ok(), Some(()));
+
+    let created = Arc::new(AtomicUsize::new(0));
+    let mut restored = counting(&created);
+
+    assert_eq!(restored.restore(&persister).ok(), Some(2));
+    assert_eq!(created.load(Ordering::SeqCst), 2);
+    assert_eq!(
+        restored
+            .get(&1)
+            .map(|ticket| ticket.context().comments),
+        Some(1)
+    );
+    assert_eq!(
+        restored
+            .get(&2)
+            .map(|ticket| ticket.state().kind()),
+        Some(TicketMachineStateKind::Closed)
+    );
+}
+
+#[test]
+fn restoring_rejects_invalid_ids() {
+    let mut persister = sqlite();
+    assert_eq!(
+        persister
+            .save("one", &Open.into(), &Ticket::default())
+            .ok(),
+        Some(())
+    );
+
+    let mut restored = tickets();
+
+    assert!(matches!(
+        restored.restore(&persister),
+        Err(RestoreError::Id(_))
+    ));
+    assert!(restored.is_empty());
+}
@@ -0,0 +1,151 @@
+//! Guards returning the reason events are rejected with.
+
+#![allow(clippy::missing_trait_methods)]
+#![allow(clippy::tests_outside_test_module)]
+
+use machine_factory::event_driven_state_machine;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Refusal {
+    Emergency,
+    NotReady,
+}
+
+#[derive(Debug, Default, Clone)]
+struct Crossing {
+    emergency: bool,
+}
+
+#[derive(Debug, Default, Clone)]
+struct Closed;
+
+impl CrossingState for Closed {
+    fn should_exit(
+        &self,
+        context: &Crossing,
+        _event: &CrossingEvent,
+    ) -> Result<(), Refusal> {
+        if context.emergency {
+            return Err(Refusal::Emergency);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+struct Open;
+
+impl CrossingState for Open {
+    fn should_exit(
+        &self,
+        _context: &Crossing,
+        event: &CrossingEvent,
+    ) -> Result<(), Refusal> {
+        match *event {
+            CrossingEvent::Lower(_) => Ok(()),
+            CrossingEvent::Raise(_) => {
+                Err(Refusal::NotReady)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Lower;
+impl CrossingEventTrait for Lower {}
+
+#[derive(Debug, Clone)]
+struct Raise;
+impl CrossingEventTrait for Raise {}
+
+event_driven_state_machine!(
+    CrossingMachine {
+        context: Crossing,
+        state_enum: #[derive(Debug, Clone)] CrossingMachineState,
+        state_trait: trait CrossingState {
+            fn should_exit(&self, _context: &Crossing, _event: &CrossingEvent) -> Result<(), Refusal> {
+                Ok(())
+            }
+        },
+        event_enum: CrossingEvent,
+        event_trait: trait CrossingEventTrait {},
+        states: [
+            Closed {
+                Raise -> Open,
+            },
+            Open {
+                Lower -> Closed,
+            },
+            _ {
+                state
+            },
+        ],
+        events: [],
+    }
+);
+
+#[test]
+fn rejections_carry_the_reason() {
+    let mut crossing = CrossingMachine::new(
+        Closed,
+        Crossing { emergency: true },
+    );
+
+    assert_eq!(
+        crossing.handle_event_outcome(Raise),
+        CrossingMachineOutcome::Rejected {
+            state: CrossingMachineStateKind::Closed,
+            reason: Refusal::Emergency,
+        }
+    );
+    assert!(matches!(
+        crossing.state(),
+        CrossingMachineState::Closed(_)
+    ));
+}
+
+#[test]
+fn reasons_depend_on_the_event() {
+    let mut crossing =
+        CrossingMachine::new(Open, Crossing::default());
+
+    assert_eq!(
+        crossing.handle_event_outcome(Raise),
+        CrossingMachineOutcome::Rejected {
+            state: CrossingMachineStateKind::Open,
+            reason: Refusal::NotReady,
+        }
+    );
+    assert_eq!(
+        crossing.handle_event_outcome(Lower),
+        CrossingMachineOutcome::Transitioned {
+            from: CrossingMachineStateKind::Open,
+            to: CrossingMachineStateKind::Closed,
+        }
+    );
+}
+
+#[test]
+fn step_returns_the_reason() {
+    let (_, _, outcome) = CrossingMachine::step(
+        Closed.into(),
+        Crossing { emergency: true },
+        Raise,
+    );
+
+    assert_eq!(
+        outcome,
+        CrossingMachineOutcome::Rejected {
+            state: CrossingMachineStateKind::Closed,
+            reason: Refusal::Emergency,
+        }
+    );
+}
+
+#[test]
+fn other_return_types_are_rejected() {
+    trybuild::TestCases::new().compile_fail(
+        "tests/ui/should_exit_return_type.rs",
+    );
+}
@@ -0,0 +1,123 @@
+//! Machines with requests, i.e. events with a reply.
+
+#![allow(clippy::missing_trait_methods)]
+#![allow(clippy::tests_outside_test_module)]
+
+use machine_factory::event_driven_state_machine;
+
+#[derive(Debug, Default, Clone)]
+struct Wallet {
+    coins: u32,
+}
+
+#[derive(Debug, Default, Clone)]
+struct Locked;
+impl WalletState for Locked {}
+
+#[derive(Debug, Default, Clone)]
+struct Unlocked;
+impl WalletState for Unlocked {}
+
+#[derive(Debug, Clone)]
+struct Deposit;
+impl WalletEventTrait for Deposit {}
+
+#[derive(Debug, Clone)]
+struct Unlock;
+impl WalletEventTrait for Unlock {}
+
+// Only answered while unlocked
+#[derive(Debug, Clone)]
+struct Balance;
+impl WalletEventTrait for Balance {}
+
+// Answered in any state by the unhandled-event block
+#[derive(Debug, Clone)]
+struct Status;
+impl WalletEventTrait for Status {}
+
+event_driven_state_machine!(
+    WalletMachine {
+        context: Wallet,
+        state_enum: #[derive(Debug, Clone)] WalletMachineState,
+        state_trait: trait WalletState {},
+        event_enum: WalletEvent,
+        event_trait: trait WalletEventTrait {},
+        states: [
+            Locked {
+                Unlock -> Unlocked,
+            },
+            Unlocked {
+                Balance {
+                    *reply = Some(context.coins);
+                    state
+                },
+                Deposit {
+                    context.coins = context.coins.saturating_add(1);
+                    state
+                },
+            },
+            _ {
+                if let WalletEvent::Status(_) = event {
+                    *reply = Some(WalletMachineReply::Status(state.name().to_owned()));
+                }
+                state
+            },
+        ],
+        events: [Balance -> u32, Status -> String],
+    }
+);
+
+fn unlocked() -> WalletMachine {
+    WalletMachine::new(Unlocked, Wallet::default())
+}
+
+#[test]
+fn transition_blocks_reply_to_requests() {
+    let mut wallet = unlocked();
+    _ = wallet.handle_event(Deposit).handle_event(Deposit);
+
+    assert_eq!(wallet.handle_request(Balance), Some(2));
+}
+
+#[test]
+fn the_unhandled_event_block_replies_to_requests() {
+    let mut wallet =
+        WalletMachine::new(Locked, Wallet::default());
+
+    assert_eq!(
+        wallet.handle_request(Status),
+        Some("Locked".to_owned())
+    );
+}
+
+#[test]
+fn requests_without_a_reply_return_none() {
+    let mut wallet =
+        WalletMachine::new(Locked, Wallet::default());
+
+    assert_eq!(wallet.handle_request(Balance), None);
+}
+
+#[test]
+fn requests_are_handled_like_events() {
+    let mut wallet = unlocked();
+
+    assert_eq!(
+        wallet.handle_event_outcome(Balance),
+        WalletMachineOutcome::Transitioned {
+            from: WalletMachineStateKind::Unlocked,
+            to: WalletMachineStateKind::Unlocked,
+        }
+    );
+    assert_eq!(
+        format!("{:?}", WalletMachineReply::Balance(1)),
+        "Balance(1)"
+    );
+}
+
+#[test]
+fn request_blocks_reply_with_the_output_of_their_request() {
+    trybuild::TestCases::new()
+        .compile_fail("tests/ui/request_reply_type.rs");
+}
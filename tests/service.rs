@@ -0,0 +1,143 @@
+//! `async` machines served as a `tower::Service` with
+//! `service: true`.
+
+#![allow(clippy::missing_trait_methods)]
+#![allow(clippy::tests_outside_test_module)]
+
+use core::time::Duration;
+use machine_factory::event_driven_state_machine;
+use tokio::task;
+use tower::{ServiceBuilder, ServiceExt as _};
+
+#[derive(Debug, Default, Clone)]
+struct Turnstile {
+    passes: u32,
+}
+
+#[derive(Debug, Default, Clone)]
+struct Closed;
+impl TurnstileState for Closed {}
+
+#[derive(Debug, Default, Clone)]
+struct Open;
+impl TurnstileState for Open {}
+
+#[derive(Debug, Clone)]
+struct Coin;
+impl TurnstileEventTrait for Coin {}
+
+#[derive(Debug, Clone)]
+struct Push;
+impl TurnstileEventTrait for Push {}
+
+// Answered in any state by the unhandled-event block
+#[derive(Debug, Clone)]
+struct Passes;
+impl TurnstileEventTrait for Passes {}
+
+event_driven_state_machine!(
+    async TurnstileMachine {
+        context: Turnstile,
+        service: true,
+        state_enum: #[derive(Debug, Clone)] TurnstileMachineState,
+        state_trait: trait TurnstileState {},
+        event_enum: TurnstileEvent,
+        event_trait: trait TurnstileEventTrait: Send {},
+        states: [
+            Closed {
+                Coin -> Open,
+            },
+            Open {
+                Push {
+                    context.passes = context.passes.saturating_add(1);
+                    TurnstileMachineState::from(Closed)
+                },
+            },
+            _ {
+                if let TurnstileEvent::Passes(_) = event {
+                    *reply = Some(TurnstileMachineReply::Passes(context.passes));
+                }
+                state
+            },
+        ],
+        events: [Passes -> u32],
+    }
+);
+
+fn turnstile() -> TurnstileMachineService {
+    TurnstileMachine::new(Closed, Turnstile::default())
+        .into_service()
+}
+
+#[tokio::test]
+async fn events_are_answered_with_their_outcome() {
+    let turnstile = turnstile();
+
+    let outcome = turnstile.clone().oneshot(Coin).await;
+
+    assert_eq!(
+        outcome,
+        Ok(TurnstileMachineOutcome::Transitioned {
+            from: TurnstileMachineStateKind::Closed,
+            to: TurnstileMachineStateKind::Open,
+        })
+    );
+    assert_eq!(
+        turnstile.lock().await.state().kind(),
+        TurnstileMachineStateKind::Open
+    );
+}
+
+#[tokio::test]
+async fn requests_are_answered_with_their_reply() {
+    let turnstile = turnstile();
+    _ = turnstile.clone().oneshot(Coin).await;
+    _ = turnstile.clone().oneshot(Push).await;
+
+    let passes = turnstile.requests().oneshot(Passes).await;
+
+    assert_eq!(passes, Ok(Some(1)));
+}
+
+#[tokio::test]
+async fn clones_share_the_machine() {
+    let turnstile = turnstile();
+
+    let (coin, push) = tokio::join!(
+        turnstile.clone().oneshot(Coin),
+        async {
+            task::yield_now().await;
+            turnstile.clone().oneshot(Push).await
+        },
+    );
+
+    assert!(coin.is_ok() && push.is_ok());
+    assert_eq!(turnstile.lock().await.context().passes, 1);
+}
+
+#[tokio::test]
+async fn services_work_with_tower_middleware() {
+    let turnstile = turnstile();
+
+    let outcome = ServiceBuilder::new()
+        .timeout(Duration::from_secs(1))
+        .concurrency_limit(1)
+        .service(turnstile.clone())
+        .oneshot(Push)
+        .await
+        .expect("the event should be handled in time");
+
+    assert_eq!(
+        outcome,
+        TurnstileMachineOutcome::Transitioned {
+            from: TurnstileMachineStateKind::Closed,
+            to: TurnstileMachineStateKind::Closed,
+        }
+    );
+}
+
+#[test]
+fn services_require_an_async_machine() {
+    trybuild::TestCases::new()
+        .compile_fail("tests/ui/service_not_async.rs");
+}
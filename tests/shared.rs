@@ -0,0 +1,259 @@
+//! Machines with `shared: true`, handled from many threads.
+
+#![allow(clippy::missing_trait_methods)]
+#![allow(clippy::tests_outside_test_module)]
+
+use core::{iter, panic::AssertUnwindSafe};
+use machine_factory::event_driven_state_machine;
+use std::{panic, thread};
+
+#[derive(Debug, Default, Clone)]
+struct Counter {
+    count: u32,
+}
+
+#[derive(Debug, Default, Clone)]
+struct Counting;
+impl CounterState for Counting {}
+
+#[derive(Debug, Default, Clone)]
+struct Done;
+impl CounterState for Done {}
+
+#[derive(Debug, Clone)]
+struct Add;
+
+impl CounterEventTrait for Add {
+    fn pre_transition(&mut self, context: &mut Counter) {
+        context.count = context.count.saturating_add(1);
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Finish;
+impl CounterEventTrait for Finish {}
+
+/// Panics after the transition block when set.
+#[derive(Debug, Clone)]
+struct Crash(bool);
+
+impl CounterEventTrait for Crash {
+    fn post_transition(&mut self, _context: &mut Counter) {
+        assert!(!self.0, "counter crashed");
+    }
+}
+
+event_driven_state_machine!(
+    CounterMachine {
+        context: Counter,
+        shared: true,
+        state_enum: #[derive(Debug, Clone)] CounterMachineState,
+        state_trait: trait CounterState {},
+        event_enum: CounterEvent,
+        event_trait: trait CounterEventTrait: Send {},
+        states: [
+            Counting {
+                Add -> Counting,
+                Crash -> Done,
+                Finish -> Done,
+            },
+            Done {
+                Finish -> Done,
+            },
+            _ {
+                state
+            },
+        ],
+        events: [],
+    }
+);
+
+#[derive(Debug, Default, Clone)]
+struct Idle;
+impl SafeCounterState for Idle {}
+
+#[derive(Debug, Default, Clone)]
+struct Stopped;
+impl SafeCounterState for Stopped {}
+
+#[derive(Debug, Clone)]
+struct Tick;
+
+impl SafeCounterEventTrait for Tick {
+    fn pre_transition(&mut self, context: &mut Counter) {
+        context.count = context.count.saturating_add(1);
+    }
+}
+
+/// Panics after the transition block when set.
+#[derive(Debug, Clone)]
+struct Stop(bool);
+
+impl SafeCounterEventTrait for Stop {
+    fn post_transition(&mut self, _context: &mut Counter) {
+        assert!(!self.0, "counter stopped");
+    }
+}
+
+event_driven_state_machine!(
+    SafeCounterMachine {
+        context: Counter,
+        shared: true,
+        transactional: true,
+        state_enum: #[derive(Debug, Clone)] SafeCounterMachineState,
+        state_trait: trait SafeCounterState {},
+        event_enum: SafeCounterEvent,
+        event_trait: trait SafeCounterEventTrait: Send {},
+        states: [
+            Idle {
+                Stop -> Stopped,
+                Tick -> Idle,
+            },
+            Stopped {
+                Tick -> Stopped,
+            },
+            _ {
+                state
+            },
+        ],
+        events: [],
+    }
+);
+
+fn counter() -> CounterMachineShared {
+    CounterMachine::new(Counting, Counter::default())
+        .into_shared()
+}
+
+#[test]
+fn events_are_handled_from_many_threads() {
+    let counter = counter();
+
+    let handles = iter::repeat_with(|| {
+        let counter = counter.clone();
+        thread::spawn(move || {
+            for _ in 0..25_u32 {
+                _ = counter.handle_event(Add);
+            }
+        })
+    })
+    .take(4)
+    .collect::<Vec<_>>();
+    for handle in handles {
+        handle.join().expect("the thread should not panic");
+    }
+
+    assert_eq!(
+        counter.with(|counter| counter.context().count),
+        100
+    );
+}
+
+#[test]
+fn the_state_kind_is_read_without_the_lock() {
+    let counter = counter();
+
+    assert_eq!(
+        counter.state_kind(),
+        CounterMachineStateKind::Counting
+    );
+
+    assert_eq!(
+        counter.handle_event(Finish),
+        CounterMachineOutcome::Transitioned {
+            from: CounterMachineStateKind::Counting,
+            to: CounterMachineStateKind::Done,
+        }
+    );
+    assert_eq!(
+        counter.state_kind(),
+        CounterMachineStateKind::Done
+    );
+}
+
+#[test]
+fn subscribers_see_changes_of_state_kind() {
+    let counter = counter();
+    let changes = counter.subscribe();
+
+    _ = counter.handle_event(Add);
+    _ = counter.handle_event(Finish);
+    _ = counter.handle_event(Add);
+
+    assert_eq!(
+        changes.try_iter().collect::<Vec<_>>(),
+        [CounterMachineStateKind::Done]
+    );
+}
+
+#[test]
+fn the_machine_is_unwrapped_from_its_last_handle() {
+    let counter = counter();
+    let clone = counter.clone();
+
+    let counter = counter.try_into_inner();
+    assert!(counter.is_err());
+
+    drop(counter);
+    assert!(
+        clone
+            .try_into_inner()
+            .is_ok_and(
+                |counter| counter.context().count == 0
+            )
+    );
+}
+
+#[test]
+fn panics_poison_machines_that_are_not_transactional() {
+    let counter = counter();
+
+    let crashed =
+        panic::catch_unwind(AssertUnwindSafe(|| {
+            _ = counter.handle_event(Crash(true));
+        }));
+    let used =
+        panic::catch_unwind(AssertUnwindSafe(|| {
+            _ = counter
+                .with(|counter| counter.context().count);
+        }));
+
+    assert!(crashed.is_err());
+    assert!(used.is_err());
+}
+
+#[test]
+fn transactional_machines_recover_from_panics() {
+    let counter =
+        SafeCounterMachine::new(Idle, Counter::default())
+            .into_shared();
+    _ = counter.handle_event(Tick);
+
+    let stopped =
+        panic::catch_unwind(AssertUnwindSafe(|| {
+            _ = counter.handle_event(Stop(true));
+        }));
+
+    assert!(stopped.is_err());
+    assert_eq!(
+        counter.state_kind(),
+        SafeCounterMachineStateKind::Idle
+    );
+    assert_eq!(
+        counter.handle_event(Tick),
+        SafeCounterMachineOutcome::Transitioned {
+            from: SafeCounterMachineStateKind::Idle,
+            to: SafeCounterMachineStateKind::Idle,
+        }
+    );
+    assert_eq!(
+        counter.with(|counter| counter.context().count),
+        2
+    );
+}
+
+#[test]
+fn shared_requires_a_machine_that_is_not_async() {
+    trybuild::TestCases::new()
+        .compile_fail("tests/ui/shared_async.rs");
+}
@@ -0,0 +1,140 @@
+//! The `step` function generated for every machine.
+
+#![allow(clippy::missing_trait_methods)]
+#![allow(clippy::tests_outside_test_module)]
+
+use machine_factory::event_driven_state_machine;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct Turnstile {
+    coins: u32,
+    refused: u32,
+}
+
+#[derive(Debug, Default, Clone)]
+struct Locked;
+
+impl TurnstileState for Locked {
+    fn on_rejected(
+        &mut self,
+        context: &mut Turnstile,
+        _event: &TurnstileEvent,
+    ) {
+        context.refused = context.refused.saturating_add(1);
+    }
+
+    fn should_exit(
+        &self,
+        _context: &Turnstile,
+        event: &TurnstileEvent,
+    ) -> bool {
+        matches!(event, TurnstileEvent::Coin(_))
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+struct Unlocked;
+impl TurnstileState for Unlocked {}
+
+#[derive(Debug, Clone)]
+struct Coin;
+
+impl TurnstileEventTrait for Coin {
+    fn pre_transition(&mut self, context: &mut Turnstile) {
+        context.coins = context.coins.saturating_add(1);
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Push;
+impl TurnstileEventTrait for Push {}
+
+event_driven_state_machine!(
+    TurnstileMachine {
+        context: Turnstile,
+        state_enum: #[derive(Debug, Clone)] TurnstileMachineState,
+        state_trait: trait TurnstileState {},
+        event_enum: #[derive(Clone)] TurnstileEvent,
+        event_trait: trait TurnstileEventTrait {},
+        states: [
+            Locked {
+                Coin -> Unlocked,
+            },
+            Unlocked {
+                Push -> Locked,
+            },
+            _ {
+                state
+            },
+        ],
+        events: [],
+    }
+);
+
+#[test]
+fn step_runs_the_hooks_over_values() {
+    let (state, context, outcome) = TurnstileMachine::step(
+        Locked.into(),
+        Turnstile::default(),
+        Coin,
+    );
+
+    assert!(matches!(
+        state,
+        TurnstileMachineState::Unlocked(_)
+    ));
+    assert_eq!(context, Turnstile { coins: 1, refused: 0 });
+    assert_eq!(
+        outcome,
+        TurnstileMachineOutcome::Transitioned {
+            from: TurnstileMachineStateKind::Locked,
+            to: TurnstileMachineStateKind::Unlocked,
+        }
+    );
+}
+
+#[test]
+fn step_returns_rejections() {
+    let (state, context, outcome) = TurnstileMachine::step(
+        Locked.into(),
+        Turnstile::default(),
+        Push,
+    );
+
+    assert!(matches!(
+        state,
+        TurnstileMachineState::Locked(_)
+    ));
+    assert_eq!(context, Turnstile { coins: 0, refused: 1 });
+    assert_eq!(
+        outcome,
+        TurnstileMachineOutcome::Rejected {
+            state: TurnstileMachineStateKind::Locked,
+        }
+    );
+}
+
+#[test]
+fn step_matches_handle_event() {
+    let mut turnstile =
+        TurnstileMachine::new(Locked, Turnstile::default());
+    let mut state = TurnstileMachineState::from(Locked);
+    let mut context = Turnstile::default();
+
+    for event in [
+        TurnstileEvent::from(Push),
+        Coin.into(),
+        Push.into(),
+        Coin.into(),
+    ] {
+        let expected =
+            turnstile.handle_event_outcome(event.clone());
+        let outcome;
+        (state, context, outcome) =
+            TurnstileMachine::step(state, context, event);
+
+        assert_eq!(outcome, expected);
+        assert_eq!(state.kind(), turnstile.state().kind());
+        assert_eq!(&context, turnstile.context());
+    }
+}
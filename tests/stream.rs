@@ -0,0 +1,154 @@
+//! Machines with `stream: true`.
+
+#![allow(clippy::missing_trait_methods)]
+#![allow(clippy::tests_outside_test_module)]
+
+use futures::{StreamExt as _, executor::block_on, stream};
+use machine_factory::event_driven_state_machine;
+
+#[derive(Debug, Default, Clone)]
+struct Counter {
+    ticks: u32,
+}
+
+#[derive(Debug, Default, Clone)]
+struct Even;
+impl CounterState for Even {}
+
+#[derive(Debug, Default, Clone)]
+struct Odd;
+impl CounterState for Odd {}
+
+#[derive(Debug, Clone)]
+struct Tick;
+
+impl CounterEventTrait for Tick {
+    fn pre_transition(&mut self, context: &mut Counter) {
+        context.ticks = context.ticks.saturating_add(1);
+    }
+}
+
+event_driven_state_machine!(
+    CounterMachine {
+        context: Counter,
+        stream: true,
+        state_enum: #[derive(Debug, Clone)] CounterMachineState,
+        state_trait: trait CounterState {},
+        event_enum: CounterEvent,
+        event_trait: trait CounterEventTrait {},
+        states: [
+            Even {
+                Tick -> Odd,
+            },
+            Odd {
+                Tick -> Even,
+            },
+        ],
+        events: [],
+    }
+);
+
+#[derive(Debug, Default, Clone)]
+struct Idle;
+impl AsyncCounterState for Idle {}
+
+#[derive(Debug, Clone)]
+struct Ping;
+impl AsyncCounterEventTrait for Ping {}
+
+event_driven_state_machine!(
+    async AsyncCounterMachine {
+        context: Counter,
+        stream: true,
+        state_enum: #[derive(Debug, Clone)] AsyncCounterMachineState,
+        state_trait: trait AsyncCounterState {},
+        event_enum: AsyncCounterEvent,
+        event_trait: trait AsyncCounterEventTrait: Send {},
+        states: [
+            Idle {
+                Ping -> Idle,
+            },
+        ],
+        events: [],
+    }
+);
+
+const fn never(_: &CounterMachineState) -> bool {
+    false
+}
+
+#[test]
+fn outcomes_end_with_the_events() {
+    let mut counter =
+        CounterMachine::new(Even, Counter::default());
+
+    let outcomes = block_on(
+        counter
+            .run_stream(
+                stream::iter([Tick, Tick, Tick]),
+                never,
+            )
+            .collect::<Vec<_>>(),
+    );
+
+    assert_eq!(outcomes.len(), 3);
+    assert_eq!(counter.context().ticks, 3);
+    assert!(matches!(
+        counter.state(),
+        CounterMachineState::Odd(_)
+    ));
+}
+
+#[test]
+fn until_stops_before_pulling_the_next_event() {
+    let mut counter =
+        CounterMachine::new(Even, Counter::default());
+    let mut events = stream::iter([Tick, Tick, Tick]);
+
+    let outcomes = block_on(
+        counter
+            .run_stream(events.by_ref(), |state| {
+                state.kind() == CounterMachineStateKind::Odd
+            })
+            .collect::<Vec<_>>(),
+    );
+
+    assert_eq!(
+        outcomes,
+        [CounterMachineOutcome::Transitioned {
+            from: CounterMachineStateKind::Even,
+            to: CounterMachineStateKind::Odd,
+        }]
+    );
+    assert_eq!(block_on(events.count()), 2);
+}
+
+#[test]
+fn until_is_checked_before_the_first_event() {
+    let mut counter =
+        CounterMachine::new(Odd, Counter::default());
+
+    let outcomes = block_on(
+        counter
+            .run_stream(stream::repeat(Tick), |state| {
+                state.kind() == CounterMachineStateKind::Odd
+            })
+            .collect::<Vec<_>>(),
+    );
+
+    assert!(outcomes.is_empty());
+    assert_eq!(counter.context().ticks, 0);
+}
+
+#[tokio::test]
+async fn async_machines_handle_streams() {
+    let mut counter =
+        AsyncCounterMachine::new(Idle, Counter::default());
+
+    let outcomes = counter
+        .run_stream(stream::iter([Ping, Ping]), |_| false)
+        .collect::<Vec<_>>()
+        .await;
+
+    assert_eq!(outcomes.len(), 2);
+}
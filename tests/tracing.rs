@@ -0,0 +1,161 @@
+//! Machines with `tracing: true`.
+
+#![allow(clippy::missing_trait_methods)]
+#![allow(clippy::tests_outside_test_module)]
+
+extern crate alloc;
+
+use alloc::sync::Arc;
+use machine_factory::event_driven_state_machine;
+use std::{
+    io,
+    sync::{Mutex, PoisonError},
+};
+use tracing::{Level, subscriber};
+
+#[derive(Debug, Default, Clone)]
+struct Fan;
+
+#[derive(Debug, Default, Clone)]
+struct Off;
+
+impl FanState for Off {
+    fn should_exit(
+        &self,
+        _context: &Fan,
+        event: &FanEvent,
+    ) -> bool {
+        matches!(event, FanEvent::Spin(_))
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+struct On;
+impl FanState for On {}
+
+#[derive(Debug, Clone)]
+struct Spin;
+impl FanEventTrait for Spin {}
+
+#[derive(Debug, Clone)]
+struct Halt;
+impl FanEventTrait for Halt {}
+
+event_driven_state_machine!(
+    FanMachine {
+        context: Fan,
+        tracing: true,
+        state_enum: #[derive(Debug, Clone)] FanMachineState,
+        state_trait: trait FanState {},
+        event_enum: FanEvent,
+        event_trait: trait FanEventTrait {},
+        states: [
+            Off {
+                Spin -> On,
+            },
+            On {
+                Halt -> Off,
+            },
+            _ {
+                state
+            },
+        ],
+        events: [],
+    }
+);
+
+/// The formatted output of the subscriber.
+#[derive(Clone, Default)]
+struct Output(Arc<Mutex<Vec<u8>>>);
+
+impl Output {
+    fn lines(&self) -> Vec<String> {
+        let bytes = self
+            .0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        String::from_utf8_lossy(&bytes)
+            .lines()
+            .map(str::to_owned)
+            .collect()
+    }
+}
+
+impl io::Write for Output {
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .extend_from_slice(buf);
+
+        Ok(buf.len())
+    }
+}
+
+/// Runs `f` with a subscriber, and returns its output.
+fn traced(f: impl FnOnce()) -> Vec<String> {
+    let output = Output::default();
+    let writer = output.clone();
+
+    let fmt_subscriber = tracing_subscriber::fmt()
+        .with_max_level(Level::TRACE)
+        .with_ansi(false)
+        .without_time()
+        .with_writer(move || writer.clone())
+        .finish();
+
+    subscriber::with_default(fmt_subscriber, f);
+
+    output.lines()
+}
+
+#[test]
+fn events_are_handled_in_a_span() {
+    let lines = traced(|| {
+        _ = FanMachine::new(Off, Fan).handle_event(Spin);
+    });
+
+    assert!(!lines.is_empty());
+    assert!(lines.iter().all(|line| line.contains(
+        r#"handle_event{machine="FanMachine" state=Off event=Spin}"#
+    )));
+}
+
+#[test]
+fn hooks_and_transitions_are_traced() {
+    let lines = traced(|| {
+        _ = FanMachine::new(Off, Fan).handle_event(Spin);
+    });
+
+    let hooks = lines
+        .iter()
+        .filter(|line| line.contains("calling hook"))
+        .count();
+
+    assert!(hooks > 0);
+    assert!(lines.last().is_some_and(|line| {
+        line.contains("transitioned from=Off to=On")
+    }));
+}
+
+#[test]
+fn rejections_are_traced() {
+    let lines = traced(|| {
+        _ = FanMachine::new(Off, Fan).handle_event(Halt);
+    });
+
+    assert!(lines.iter().any(|line| {
+        line.contains("DEBUG")
+            && line.contains("event rejected")
+    }));
+    assert!(
+        !lines
+            .iter()
+            .any(|line| line.contains("transitioned"))
+    );
+}
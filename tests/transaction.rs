@@ -0,0 +1,130 @@
+//! Machines with `transactional: true`.
+
+#![allow(clippy::missing_trait_methods)]
+#![allow(clippy::tests_outside_test_module)]
+
+use core::panic::AssertUnwindSafe;
+use machine_factory::event_driven_state_machine;
+use std::panic;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct Transfer {
+    credited: u32,
+    debited: u32,
+}
+
+#[derive(Debug, Default, Clone)]
+struct Pending;
+
+impl TransferState for Pending {
+    fn on_exit(&mut self, context: &mut Transfer) {
+        context.debited = context.debited.saturating_add(1);
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+struct Settled;
+
+impl TransferState for Settled {
+    fn on_enter(&mut self, context: &mut Transfer) {
+        context.credited =
+            context.credited.saturating_add(1);
+    }
+}
+
+/// Panics after the transition block when set.
+#[derive(Debug, Clone)]
+struct Settle(bool);
+
+impl TransferEventTrait for Settle {
+    fn post_transition(&mut self, _context: &mut Transfer) {
+        assert!(!self.0, "ledger unavailable");
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Reopen;
+impl TransferEventTrait for Reopen {}
+
+event_driven_state_machine!(
+    TransferMachine {
+        context: Transfer,
+        transactional: true,
+        state_enum: #[derive(Debug, Clone)] TransferMachineState,
+        state_trait: trait TransferState {},
+        event_enum: TransferEvent,
+        event_trait: trait TransferEventTrait {},
+        states: [
+            Pending {
+                Settle -> Settled,
+            },
+            Settled {
+                Reopen -> Pending,
+            },
+            _ {
+                state
+            },
+        ],
+        events: [],
+    }
+);
+
+#[test]
+fn panics_restore_the_state_and_context() {
+    let mut transfer =
+        TransferMachine::new(Pending, Transfer::default());
+
+    let result =
+        panic::catch_unwind(AssertUnwindSafe(|| {
+            _ = transfer.handle_event(Settle(true));
+        }));
+
+    assert!(result.is_err());
+    assert!(matches!(
+        transfer.state(),
+        TransferMachineState::Pending(_)
+    ));
+    assert_eq!(transfer.context(), &Transfer::default());
+}
+
+#[test]
+fn machines_keep_working_after_a_panic() {
+    let mut transfer =
+        TransferMachine::new(Pending, Transfer::default());
+
+    let result =
+        panic::catch_unwind(AssertUnwindSafe(|| {
+            _ = transfer.handle_event(Settle(true));
+        }));
+    assert!(result.is_err());
+
+    assert_eq!(
+        transfer.handle_event_outcome(Settle(false)),
+        TransferMachineOutcome::Transitioned {
+            from: TransferMachineStateKind::Pending,
+            to: TransferMachineStateKind::Settled,
+        }
+    );
+    assert_eq!(
+        transfer.context(),
+        &Transfer { credited: 1, debited: 1 }
+    );
+}
+
+#[test]
+fn transitions_without_a_panic_are_kept() {
+    let mut transfer =
+        TransferMachine::new(Pending, Transfer::default());
+    _ = transfer
+        .handle_event(Settle(false))
+        .handle_event(Reopen);
+
+    assert!(matches!(
+        transfer.state(),
+        TransferMachineState::Pending(_)
+    ));
+    assert_eq!(
+        transfer.context(),
+        &Transfer { credited: 1, debited: 1 }
+    );
+}
@@ -0,0 +1,35 @@
+use machine_factory::event_driven_state_machine;
+use machine_factory_runtime::InMemoryEventStore;
+
+#[derive(Clone)]
+struct Lamp;
+
+#[derive(Clone, Default)]
+struct Off;
+impl LampState for Off {}
+
+#[derive(Clone)]
+struct Switch;
+impl LampEvent for Switch {}
+
+type Store = InMemoryEventStore<LampMachineEvent, LampMachineState, Lamp>;
+
+event_driven_state_machine!(
+    LampMachine {
+        context: Lamp,
+        history: 4,
+        event_store: Store,
+        state_enum: #[derive(Clone)] LampMachineState,
+        state_trait: trait LampState {},
+        event_enum: #[derive(Clone)] LampMachineEvent,
+        event_trait: trait LampEvent {},
+        states: [
+            Off {
+                Switch -> Off,
+            },
+        ],
+        events: [],
+    }
+);
+
+fn main() {}
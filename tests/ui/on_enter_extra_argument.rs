@@ -0,0 +1,30 @@
+use machine_factory::event_driven_state_machine;
+
+struct Lamp;
+
+#[derive(Default)]
+struct Off;
+impl LampState for Off {}
+
+struct Switch;
+impl LampEvent for Switch {}
+
+event_driven_state_machine!(
+    LampMachine {
+        context: Lamp,
+        state_enum: LampMachineState,
+        state_trait: trait LampState {
+            fn on_enter(&mut self, context: &mut Lamp, brightness: &u8) {}
+        },
+        event_enum: LampMachineEvent,
+        event_trait: trait LampEvent {},
+        states: [
+            Off {
+                Switch -> Off,
+            },
+        ],
+        events: [],
+    }
+);
+
+fn main() {}
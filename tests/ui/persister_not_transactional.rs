@@ -0,0 +1,30 @@
+use machine_factory::event_driven_state_machine;
+
+#[derive(Clone)]
+struct Lamp;
+
+#[derive(Clone, Default)]
+struct Off;
+impl LampState for Off {}
+
+struct Switch;
+impl LampEvent for Switch {}
+
+event_driven_state_machine!(
+    LampMachine {
+        context: Lamp,
+        persister: machine_factory_runtime::SqlitePersister<LampMachineState, Lamp>,
+        state_enum: #[derive(Clone)] LampMachineState,
+        state_trait: trait LampState {},
+        event_enum: LampMachineEvent,
+        event_trait: trait LampEvent {},
+        states: [
+            Off {
+                Switch -> Off,
+            },
+        ],
+        events: [],
+    }
+);
+
+fn main() {}
@@ -0,0 +1,35 @@
+use machine_factory::event_driven_state_machine;
+
+struct Wallet;
+
+#[derive(Default)]
+struct Open;
+impl WalletState for Open {}
+
+struct Balance;
+impl WalletEvent for Balance {}
+
+struct Status;
+impl WalletEvent for Status {}
+
+event_driven_state_machine!(
+    WalletMachine {
+        context: Wallet,
+        state_enum: WalletMachineState,
+        state_trait: trait WalletState {},
+        event_enum: WalletMachineEvent,
+        event_trait: trait WalletEvent {},
+        states: [
+            Open {
+                Balance {
+                    *reply = Some(WalletMachineReply::Status(String::new()));
+                    state
+                },
+                Status -> Open,
+            },
+        ],
+        events: [Balance -> u32, Status -> String],
+    }
+);
+
+fn main() {}
@@ -0,0 +1,29 @@
+use machine_factory::event_driven_state_machine;
+
+struct Lamp;
+
+#[derive(Default)]
+struct Off;
+impl LampState for Off {}
+
+struct Switch;
+impl LampEvent for Switch {}
+
+event_driven_state_machine!(
+    LampMachine {
+        context: Lamp,
+        service: true,
+        state_enum: LampMachineState,
+        state_trait: trait LampState {},
+        event_enum: LampMachineEvent,
+        event_trait: trait LampEvent {},
+        states: [
+            Off {
+                Switch -> Off,
+            },
+        ],
+        events: [],
+    }
+);
+
+fn main() {}
@@ -0,0 +1,32 @@
+use machine_factory::event_driven_state_machine;
+
+struct Lamp;
+
+#[derive(Default)]
+struct Off;
+impl LampState for Off {}
+
+struct Switch;
+impl LampEvent for Switch {}
+
+event_driven_state_machine!(
+    LampMachine {
+        context: Lamp,
+        state_enum: LampMachineState,
+        state_trait: trait LampState {
+            fn should_exit(&self, context: &Lamp, event: &LampMachineEvent) -> Option<u8> {
+                None
+            }
+        },
+        event_enum: LampMachineEvent,
+        event_trait: trait LampEvent {},
+        states: [
+            Off {
+                Switch -> Off,
+            },
+        ],
+        events: [],
+    }
+);
+
+fn main() {}
@@ -0,0 +1,35 @@
+use machine_factory::event_driven_state_machine;
+
+struct Lamp;
+
+#[derive(Default)]
+struct Off;
+
+impl LampState for Off {
+    fn name(&self) -> String {
+        "custom off".to_owned()
+    }
+}
+
+struct Switch;
+impl LampEvent for Switch {}
+
+event_driven_state_machine!(
+    LampMachine {
+        context: Lamp,
+        state_enum: LampMachineState,
+        state_trait: trait LampState {
+            fn name(&self) -> String;
+        },
+        event_enum: LampMachineEvent,
+        event_trait: trait LampEvent {},
+        states: [
+            Off {
+                Switch -> Off,
+            },
+        ],
+        events: [],
+    }
+);
+
+fn main() {}